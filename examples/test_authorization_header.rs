@@ -9,7 +9,7 @@ extern crate oauth_client as oauth;
 extern crate rand;
 extern crate reqwest;
 
-use oauth::Token;
+use oauth::{AccessToken, ConsumerToken};
 use rand::{distributions::Alphanumeric, Rng};
 use reqwest::header::{HeaderMap, AUTHORIZATION};
 use reqwest::Client;
@@ -19,28 +19,26 @@ use std::iter;
 use std::str;
 
 mod api {
-    pub const REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
-    pub const ACCESS_TOKEN: &'static str = "http://oauthbin.com/v1/access-token";
-    pub const ECHO: &'static str = "http://oauthbin.com/v1/echo";
+    pub const REQUEST_TOKEN: &str = "http://oauthbin.com/v1/request-token";
+    pub const ACCESS_TOKEN: &str = "http://oauthbin.com/v1/access-token";
+    pub const ECHO: &str = "http://oauthbin.com/v1/echo";
 }
 
 fn split_query<'a>(query: &'a str) -> HashMap<Cow<'a, str>, Cow<'a, str>> {
     let mut param = HashMap::new();
     for q in query.split('&') {
-        let mut s = q.splitn(2, '=');
-        let k = s.next().unwrap();
-        let v = s.next().unwrap();
+        let (k, v) = q.split_once('=').unwrap();
         let _ = param.insert(k.into(), v.into());
     }
     param
 }
 
-async fn get_request_token(consumer: &Token<'_>) -> Token<'static> {
-    let (header, _body) =
-        oauth::authorization_header("GET", api::REQUEST_TOKEN, consumer, None, None);
+async fn get_request_token(consumer: &ConsumerToken<'_>) -> AccessToken<'static> {
+    let header =
+        oauth::authorization_header("GET", api::REQUEST_TOKEN, consumer, None, None).unwrap();
     let handle = Client::new();
     let mut headers = HeaderMap::new();
-    headers.insert(AUTHORIZATION, header.parse().unwrap());
+    headers.insert(AUTHORIZATION, header.header().parse().unwrap());
     let response = handle
         .get(api::REQUEST_TOKEN)
         .headers(headers)
@@ -50,18 +48,22 @@ async fn get_request_token(consumer: &Token<'_>) -> Token<'static> {
     let resp = response.text().await.unwrap();
     println!("get_request_token response: {:?}", resp);
     let param = split_query(resp.as_ref());
-    Token::new(
+    AccessToken::new(
         param.get("oauth_token").unwrap().to_string(),
         param.get("oauth_token_secret").unwrap().to_string(),
     )
 }
 
-async fn get_access_token(consumer: &Token<'_>, request: &Token<'_>) -> Token<'static> {
-    let (header, _body) =
-        oauth::authorization_header("GET", api::ACCESS_TOKEN, consumer, Some(request), None);
+async fn get_access_token(
+    consumer: &ConsumerToken<'_>,
+    request: &AccessToken<'_>,
+) -> AccessToken<'static> {
+    let header =
+        oauth::authorization_header("GET", api::ACCESS_TOKEN, consumer, Some(request), None)
+            .unwrap();
     let handle = Client::new();
     let mut headers = HeaderMap::new();
-    headers.insert(AUTHORIZATION, header.parse().unwrap());
+    headers.insert(AUTHORIZATION, header.header().parse().unwrap());
     let response = handle
         .get(api::ACCESS_TOKEN)
         .headers(headers)
@@ -71,13 +73,13 @@ async fn get_access_token(consumer: &Token<'_>, request: &Token<'_>) -> Token<'s
     let resp = response.text().await.unwrap();
     println!("get_access_token response: {:?}", resp);
     let param = split_query(resp.as_ref());
-    Token::new(
+    AccessToken::new(
         param.get("oauth_token").unwrap().to_string(),
         param.get("oauth_token_secret").unwrap().to_string(),
     )
 }
 
-async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
+async fn echo(consumer: &ConsumerToken<'_>, access: &AccessToken<'_>) {
     let mut rng = rand::thread_rng();
     let mut req_param = HashMap::new();
     let _ = req_param.insert("testFOO".into(), "testFoo".into());
@@ -95,16 +97,18 @@ async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
                 .collect(),
         );
     }
-    let (header, body) =
-        oauth::authorization_header("POST", api::ECHO, consumer, Some(access), Some(&req_param));
+    let param = oauth::ParamList::from(req_param.clone());
+    let header =
+        oauth::authorization_header("POST", api::ECHO, consumer, Some(access), Some(&param))
+            .unwrap();
 
     let mut headers = HeaderMap::new();
-    headers.insert(AUTHORIZATION, header.parse().unwrap());
+    headers.insert(AUTHORIZATION, header.header().parse().unwrap());
 
     let response = Client::new()
         .post(api::ECHO)
         .headers(headers)
-        .body(body.clone())
+        .body(header.body().to_string())
         .send()
         .await
         .unwrap();
@@ -112,12 +116,12 @@ async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
     let resp = response.text().await.unwrap();
     println!("echo response: {:?}", resp);
     let resp_body: &str = resp.as_ref();
-    assert_eq!(body, resp_body);
+    assert_eq!(header.body(), resp_body);
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let consumer = Token::new("key", "secret");
+    let consumer = ConsumerToken::new("key", "secret");
     println!("consumer: {:?}", consumer);
 
     let request = get_request_token(&consumer).await;
@@ -5,58 +5,58 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use oauth::Token;
+use oauth::{AccessToken, ConsumerToken};
 use oauth_client as oauth;
-use rand;
 use rand::{distributions::Alphanumeric, Rng};
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::iter;
 
 mod api {
-    pub const REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
-    pub const ACCESS_TOKEN: &'static str = "http://oauthbin.com/v1/access-token";
-    pub const ECHO: &'static str = "http://oauthbin.com/v1/echo";
+    pub const REQUEST_TOKEN: &str = "http://oauthbin.com/v1/request-token";
+    pub const ACCESS_TOKEN: &str = "http://oauthbin.com/v1/access-token";
+    pub const ECHO: &str = "http://oauthbin.com/v1/echo";
 }
 
 fn split_query<'a>(query: &'a str) -> HashMap<Cow<'a, str>, Cow<'a, str>> {
     let mut param = HashMap::new();
     for q in query.split('&') {
-        let mut s = q.splitn(2, '=');
-        let k = s.next().unwrap();
-        let v = s.next().unwrap();
+        let (k, v) = q.split_once('=').unwrap();
         let _ = param.insert(k.into(), v.into());
     }
     param
 }
 
-async fn get_request_token(consumer: &Token<'_>) -> Token<'static> {
+async fn get_request_token(consumer: &ConsumerToken<'_>) -> AccessToken<'static> {
     let bytes = oauth::post(api::REQUEST_TOKEN, consumer, None, None)
         .await
         .unwrap();
     let resp = String::from_utf8(bytes).unwrap();
     println!("get_request_token response: {:?}", resp);
     let param = split_query(&resp[..]);
-    Token::new(
+    AccessToken::new(
         param.get("oauth_token").unwrap().to_string(),
         param.get("oauth_token_secret").unwrap().to_string(),
     )
 }
 
-async fn get_access_token(consumer: &Token<'_>, request: &Token<'_>) -> Token<'static> {
+async fn get_access_token(
+    consumer: &ConsumerToken<'_>,
+    request: &AccessToken<'_>,
+) -> AccessToken<'static> {
     let bytes = oauth::post(api::ACCESS_TOKEN, consumer, Some(request), None)
         .await
         .unwrap();
     let resp = String::from_utf8(bytes).unwrap();
     println!("get_access_token response: {:?}", resp);
     let param = split_query(&resp[..]);
-    Token::new(
+    AccessToken::new(
         param.get("oauth_token").unwrap().to_string(),
         param.get("oauth_token_secret").unwrap().to_string(),
     )
 }
 
-async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
+async fn echo(consumer: &ConsumerToken<'_>, access: &AccessToken<'_>) {
     let mut rng = rand::thread_rng();
     let mut req_param = HashMap::new();
     let _ = req_param.insert("testFOO".into(), "testFOO".into());
@@ -74,7 +74,8 @@ async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
                 .collect(),
         );
     }
-    let bytes = oauth::post(api::ECHO, consumer, Some(access), Some(&req_param))
+    let param = oauth::ParamList::from(req_param.clone());
+    let bytes = oauth::post(api::ECHO, consumer, Some(access), Some(&param))
         .await
         .unwrap();
     let resp = String::from_utf8(bytes).unwrap();
@@ -85,7 +86,7 @@ async fn echo(consumer: &Token<'_>, access: &Token<'_>) {
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let consumer = Token::new("key", "secret");
+    let consumer = ConsumerToken::new("key", "secret");
     println!("consumer: {:?}", consumer);
 
     let request = get_request_token(&consumer).await;
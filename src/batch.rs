@@ -0,0 +1,149 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Batches of requests sent concurrently.
+//!
+//! Requires the `batch` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use oauth_client::batch::BatchRequest;
+//!
+//! async {
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//!     let access = oauth_client::AccessToken::new("token", "secret");
+//!     let session = oauth_client::Session::new(consumer, Some(access));
+//!     let requests = (1..=500).map(|id| {
+//!         BatchRequest::get("https://api.twitter.com/1.1/statuses/show.json")
+//!             .param(oauth_client::params! { "id" => id.to_string() })
+//!     });
+//!     for body in session.batch(requests, 8).await {
+//!         println!("{} bytes", body.unwrap().len());
+//!     }
+//! };
+//! ```
+
+use crate::{ParamList, Result, Session};
+use futures_util::stream::{self, StreamExt};
+use http::Method;
+
+/// A request of a batch, signed when it is sent.
+#[derive(Clone, Debug)]
+pub struct BatchRequest {
+    method: Method,
+    uri: String,
+    param: Option<ParamList<'static>>,
+}
+
+impl BatchRequest {
+    /// Create new GET request to `uri`
+    pub fn get<U: Into<String>>(uri: U) -> BatchRequest {
+        BatchRequest::new(Method::GET, uri.into())
+    }
+
+    /// Create new form-encoded POST request to `uri`
+    pub fn post<U: Into<String>>(uri: U) -> BatchRequest {
+        BatchRequest::new(Method::POST, uri.into())
+    }
+
+    fn new(method: Method, uri: String) -> BatchRequest {
+        BatchRequest {
+            method,
+            uri,
+            param: None,
+        }
+    }
+
+    /// Set the parameters of the request
+    pub fn param(mut self, param: ParamList<'static>) -> BatchRequest {
+        self.param = Some(param);
+        self
+    }
+
+    /// Send the request with `session`
+    async fn send(self, session: &Session) -> Result<Vec<u8>> {
+        if self.method == Method::POST {
+            session.post(&self.uri, self.param.as_ref()).await
+        } else {
+            session.get(&self.uri, self.param.as_ref()).await
+        }
+    }
+}
+
+/// The responses to `requests`, in the same order, with at most
+/// `max_in_flight` of them sent at a time
+pub(crate) async fn batch<I>(
+    session: &Session,
+    requests: I,
+    max_in_flight: usize,
+) -> Vec<Result<Vec<u8>>>
+where
+    I: IntoIterator<Item = BatchRequest>,
+{
+    stream::iter(requests)
+        .map(|request| request.send(session))
+        .buffered(max_in_flight.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BatchRequest;
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{ConsumerToken, Result, Session};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Echo of the URL, answered later for the first requests, recording
+    /// the largest number of requests in flight
+    #[derive(Default)]
+    struct Slow {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Arc<Slow> {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            let _ = self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            let delay = if request.url.ends_with("=0") { 20 } else { 1 };
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            let _ = self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(Response {
+                url: request.url.clone(),
+                status: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: request.url.into_bytes(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn batch() {
+        let transport = Arc::new(Slow::default());
+        let session =
+            Session::new(ConsumerToken::new("key", "secret"), None).transport(transport.clone());
+        let requests = (0..10).map(|id| {
+            BatchRequest::get("http://example.com/show")
+                .param(crate::params! { "id" => id.to_string() })
+        });
+        let bodies = session.batch(requests, 3).await;
+        let bodies = bodies
+            .into_iter()
+            .map(|body| String::from_utf8(body.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        let expected = (0..10)
+            .map(|id| format!("http://example.com/show?id={}", id))
+            .collect::<Vec<_>>();
+        assert_eq!(bodies, expected);
+        assert_eq!(transport.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,121 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Blocking variants of the request functions.
+//!
+//! Requires the `blocking` feature, sending the requests with reqwest, or the
+//! `ureq` feature, sending them with ureq.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! const REQUEST_TOKEN: &str = "http://oauthbin.com/v1/request-token";
+//! let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//! let bytes = oauth_client::blocking::get(REQUEST_TOKEN, &consumer, None, None).unwrap();
+//! ```
+
+use crate::core::{get_request_with, post_request_with};
+use crate::transport::{BlockingTransport, SignedRequest};
+use crate::{check_status, AccessToken, Config, ConsumerToken, ParamList, Result};
+use lazy_static::*;
+
+lazy_static! {
+    static ref TRANSPORT: Box<dyn BlockingTransport> = default_transport();
+}
+
+#[cfg(feature = "blocking")]
+fn default_transport() -> Box<dyn BlockingTransport> {
+    Box::new(crate::transport::ReqwestBlockingTransport::default())
+}
+
+#[cfg(not(feature = "blocking"))]
+fn default_transport() -> Box<dyn BlockingTransport> {
+    Box::new(crate::transport::UreqTransport::default())
+}
+
+/// Send authorized GET request to the specified URL.
+/// `consumer` is a consumer token and `token` the access token, if any.
+///
+/// # Examples
+///
+/// ```no_run
+/// let REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let bytes = oauth_client::blocking::get(REQUEST_TOKEN, &consumer, None, None).unwrap();
+/// let resp = String::from_utf8(bytes).unwrap();
+/// ```
+pub fn get(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    get_with_transport(&**TRANSPORT, uri, consumer, token, other_param)
+}
+
+/// Send authorized GET request to the specified URL with `transport`.
+pub fn get_with_transport(
+    transport: &dyn BlockingTransport,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    let request = get_request_with(uri, consumer, token, other_param, &Config::default());
+    send(transport, request?)
+}
+
+/// Send authorized POST request to the specified URL.
+/// `consumer` is a consumer token and `token` the access token, if any.
+///
+/// # Examples
+///
+/// ```no_run
+/// # let request = oauth_client::AccessToken::new("key", "secret");
+/// let ACCESS_TOKEN: &'static str = "http://oauthbin.com/v1/access-token";
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let bytes = oauth_client::blocking::post(ACCESS_TOKEN, &consumer, Some(&request), None).unwrap();
+/// let resp = String::from_utf8(bytes).unwrap();
+/// ```
+pub fn post(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    post_with_transport(&**TRANSPORT, uri, consumer, token, other_param)
+}
+
+/// Send authorized POST request to the specified URL with `transport`.
+pub fn post_with_transport(
+    transport: &dyn BlockingTransport,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    let request = post_request_with(uri, consumer, token, other_param, &Config::default());
+    send(transport, request?)
+}
+
+/// Send request to the server
+fn send(transport: &dyn BlockingTransport, request: SignedRequest) -> Result<Vec<u8>> {
+    let response = transport.execute(request)?;
+    check_status(
+        &response.url,
+        response.status,
+        &response.headers,
+        &response.body,
+    )?;
+    Ok(response.body)
+}
@@ -0,0 +1,259 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Conditional GET requests, answered from a cache on `304 Not Modified`.
+
+use crate::transport::{authorization_param, Response, SignedRequest};
+use http::header::{
+    HeaderMap, HeaderValue, CACHE_CONTROL, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use http::{Method, StatusCode};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Cache of the GET responses carrying an `ETag` or a `Last-Modified`
+/// header.
+///
+/// Repeated GET requests to a cached URL are sent with `If-None-Match` and
+/// `If-Modified-Since`, and a `304 Not Modified` response is answered with
+/// the cached body. Entries are keyed by URL, without its `oauth_*`
+/// parameters, and by `oauth_consumer_key` and `oauth_token`, so that
+/// sessions of different applications or users never share their responses.
+/// Responses with `Cache-Control: no-store` are not cached, and remove the
+/// entry of their URL. Clones of a cache, and of the sessions using it, share
+/// its entries.
+///
+/// Entries are never evicted: the cache grows with the URLs requested until
+/// [`clear`](#method.clear) is called. `Vary` is not honoured, so responses
+/// varying on other request headers than `Authorization` must not be cached.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session =
+///     oauth_client::Session::new(consumer, None).cache(oauth_client::ResponseCache::new());
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ResponseCache {
+    entries: Arc<Mutex<HashMap<String, Entry>>>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+    body: Vec<u8>,
+}
+
+impl ResponseCache {
+    /// Create new empty cache
+    pub fn new() -> ResponseCache {
+        ResponseCache::default()
+    }
+
+    /// Number of cached responses
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether no response is cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all the cached responses
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// The key of `request`, `None` unless it is a GET request
+    pub(crate) fn key(&self, request: &SignedRequest) -> Option<String> {
+        if request.method != Method::GET {
+            return None;
+        }
+        let mut url = url::Url::parse(&request.url).ok()?;
+        let mut consumer = authorization_param(&request.headers, "oauth_consumer_key");
+        let mut token = authorization_param(&request.headers, "oauth_token");
+        let query = url
+            .query_pairs()
+            .filter(|(k, v)| {
+                match k.as_ref() {
+                    "oauth_consumer_key" => consumer = Some(v.to_string()),
+                    "oauth_token" => token = Some(v.to_string()),
+                    _ => {}
+                }
+                !k.starts_with("oauth_")
+            })
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect::<Vec<_>>();
+        url.set_fragment(None);
+        url.set_query(None);
+        if !query.is_empty() {
+            let _ = url.query_pairs_mut().extend_pairs(query);
+        }
+        Some(format!(
+            "{}\n{}\n{}",
+            consumer.unwrap_or_default(),
+            token.unwrap_or_default(),
+            url
+        ))
+    }
+
+    /// Add the conditional headers of the entry `key`, if any, to `headers`
+    pub(crate) fn add_conditions(&self, key: &str, headers: &mut HeaderMap) {
+        let entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(key) {
+            if let Some(etag) = &entry.etag {
+                let _ = headers.insert(IF_NONE_MATCH, etag.clone());
+            }
+            if let Some(last_modified) = &entry.last_modified {
+                let _ = headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+            }
+        }
+    }
+
+    /// Store `response` under `key`, or answer a `304 Not Modified` response
+    /// with the cached one
+    pub(crate) fn update(&self, key: String, mut response: Response) -> Response {
+        let mut entries = self.entries.lock().unwrap();
+        match response.status {
+            StatusCode::NOT_MODIFIED => {
+                if let Some(entry) = entries.get(&key) {
+                    response.status = StatusCode::OK;
+                    response.body = entry.body.clone();
+                }
+            }
+            StatusCode::OK => {
+                let etag = response.headers.get(ETAG).cloned();
+                let last_modified = response.headers.get(LAST_MODIFIED).cloned();
+                if (etag.is_some() || last_modified.is_some()) && !no_store(&response.headers) {
+                    let entry = Entry {
+                        etag,
+                        last_modified,
+                        body: response.body.clone(),
+                    };
+                    let _ = entries.insert(key, entry);
+                } else {
+                    let _ = entries.remove(&key);
+                }
+            }
+            _ => {}
+        }
+        response
+    }
+}
+
+/// Whether `headers` forbid storing the response, with `Cache-Control: no-store`
+fn no_store(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .any(|directive| directive.trim().eq_ignore_ascii_case("no-store"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ResponseCache;
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{AccessToken, ConsumerToken, Result, Session};
+    use http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
+    use std::sync::{Arc, Mutex};
+
+    /// Version `"1"` of a resource, recording the conditional headers, with
+    /// `Cache-Control: no-store` on the requests to `/private`
+    struct Versioned(Arc<Mutex<Vec<Option<String>>>>);
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Versioned {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            let condition = request.headers.get(IF_NONE_MATCH);
+            let condition = condition.map(|v| v.to_str().unwrap().to_string());
+            let not_modified = condition.as_deref() == Some("\"1\"");
+            self.0.lock().unwrap().push(condition);
+            let mut headers = http::HeaderMap::new();
+            let _ = headers.insert(ETAG, "\"1\"".parse().unwrap());
+            if request.url.contains("/private") {
+                let _ = headers.insert(CACHE_CONTROL, "private, no-store".parse().unwrap());
+            }
+            Ok(Response {
+                url: request.url,
+                status: if not_modified {
+                    http::StatusCode::NOT_MODIFIED
+                } else {
+                    http::StatusCode::OK
+                },
+                headers,
+                body: if not_modified {
+                    Vec::new()
+                } else {
+                    b"v1".to_vec()
+                },
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn not_modified() {
+        let conditions = Arc::new(Mutex::new(Vec::new()));
+        let cache = ResponseCache::new();
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .transport(Versioned(conditions.clone()))
+            .cache(cache.clone());
+        let uri = "http://example.com/a?b=1";
+        assert_eq!(session.get(uri, None).await.unwrap(), b"v1");
+        assert_eq!(session.get(uri, None).await.unwrap(), b"v1");
+        assert_eq!(cache.len(), 1);
+
+        // Another user does not see the cached response.
+        let other = session.with_token(Some(AccessToken::new("token", "secret")));
+        assert_eq!(other.get(uri, None).await.unwrap(), b"v1");
+        assert_eq!(cache.len(), 2);
+
+        // Nor does another application.
+        let other = Session::new(ConsumerToken::new("other", "secret"), None)
+            .transport(Versioned(conditions.clone()))
+            .cache(cache.clone());
+        assert_eq!(other.get(uri, None).await.unwrap(), b"v1");
+        assert_eq!(cache.len(), 3);
+
+        let conditions = conditions.lock().unwrap();
+        let expected = [None, Some("\"1\"".to_string()), None, None];
+        assert_eq!(*conditions, expected);
+    }
+
+    #[tokio::test]
+    async fn no_store() {
+        let conditions = Arc::new(Mutex::new(Vec::new()));
+        let cache = ResponseCache::new();
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .transport(Versioned(conditions.clone()))
+            .cache(cache.clone());
+        let uri = "http://example.com/private";
+        assert_eq!(session.get(uri, None).await.unwrap(), b"v1");
+        assert_eq!(session.get(uri, None).await.unwrap(), b"v1");
+        assert!(cache.is_empty());
+        assert_eq!(*conditions.lock().unwrap(), [None, None]);
+
+        // A no-store response removes the cached one.
+        let mut headers = http::HeaderMap::new();
+        let _ = headers.insert(ETAG, "\"1\"".parse().unwrap());
+        let response = |headers: &http::HeaderMap| Response {
+            url: uri.into(),
+            status: http::StatusCode::OK,
+            headers: headers.clone(),
+            body: b"v1".to_vec(),
+        };
+        let _ = cache.update("key".into(), response(&headers));
+        assert_eq!(cache.len(), 1);
+        let _ = headers.insert(CACHE_CONTROL, "no-store".parse().unwrap());
+        let _ = cache.update("key".into(), response(&headers));
+        assert!(cache.is_empty());
+    }
+}
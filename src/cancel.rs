@@ -0,0 +1,149 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Cancellation of in-flight requests.
+
+use crate::{Error, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Poll, Waker};
+
+/// Token cancelling the requests of the sessions holding it.
+///
+/// Cancelled requests fail with `Error::Cancelled`. The future of the
+/// transport is dropped, closing its connection, and no retry is attempted.
+/// Clones of a token share its state, so a supervisor may keep one and give
+/// another to a session.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).cancellation_token(token.clone());
+/// # #[cfg(feature = "client")]
+/// async move {
+///     let request = session.get("http://oauthbin.com/v1/echo", None);
+///     token.cancel();
+///     assert!(request.await.unwrap_err().is_cancelled());
+/// };
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Wakers>,
+}
+
+/// Wakers of the futures being run, by the id of their `run` call
+#[derive(Debug, Default)]
+struct Wakers {
+    wakers: HashMap<u64, Waker>,
+    next_id: u64,
+}
+
+/// Slot of a `run` call, freeing its waker when the call completes or is
+/// dropped
+struct Slot<'a> {
+    inner: &'a Inner,
+    id: u64,
+}
+
+impl Drop for Slot<'_> {
+    fn drop(&mut self) {
+        let _ = self.inner.wakers.lock().unwrap().wakers.remove(&self.id);
+    }
+}
+
+impl CancellationToken {
+    /// Create new token, not cancelled
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancel the requests in flight and the ones sent from now on
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut self.inner.wakers.lock().unwrap().wakers);
+        for waker in wakers.into_values() {
+            waker.wake();
+        }
+    }
+
+    /// Whether the token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Run `future` until it completes or the token is cancelled
+    pub(crate) async fn run<F, T>(&self, future: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let mut future = std::pin::pin!(future);
+        let slot = {
+            let mut wakers = self.inner.wakers.lock().unwrap();
+            wakers.next_id += 1;
+            Slot {
+                inner: &self.inner,
+                id: wakers.next_id,
+            }
+        };
+        std::future::poll_fn(|cx| {
+            if self.is_cancelled() {
+                return Poll::Ready(Err(Error::Cancelled));
+            }
+            if let Poll::Ready(rsp) = future.as_mut().poll(cx) {
+                return Poll::Ready(rsp);
+            }
+            let mut wakers = self.inner.wakers.lock().unwrap();
+            match wakers.wakers.get_mut(&slot.id) {
+                Some(waker) if waker.will_wake(cx.waker()) => {}
+                Some(waker) => waker.clone_from(cx.waker()),
+                None => {
+                    let _ = wakers.wakers.insert(slot.id, cx.waker().clone());
+                }
+            }
+            drop(wakers);
+            // `cancel` may have run before the waker was registered.
+            if self.is_cancelled() {
+                return Poll::Ready(Err(Error::Cancelled));
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CancellationToken;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wakers_freed() {
+        let token = CancellationToken::new();
+        for _ in 0..10 {
+            let future = async {
+                tokio::task::yield_now().await;
+                Ok(())
+            };
+            token.run(future).await.unwrap();
+        }
+        let hanging = token.run(std::future::pending::<crate::Result<()>>());
+        let _ = tokio::time::timeout(Duration::from_millis(1), hanging).await;
+        assert!(token.inner.wakers.lock().unwrap().wakers.is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Circuit breaker failing fast while a provider is down.
+
+use crate::retry::is_transient;
+use crate::{Error, Result};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Circuit breaker failing the requests fast with `Error::CircuitOpen` once
+/// a provider keeps failing.
+///
+/// After `failure_threshold` consecutive failures (timeouts, connection
+/// failures, rate limits and 5xx statuses), the circuit opens and requests
+/// fail without being sent for `open_duration`. Then a few probe requests are
+/// let through: the circuit closes again when they all succeed, and opens
+/// again on the first failure. A probe dropped before completing, by a
+/// deadline or a cancellation, frees its place for another request.
+///
+/// Time is read from the clock of the session, in seconds. Clones of a
+/// breaker, and of the sessions using it, share its state.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::CircuitBreaker;
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::new(5, Duration::from_secs(30)).half_open_probes(2);
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).circuit_breaker(breaker);
+/// ```
+#[derive(Clone, Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    half_open_probes: u32,
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Clone, Copy, Debug)]
+enum State {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        since: i64,
+    },
+    /// Probing after the circuit opened at `since`
+    HalfOpen {
+        probes: u32,
+        successes: u32,
+        since: i64,
+    },
+}
+
+/// A request let through by a breaker, whose outcome is recorded by
+/// [`record`](#method.record). Dropped without being recorded, a half-open
+/// probe frees its place.
+#[derive(Debug)]
+#[must_use]
+pub(crate) struct Probe<'a> {
+    breaker: &'a CircuitBreaker,
+    /// The opening of the circuit a half-open probe follows
+    half_open: Option<i64>,
+}
+
+impl CircuitBreaker {
+    /// Create new closed breaker opening after `failure_threshold`
+    /// consecutive failures, for `open_duration`, and sending one probe
+    /// request before closing
+    pub fn new(failure_threshold: u32, open_duration: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold,
+            open_duration,
+            half_open_probes: 1,
+            state: Arc::new(Mutex::new(State::Closed { failures: 0 })),
+        }
+    }
+
+    /// Let `probes` requests through once the open duration is over, closing
+    /// the circuit when they all succeed
+    pub fn half_open_probes(mut self, probes: u32) -> CircuitBreaker {
+        self.half_open_probes = probes.max(1);
+        self
+    }
+
+    /// Whether requests currently fail without being sent
+    pub fn is_open(&self, now: i64) -> bool {
+        match *self.state.lock().unwrap() {
+            State::Closed { .. } => false,
+            State::Open { since } => now < self.reopen_at(since),
+            State::HalfOpen { probes, .. } => probes >= self.half_open_probes,
+        }
+    }
+
+    fn reopen_at(&self, since: i64) -> i64 {
+        let secs = self.open_duration.as_secs() + u64::from(self.open_duration.subsec_nanos() > 0);
+        since.saturating_add(secs as i64)
+    }
+
+    /// Let a request through at the Unix time `now`, or fail fast
+    pub(crate) fn acquire(&self, now: i64) -> Result<Probe<'_>> {
+        let mut state = self.state.lock().unwrap();
+        if let State::Open { since } = *state {
+            let reopen_at = self.reopen_at(since);
+            if now < reopen_at {
+                let until = UNIX_EPOCH + Duration::from_secs(reopen_at.max(0) as u64);
+                return Err(Error::CircuitOpen { until: Some(until) });
+            }
+            *state = State::HalfOpen {
+                probes: 0,
+                successes: 0,
+                since,
+            };
+        }
+        let mut half_open = None;
+        if let State::HalfOpen { probes, since, .. } = &mut *state {
+            if *probes >= self.half_open_probes {
+                return Err(Error::CircuitOpen { until: None });
+            }
+            *probes += 1;
+            half_open = Some(*since);
+        }
+        Ok(Probe {
+            breaker: self,
+            half_open,
+        })
+    }
+
+    /// Record the outcome of a request let through at the Unix time `now`
+    fn record<T>(&self, rsp: &Result<T>, now: i64) {
+        let failed = match rsp {
+            Ok(_) => false,
+            Err(err) => is_transient(err),
+        };
+        let mut state = self.state.lock().unwrap();
+        *state = match (*state, failed) {
+            (State::Closed { failures }, true) if failures + 1 >= self.failure_threshold => {
+                State::Open { since: now }
+            }
+            (State::Closed { failures }, true) => State::Closed {
+                failures: failures + 1,
+            },
+            (State::Closed { .. }, false) => State::Closed { failures: 0 },
+            (State::HalfOpen { .. }, true) => State::Open { since: now },
+            (State::HalfOpen { successes, .. }, false)
+                if successes + 1 >= self.half_open_probes =>
+            {
+                State::Closed { failures: 0 }
+            }
+            (
+                State::HalfOpen {
+                    probes,
+                    successes,
+                    since,
+                },
+                false,
+            ) => State::HalfOpen {
+                probes,
+                successes: successes + 1,
+                since,
+            },
+            (state @ State::Open { .. }, _) => state,
+        };
+    }
+}
+
+impl Probe<'_> {
+    /// Record the outcome of the request at the Unix time `now`
+    pub(crate) fn record<T>(mut self, rsp: &Result<T>, now: i64) {
+        self.half_open = None;
+        self.breaker.record(rsp, now);
+    }
+}
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        if let Some(opened) = self.half_open {
+            let mut state = self.breaker.state.lock().unwrap();
+            if let State::HalfOpen { probes, since, .. } = &mut *state {
+                if *since == opened {
+                    *probes = probes.saturating_sub(1);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBreaker;
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{ConsumerToken, Error, Result, Session};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn open_and_close() {
+        let failure: Result<()> = Err(Error::Timeout("slow".into()));
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(10)).half_open_probes(2);
+        for now in 0..2 {
+            breaker.acquire(now).unwrap().record(&failure, now);
+        }
+        match breaker.acquire(5) {
+            Err(Error::CircuitOpen { until }) => assert!(until.is_some()),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        let first = breaker.acquire(11).unwrap();
+        let second = breaker.acquire(11).unwrap();
+        assert!(breaker.acquire(11).is_err());
+        first.record(&Ok(()), 11);
+        second.record(&Ok(()), 11);
+        assert!(!breaker.is_open(11));
+
+        breaker.acquire(12).unwrap().record(&failure, 12);
+        breaker.acquire(12).unwrap().record(&failure, 12);
+        breaker.acquire(22).unwrap().record(&failure, 22);
+        assert!(breaker.is_open(31));
+        assert!(!breaker.is_open(32));
+    }
+
+    #[test]
+    fn dropped_probe() {
+        let failure: Result<()> = Err(Error::Timeout("slow".into()));
+        let breaker = CircuitBreaker::new(1, Duration::from_secs(10));
+        breaker.acquire(0).unwrap().record(&failure, 0);
+        let probe = breaker.acquire(10).unwrap();
+        assert!(breaker.is_open(10));
+        drop(probe);
+        assert!(!breaker.is_open(10));
+
+        // A probe of an earlier opening does not free the place of another.
+        let stale = breaker.acquire(10).unwrap();
+        breaker.acquire(10).unwrap_err();
+        *breaker.state.lock().unwrap() = super::State::Open { since: 20 };
+        let probe = breaker.acquire(30).unwrap();
+        drop(stale);
+        assert!(breaker.is_open(30));
+        probe.record(&Ok(()), 30);
+        assert!(!breaker.is_open(30));
+    }
+
+    /// Failing the first request, never answering the second, and answering
+    /// the next ones
+    #[derive(Default)]
+    struct Hanging(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Hanging {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            match self.0.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(Error::Timeout("slow".into())),
+                1 => std::future::pending().await,
+                _ => Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::OK,
+                    headers: http::HeaderMap::new(),
+                    body: Vec::new(),
+                }),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn probe_dropped_mid_flight() {
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .clock(|| 100)
+            .transport(Hanging::default())
+            .circuit_breaker(CircuitBreaker::new(1, Duration::ZERO));
+        let uri = "http://example.com/a";
+        session.get(uri, None).await.unwrap_err();
+        let probe = session.get(uri, None);
+        assert!(tokio::time::timeout(Duration::from_millis(10), probe)
+            .await
+            .is_err());
+        session.get(uri, None).await.unwrap();
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Source of the `oauth_timestamp` values.
+
+use std::fmt;
+#[cfg(not(target_arch = "wasm32"))]
+use time::offset;
+
+/// Source of the current time used for `oauth_timestamp`.
+///
+/// Implemented for closures returning the Unix time in seconds.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).clock(|| 1318622958);
+/// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+/// assert!(header.header().contains("oauth_timestamp=\"1318622958\""));
+/// ```
+pub trait Clock: Send + Sync {
+    /// Current Unix time in seconds
+    fn now(&self) -> i64;
+}
+
+impl fmt::Debug for dyn Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Clock")
+    }
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> i64 + Send + Sync,
+{
+    fn now(&self) -> i64 {
+        self()
+    }
+}
+
+/// The system clock, used by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        (time::OffsetDateTime::now_utc().to_offset(offset!(UTC))
+            - time::OffsetDateTime::unix_epoch())
+        .whole_seconds()
+    }
+}
+
+/// `time` cannot read the clock in a browser
+#[cfg(target_arch = "wasm32")]
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        (js_sys::Date::now() / 1000.0) as i64
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Signature test vectors from the OAuth specifications and the Twitter
+//! documentation.
+//!
+//! Requires the `conformance` feature.
+//!
+//! # Examples
+//!
+//! Check the signing functions of this crate:
+//!
+//! ```
+//! oauth_client::conformance::verify().unwrap();
+//! ```
+//!
+//! Check a custom implementation:
+//!
+//! ```
+//! use oauth_client::conformance;
+//!
+//! conformance::verify_with(
+//!     |v| oauth_client::signature_base_string(v.method, v.uri, &v.param_list()),
+//!     |v, base| oauth_client::hmac_sha1_signature(base, v.consumer_secret, v.token_secret),
+//! )
+//! .unwrap();
+//! ```
+
+use crate::{hmac_sha1_signature, signature_base_string, ParamList};
+use thiserror::Error;
+
+/// A signature test vector.
+#[derive(Clone, Copy, Debug)]
+pub struct Vector {
+    /// Where the vector comes from
+    pub name: &'static str,
+    /// HTTP method of the request
+    pub method: &'static str,
+    /// Base URI of the request
+    pub uri: &'static str,
+    /// All the parameters to sign, not encoded
+    pub params: &'static [(&'static str, &'static str)],
+    /// Consumer secret
+    pub consumer_secret: &'static str,
+    /// Token secret, if any
+    pub token_secret: Option<&'static str>,
+    /// Expected signature base string
+    pub base_string: &'static str,
+    /// Expected base64 encoded HMAC-SHA1 signature
+    pub signature: &'static str,
+}
+
+impl Vector {
+    /// The parameters of the vector as a `ParamList`
+    pub fn param_list(&self) -> ParamList<'static> {
+        self.params.iter().copied().collect()
+    }
+}
+
+/// All test vectors.
+pub const VECTORS: &[Vector] = &[
+    Vector {
+        name: "OAuth Core 1.0, Appendix A",
+        method: "GET",
+        uri: "http://photos.example.net/photos",
+        params: &[
+            ("file", "vacation.jpg"),
+            ("size", "original"),
+            ("oauth_consumer_key", "dpf43f3p2l4k3l03"),
+            ("oauth_token", "nnch734d00sl2jdk"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1191242096"),
+            ("oauth_nonce", "kllo9940pd9333jh"),
+            ("oauth_version", "1.0"),
+        ],
+        consumer_secret: "kd94hf93k423kf44",
+        token_secret: Some("pfkkdhi9sl3r4s00"),
+        base_string: "GET&http%3A%2F%2Fphotos.example.net%2Fphotos&file%3Dvacation.jpg\
+                      %26oauth_consumer_key%3Ddpf43f3p2l4k3l03%26oauth_nonce%3Dkllo9940pd9333jh\
+                      %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D1191242096\
+                      %26oauth_token%3Dnnch734d00sl2jdk%26oauth_version%3D1.0%26size%3Doriginal",
+        signature: "tR3+Ty81lMeYAr/Fid0kMTYa/WM=",
+    },
+    // The signature printed in the RFC does not match its inputs; this is
+    // the one computed from the documented request.
+    Vector {
+        name: "RFC 5849, section 1.2",
+        method: "GET",
+        uri: "http://photos.example.net/photos",
+        params: &[
+            ("file", "vacation.jpg"),
+            ("size", "original"),
+            ("oauth_consumer_key", "dpf43f3p2l4k3l03"),
+            ("oauth_token", "nnch734d00sl2jdk"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "137131202"),
+            ("oauth_nonce", "chapoH"),
+        ],
+        consumer_secret: "kd94hf93k423kf44",
+        token_secret: Some("pfkkdhi9sl3r4s00"),
+        base_string: "GET&http%3A%2F%2Fphotos.example.net%2Fphotos&file%3Dvacation.jpg\
+                      %26oauth_consumer_key%3Ddpf43f3p2l4k3l03%26oauth_nonce%3DchapoH\
+                      %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D137131202\
+                      %26oauth_token%3Dnnch734d00sl2jdk%26size%3Doriginal",
+        signature: "MdpQcU8iPSUjWoN/UDMsK2sui9I=",
+    },
+    // The RFC gives no secrets for this request; the signature is computed
+    // with arbitrary ones.
+    Vector {
+        name: "RFC 5849, section 3.4.1",
+        method: "POST",
+        uri: "http://example.com/request",
+        params: &[
+            ("b5", "=%3D"),
+            ("a3", "a"),
+            ("c@", ""),
+            ("a2", "r b"),
+            ("oauth_consumer_key", "9djdj82h48djs9d2"),
+            ("oauth_token", "kkk9d7dh3k39sjv7"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "137131201"),
+            ("oauth_nonce", "7d8f3e4a"),
+            ("c2", ""),
+            ("a3", "2 q"),
+        ],
+        consumer_secret: "j49sk3j29djd",
+        token_secret: Some("dh893hdasih9"),
+        base_string: "POST&http%3A%2F%2Fexample.com%2Frequest&a2%3Dr%2520b%26a3%3D2%2520q\
+                      %26a3%3Da%26b5%3D%253D%25253D%26c%2540%3D%26c2%3D\
+                      %26oauth_consumer_key%3D9djdj82h48djs9d2%26oauth_nonce%3D7d8f3e4a\
+                      %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D137131201\
+                      %26oauth_token%3Dkkk9d7dh3k39sjv7",
+        signature: "r6/TJjbCOr97/+UU0NsvSne7s5g=",
+    },
+    Vector {
+        name: "Twitter, Creating a signature",
+        method: "POST",
+        uri: "https://api.twitter.com/1.1/statuses/update.json",
+        params: &[
+            (
+                "status",
+                "Hello Ladies + Gentlemen, a signed OAuth request!",
+            ),
+            ("include_entities", "true"),
+            ("oauth_consumer_key", "xvz1evFS4wEEPTGEFPHBog"),
+            ("oauth_nonce", "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg"),
+            ("oauth_signature_method", "HMAC-SHA1"),
+            ("oauth_timestamp", "1318622958"),
+            (
+                "oauth_token",
+                "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+            ),
+            ("oauth_version", "1.0"),
+        ],
+        consumer_secret: "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+        token_secret: Some("LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE"),
+        base_string: "POST&https%3A%2F%2Fapi.twitter.com%2F1.1%2Fstatuses%2Fupdate.json\
+                      &include_entities%3Dtrue%26oauth_consumer_key%3Dxvz1evFS4wEEPTGEFPHBog\
+                      %26oauth_nonce%3DkYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg\
+                      %26oauth_signature_method%3DHMAC-SHA1%26oauth_timestamp%3D1318622958\
+                      %26oauth_token%3D370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb\
+                      %26oauth_version%3D1.0%26status%3DHello%2520Ladies%2520%252B%2520Gentlemen\
+                      %252C%2520a%2520signed%2520OAuth%2520request%2521",
+        signature: "hCtSmYh+iHYCEqBWrE7C7hYmtUk=",
+    },
+];
+
+/// An error happening when an implementation does not reproduce a vector.
+#[derive(Debug, Error, Clone)]
+#[error("{what} of `{name}`: expected `{expected}`, got `{actual}`")]
+pub struct Mismatch {
+    /// Name of the failing vector
+    pub name: &'static str,
+    /// What was checked, `base string` or `signature`
+    pub what: &'static str,
+    /// The expected value
+    pub expected: String,
+    /// The value produced by the implementation
+    pub actual: String,
+}
+
+impl From<Mismatch> for crate::Error {
+    fn from(err: Mismatch) -> crate::Error {
+        crate::Error::Signature(err.to_string())
+    }
+}
+
+/// Check the signing functions of this crate against all the vectors.
+pub fn verify() -> Result<(), Mismatch> {
+    verify_with(
+        |v| signature_base_string(v.method, v.uri, &v.param_list()),
+        |v, base| hmac_sha1_signature(base, v.consumer_secret, v.token_secret),
+    )
+}
+
+/// Check custom implementations against all the vectors.
+///
+/// `base_string` builds the base string of a vector and `sign` signs it.
+pub fn verify_with<B, S>(base_string: B, sign: S) -> Result<(), Mismatch>
+where
+    B: Fn(&Vector) -> String,
+    S: Fn(&Vector, &str) -> String,
+{
+    for v in VECTORS {
+        let base = base_string(v);
+        check(v, "base string", v.base_string, &base)?;
+        check(v, "signature", v.signature, &sign(v, &base))?;
+    }
+    Ok(())
+}
+
+fn check(v: &Vector, what: &'static str, expected: &str, actual: &str) -> Result<(), Mismatch> {
+    if expected == actual {
+        return Ok(());
+    }
+    Err(Mismatch {
+        name: v.name,
+        what,
+        expected: expected.to_string(),
+        actual: actual.to_string(),
+    })
+}
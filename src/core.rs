@@ -0,0 +1,1324 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Sans-IO signing core.
+//!
+//! Base strings, signatures, `Authorization` headers and signed requests, with
+//! no dependency on any HTTP client or runtime. Servers, proxies and other
+//! code with an HTTP stack of their own can sign with these functions and
+//! send the [`SignedRequest`](../transport/struct.SignedRequest.html)s
+//! themselves.
+
+use crate::param::collect_params;
+use crate::quirks::ParamPlacement;
+use crate::transport::SignedRequest;
+#[cfg(feature = "client")]
+use crate::StatusClass;
+use crate::{
+    AccessToken, Challenge, ChallengeError, Clock, Config, ConsumerToken, Error, ParamList,
+    ProblemError, QuirkProfile, RateLimit, Result, StatusError, SystemClock, Token,
+};
+use http::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE, LOCATION, WWW_AUTHENTICATE};
+use http::{Method, StatusCode};
+use log::*;
+#[cfg(not(target_arch = "wasm32"))]
+use ring::hmac;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+pub(crate) fn insert_param<'a, K, V>(
+    param: &mut ParamList<'a>,
+    key: K,
+    value: V,
+) -> Option<Cow<'a, str>>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    param.insert(key.into(), value.into())
+}
+
+pub(crate) fn join_query(param: &ParamList<'_>) -> String {
+    join_query_with(param, &QuirkProfile::default())
+}
+
+/// Normalize the parameters: sorted by encoded name, then by encoded value
+/// for the names appearing several times. See RFC 5849, section 3.4.1.3.2.
+pub(crate) fn join_query_with(param: &ParamList<'_>, quirks: &QuirkProfile) -> String {
+    let mut pairs = param
+        .iter()
+        .map(|(k, v)| (encode_with(k, quirks), encode_with(v, quirks)))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+// Encode all but the unreserved characters defined in
+// RFC 3986, section 2.3. "Unreserved Characters"
+// https://tools.ietf.org/html/rfc3986#page-12
+
+pub(crate) const URL: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Percent encode string as in the signature: every byte of the UTF-8
+/// encoding is encoded as `%XX`, with uppercase hexadecimal digits, except
+/// the unreserved characters `A-Z`, `a-z`, `0-9`, `-`, `.`, `_` and `~`.
+/// In particular, `*` is encoded as `%2A`, `+` as `%2B` and space as `%20`.
+/// See RFC 5849, section 3.6.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(oauth_client::encode("a b+c*~"), "a%20b%2Bc%2A~");
+/// assert_eq!(oauth_client::encode("café"), "caf%C3%A9");
+/// ```
+pub fn encode(s: &str) -> String {
+    percent_encoding::percent_encode(s.as_bytes(), URL).collect()
+}
+
+/// Percent decode string, the inverse of [`encode`](fn.encode.html).
+///
+/// `+` is kept as is, not decoded to a space, and every `%` must be followed
+/// by two hexadecimal digits. The decoded bytes must be valid UTF-8.
+///
+/// # Examples
+///
+/// ```
+/// assert_eq!(oauth_client::decode("a%20b+c").unwrap(), "a b+c");
+/// assert_eq!(oauth_client::decode("caf%c3%a9").unwrap(), "café");
+/// assert!(oauth_client::decode("100%").is_err());
+/// ```
+pub fn decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    for (i, _) in s.match_indices('%') {
+        let valid = bytes.len() > i + 2
+            && bytes[i + 1].is_ascii_hexdigit()
+            && bytes[i + 2].is_ascii_hexdigit();
+        if !valid {
+            return Err(Error::InvalidEncoding(format!(
+                "`%` not followed by two hexadecimal digits at {}",
+                i
+            )));
+        }
+    }
+    percent_encoding::percent_decode(bytes)
+        .decode_utf8()
+        .map(Cow::into_owned)
+        .map_err(|err| Error::InvalidEncoding(err.to_string()))
+}
+
+/// Parse an `application/x-www-form-urlencoded` body, such as the responses
+/// of the token endpoints. Names and values are percent-decoded, with `+`
+/// decoded to a space.
+///
+/// # Examples
+///
+/// ```
+/// let param =
+///     oauth_client::parse_form_body(b"oauth_token=ab%2Bc&oauth_token_secret=x&name=J+Doe")
+///         .unwrap();
+/// assert_eq!(param.get("oauth_token"), Some("ab+c"));
+/// assert_eq!(param.get("name"), Some("J Doe"));
+/// ```
+pub fn parse_form_body(body: &[u8]) -> Result<ParamList<'static>> {
+    let body = std::str::from_utf8(body).map_err(|err| Error::InvalidEncoding(err.to_string()))?;
+    body.split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let name = decode(&name.replace('+', " "))?;
+            let value = decode(&value.replace('+', " "))?;
+            Ok((Cow::Owned(name), Cow::Owned(value)))
+        })
+        .collect()
+}
+
+/// Percent encode string, following the encoding quirks of the provider
+pub(crate) fn encode_with(s: &str, quirks: &QuirkProfile) -> String {
+    percent_encoding::percent_encode(s.as_bytes(), quirks.encode_set()).collect()
+}
+
+/// Construct the signature base string of a request from its `method`, its
+/// base `uri` and all the parameters to sign.
+/// The scheme and host of `uri` are lowercased and its default port, query
+/// and fragment are dropped. See RFC 5849, section 3.4.1.
+///
+/// # Examples
+///
+/// ```
+/// let mut param = oauth_client::ParamList::new();
+/// let _ = param.insert("b".into(), "x y".into());
+/// let _ = param.insert("a".into(), "1".into());
+/// let base = oauth_client::signature_base_string("GET", "http://example.com/", &param);
+/// assert_eq!(base, "GET&http%3A%2F%2Fexample.com%2F&a%3D1%26b%3Dx%2520y");
+/// ```
+pub fn signature_base_string(method: &str, uri: impl AsRef<str>, param: &ParamList) -> String {
+    let uri = uri.as_ref();
+    base_string(method, uri, &join_query(param), &QuirkProfile::default())
+}
+
+fn base_string(method: &str, uri: &str, query: &str, quirks: &QuirkProfile) -> String {
+    format!(
+        "{}&{}&{}",
+        encode_with(method, quirks),
+        encode_with(&base_uri(uri), quirks),
+        encode_with(query, quirks)
+    )
+}
+
+/// Normalize the base string URI: lowercase scheme and host, no default port,
+/// no query and no fragment. See RFC 5849, section 3.4.1.2.
+/// URIs failing to parse are kept verbatim.
+fn base_uri(uri: &str) -> Cow<'_, str> {
+    match url::Url::parse(uri) {
+        // `Url` already lowercases the scheme and the host and drops the
+        // default port.
+        Ok(mut url) => {
+            url.set_query(None);
+            url.set_fragment(None);
+            Cow::Owned(url.into())
+        }
+        Err(_) => Cow::Borrowed(uri),
+    }
+}
+
+/// Sign a signature base string with HMAC-SHA1, returning the base64 encoded
+/// signature. See RFC 5849, section 3.4.2.
+pub fn hmac_sha1_signature(
+    base: &str,
+    consumer_secret: &str,
+    token_secret: Option<&str>,
+) -> String {
+    let consumer_secret = encode(consumer_secret);
+    let token_secret = encode(token_secret.unwrap_or(""));
+    let key = format!("{}&{}", consumer_secret, token_secret);
+    let signature = base64::encode(hmac_sha1(key.as_bytes(), base.as_bytes()));
+    wipe(consumer_secret);
+    wipe(token_secret);
+    wipe(key);
+    signature
+}
+
+/// Wipe a copy of a secret from memory
+#[cfg(feature = "zeroize")]
+fn wipe(mut secret: String) {
+    zeroize::Zeroize::zeroize(&mut secret);
+}
+
+#[cfg(not(feature = "zeroize"))]
+fn wipe(_secret: String) {}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let signing_key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key);
+    hmac::sign(&signing_key, data).as_ref().to_vec()
+}
+
+/// `ring` does not support `wasm32-unknown-unknown`
+#[cfg(target_arch = "wasm32")]
+fn hmac_sha1(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::Mac;
+
+    // HMAC accepts keys of any length.
+    let mut mac = hmac::Hmac::<sha1::Sha1>::new_from_slice(key).unwrap();
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Create signature. See https://dev.twitter.com/oauth/overview/creating-signatures
+fn signature(
+    method: &str,
+    uri: &str,
+    query: &str,
+    consumer_secret: &str,
+    token_secret: Option<&str>,
+    quirks: &QuirkProfile,
+) -> String {
+    let base = base_string(method, uri, query, quirks);
+    debug!(
+        "Signature base string: {}",
+        describe_base_string(&base, query)
+    );
+    hmac_sha1_signature(&base, consumer_secret, token_secret)
+}
+
+/// The length of `base` and the names of the parameters of `query`, to be
+/// logged in place of the base string, whose values may be secrets
+fn describe_base_string(base: &str, query: &str) -> String {
+    let names = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| pair.split('=').next().unwrap_or(pair))
+        .collect::<Vec<_>>();
+    format!("{} bytes, parameters {}", base.len(), names.join(", "))
+}
+
+/// Constuct plain-text header
+fn header(param: &ParamList, quirks: &QuirkProfile) -> String {
+    if quirks.placement != ParamPlacement::Header {
+        return String::new();
+    }
+    let mut pairs = param
+        .iter()
+        .filter(|(k, _)| k.starts_with("oauth_"))
+        .map(|(k, v)| format!("{}=\"{}\"", k, encode_with(v, quirks)))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    if let Some(realm) = &quirks.realm {
+        let realm = realm.replace('\\', "\\\\").replace('"', "\\\"");
+        pairs.insert(0, format!("realm=\"{}\"", realm));
+    }
+    format!("OAuth {}", pairs.join(", "))
+}
+
+/// Construct plain-text body from 'ParamList'
+fn body(param: &ParamList, quirks: &QuirkProfile) -> String {
+    let in_body = |k: &str| quirks.placement == ParamPlacement::Query || !k.starts_with("oauth_");
+    let mut pairs = param
+        .iter()
+        .filter(|(k, _)| in_body(k))
+        .map(|(k, v)| format!("{}={}", encode_with(k, quirks), encode_with(v, quirks)))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs.join("&")
+}
+
+/// A signed request's `Authorization` header and encoded parameters, as
+/// returned by [`authorization_header`](fn.authorization_header.html)
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let header = oauth_client::authorization_header_with_params(
+///     "GET",
+///     "http://example.com/search?q=rust",
+///     &consumer,
+///     None,
+///     [("count", "10")],
+/// )
+/// .unwrap();
+/// assert!(header.header().starts_with("OAuth "));
+/// assert_eq!(header.body(), "count=10");
+/// assert_eq!(header.query(), "q=rust&count=10");
+/// assert_eq!(header.signed_params().get("q"), Some("rust"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthorizationHeader {
+    header: String,
+    signed_params: ParamList<'static>,
+    body: String,
+    query: String,
+}
+
+impl AuthorizationHeader {
+    /// The value of the `Authorization` header, empty when the `oauth_*`
+    /// parameters are placed in the body or the query instead
+    pub fn header(&self) -> &str {
+        &self.header
+    }
+
+    /// The parameters covered by the signature, sorted: the `oauth_*`
+    /// parameters, the other parameters and the query parameters of the URI
+    pub fn signed_params(&self) -> &ParamList<'static> {
+        &self.signed_params
+    }
+
+    /// The encoded parameters to send as the form body of a POST request.
+    /// The query parameters of the URI are not repeated.
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    /// The full query of a GET request: the query of the URI followed by
+    /// the encoded parameters
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The value of the `Authorization` header
+    pub fn into_header(self) -> String {
+        self.header
+    }
+}
+
+/// Parse `uri`, rejecting the URLs that cannot be signed: relative URLs and
+/// schemes other than `http` and `https`
+pub(crate) fn parse_url(uri: &str) -> Result<url::Url> {
+    let url = url::Url::parse(uri).map_err(|err| Error::invalid_url(uri, err))?;
+    if url.scheme() != "http" && url.scheme() != "https" {
+        return Err(Error::invalid_url(uri, "the scheme is not http or https"));
+    }
+    Ok(url)
+}
+
+/// Create header and body, after checking that `uri` can be signed.
+/// The query parameters of `uri` are signed, but left out of the body.
+/// The header is empty when the `oauth_*` parameters are placed in the body.
+pub(crate) fn get_header(
+    method: &str,
+    uri: &str,
+    consumer: &Token,
+    token: Option<&Token>,
+    other_param: Option<&ParamList>,
+    config: &Config,
+) -> Result<AuthorizationHeader> {
+    let url = parse_url(uri)?;
+    check_realm(&config.quirks)?;
+    let query = url
+        .query_pairs()
+        .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned())))
+        .collect::<ParamList>();
+    let mut header = sign_param(method, uri, consumer, token, other_param, &query, config);
+    let uri_query = uri
+        .split('#')
+        .next()
+        .and_then(|uri| uri.split_once('?'))
+        .map_or("", |(_, query)| query);
+    header.query = match (uri_query.is_empty(), header.body.is_empty()) {
+        (_, true) => uri_query.to_string(),
+        (true, false) => header.body.clone(),
+        (false, false) => format!("{}&{}", uri_query, header.body),
+    };
+    Ok(header)
+}
+
+/// Fail with `Error::InvalidHeader` on a realm which cannot be sent in a
+/// header, the other values of the header being percent-encoded
+fn check_realm(quirks: &QuirkProfile) -> Result<()> {
+    match &quirks.realm {
+        Some(realm) if realm.chars().any(char::is_control) => Err(Error::InvalidHeader(
+            "the realm contains control characters".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Create header and body for a valid `uri`, also signing the parameters of
+/// its `query`
+fn sign_param(
+    method: &str,
+    uri: &str,
+    consumer: &Token,
+    token: Option<&Token>,
+    other_param: Option<&ParamList>,
+    query: &ParamList,
+    config: &Config,
+) -> AuthorizationHeader {
+    #[cfg(feature = "unicode-normalization")]
+    let normalized = other_param
+        .filter(|_| config.normalize_nfc)
+        .map(normalize_nfc);
+    #[cfg(feature = "unicode-normalization")]
+    let other_param = normalized.as_ref().or(other_param);
+
+    let mut param = ParamList::new();
+    let timestamp = config.timestamp().to_string();
+    let nonce = config.nonce();
+
+    let _ = insert_param(&mut param, "oauth_consumer_key", consumer.key.to_string());
+    let _ = insert_param(&mut param, "oauth_nonce", nonce);
+    let _ = insert_param(&mut param, "oauth_signature_method", "HMAC-SHA1");
+    let _ = insert_param(&mut param, "oauth_timestamp", timestamp);
+    if !config.quirks.omit_version {
+        let _ = insert_param(&mut param, "oauth_version", "1.0");
+    }
+    match token {
+        Some(tk) => {
+            let _ = insert_param(&mut param, "oauth_token", tk.key.as_ref());
+        }
+        None if config.quirks.empty_token => {
+            let _ = insert_param(&mut param, "oauth_token", "");
+        }
+        None => {}
+    }
+
+    // Other parameters may replace the generated `oauth_*` ones, and may
+    // repeat their names.
+    for (k, v) in other_param.into_iter().flatten() {
+        if k.starts_with("oauth_") {
+            let _ = insert_param(&mut param, k.as_ref(), v.as_ref());
+        } else {
+            param.push(k.as_ref().into(), v.as_ref().into());
+        }
+    }
+
+    let mut signed = param.clone();
+    signed.extend(query.iter().map(|(k, v)| (k.as_ref(), v.as_ref())));
+    let sign = signature(
+        method,
+        uri,
+        join_query_with(&signed, &config.quirks).as_ref(),
+        consumer.secret.as_ref(),
+        token.map(|t| t.secret.as_ref()),
+        &config.quirks,
+    );
+    let _ = insert_param(&mut param, "oauth_signature", sign);
+
+    let mut signed_params = signed
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect::<Vec<_>>();
+    signed_params.sort();
+    AuthorizationHeader {
+        header: header(&param, &config.quirks),
+        signed_params: signed_params.into_iter().collect(),
+        body: body(&param, &config.quirks),
+        query: String::new(),
+    }
+}
+
+/// The parameters with their names and values in Unicode Normalization Form C
+#[cfg(feature = "unicode-normalization")]
+fn normalize_nfc(param: &ParamList) -> ParamList<'static> {
+    use unicode_normalization::UnicodeNormalization;
+
+    param
+        .iter()
+        .map(|(k, v)| (k.nfc().collect::<String>(), v.nfc().collect::<String>()))
+        .collect()
+}
+
+/// Create an authorization header, with the encoded parameters to send.
+/// See https://dev.twitter.com/oauth/overview/authorizing-requests
+///
+/// # Examples
+///
+/// ```
+/// # extern crate oauth_client;
+/// # fn main() {
+/// const REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let header = oauth_client::authorization_header("GET", REQUEST_TOKEN, &consumer, None, None);
+/// assert!(header.is_ok());
+/// let header = oauth_client::authorization_header("GET", "/v1/request-token", &consumer, None, None);
+/// assert!(header.is_err());
+/// # }
+/// ```
+pub fn authorization_header(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList>,
+) -> Result<AuthorizationHeader> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    get_header(
+        method,
+        uri,
+        consumer,
+        token,
+        other_param,
+        &Config::default(),
+    )
+}
+
+/// [`authorization_header`](fn.authorization_header.html), with the
+/// parameters given as any pairs of strings, such as a slice, a `Vec` or a
+/// map, instead of a `ParamList`.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let header = oauth_client::authorization_header_with_params(
+///     "POST",
+///     "http://example.com/statuses",
+///     &consumer,
+///     None,
+///     [("status", "Hello")],
+/// )
+/// .unwrap();
+/// assert_eq!(header.body(), "status=Hello");
+/// ```
+pub fn authorization_header_with_params<I, K, V>(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    params: I,
+) -> Result<AuthorizationHeader>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let uri = uri.as_ref();
+    let param = collect_params(params);
+    authorization_header(method, uri, consumer, token, Some(&param))
+}
+
+/// Create only the value of the `Authorization` header, to set on a request
+/// of another HTTP stack. The parameters in `other_param` must still be sent
+/// with the request.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let header =
+///     oauth_client::sign_as_header("GET", "http://example.com/a?b=1", &consumer, None, None)
+///         .unwrap();
+/// assert!(header.starts_with("OAuth oauth_consumer_key=\"key\", "));
+/// ```
+pub fn sign_as_header(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList>,
+) -> Result<String> {
+    let header = authorization_header(method, uri, consumer, token, other_param)?;
+    Ok(header.into_header())
+}
+
+/// Create only a signed URL, with the `oauth_*` parameters and
+/// `other_param` in its query, for a request sending no `Authorization`
+/// header. The fragment of `uri` is left out.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let url = oauth_client::sign_as_query("GET", "http://example.com/a?b=1", &consumer, None, None)
+///     .unwrap();
+/// assert!(url.starts_with("http://example.com/a?b=1&oauth_consumer_key=key&"));
+/// assert!(url.contains("&oauth_signature="));
+/// ```
+pub fn sign_as_query(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList>,
+) -> Result<String> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    let config = Config {
+        quirks: QuirkProfile::query_string(),
+        ..Config::default()
+    };
+    let header = get_header(method, uri, consumer, token, other_param, &config)?;
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    Ok(format!("{}?{}", path, header.query()))
+}
+
+/// Create an authorization header with an explicit `nonce` and `timestamp`
+/// instead of generated ones, so the output is reproducible in tests.
+///
+/// # Examples
+///
+/// ```
+/// // Example of https://developer.twitter.com/en/docs/authentication/oauth-1-0a/creating-a-signature
+/// let consumer = oauth_client::ConsumerToken::new(
+///     "xvz1evFS4wEEPTGEFPHBog",
+///     "kAcSOqF21Fu85e7zjz7ZN2U4ZRhfV3WpwPAoE3Z7kBw",
+/// );
+/// let token = oauth_client::AccessToken::new(
+///     "370773112-GmHxMAgYyLbNEtIKZeRNFsMKPR9EyMZeS9weJAEb",
+///     "LswwdoUaIvS8ltyTt5jkRh4J50vUPVVHtR2YPi5kE",
+/// );
+/// let mut param = oauth_client::ParamList::new();
+/// let _ = param.insert("include_entities".into(), "true".into());
+/// let _ = param.insert(
+///     "status".into(),
+///     "Hello Ladies + Gentlemen, a signed OAuth request!".into(),
+/// );
+/// let header = oauth_client::sign_with(
+///     "POST",
+///     "https://api.twitter.com/1.1/statuses/update.json",
+///     &consumer,
+///     Some(&token),
+///     Some(&param),
+///     "kYjzVBB8Y0ZFabxSWbWovY3uYSQ2pTgmZeNu2VS4cg",
+///     1318622958,
+/// )
+/// .unwrap();
+/// assert!(header.header().contains("oauth_signature=\"hCtSmYh%2BiHYCEqBWrE7C7hYmtUk%3D\""));
+/// ```
+pub fn sign_with(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList>,
+    nonce: &str,
+    timestamp: i64,
+) -> Result<AuthorizationHeader> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    let nonce = nonce.to_string();
+    let config = Config {
+        timestamp: Some(timestamp),
+        nonce: Some(Arc::new(move || nonce.clone())),
+        ..Config::default()
+    };
+    get_header(method, uri, consumer, token, other_param, &config)
+}
+
+/// Create an authorization header for the request-token step.
+/// `callback` is signed and sent as `oauth_callback`; pass `"oob"` when the
+/// application cannot receive a redirect.
+///
+/// # Examples
+///
+/// ```
+/// const REQUEST_TOKEN: &str = "http://oauthbin.com/v1/request-token";
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let header = oauth_client::request_token_header(
+///     "POST",
+///     REQUEST_TOKEN,
+///     &consumer,
+///     "https://example.com/callback",
+///     None,
+/// )
+/// .unwrap();
+/// assert!(header.header().contains("oauth_callback=\"https%3A%2F%2Fexample.com%2Fcallback\""));
+/// ```
+pub fn request_token_header(
+    method: &str,
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    callback: &str,
+    other_param: Option<&ParamList>,
+) -> Result<AuthorizationHeader> {
+    let uri = uri.as_ref();
+    let mut param = ParamList::new();
+    param.extend(
+        other_param
+            .into_iter()
+            .flatten()
+            .map(|(k, v)| (k.as_ref(), v.as_ref())),
+    );
+    let _ = insert_param(&mut param, "oauth_callback", callback);
+    get_header(
+        method,
+        uri,
+        consumer,
+        None,
+        Some(&param),
+        &Config::default(),
+    )
+}
+
+/// Add an OAuth `Authorization` header to an `http` request, as built by
+/// frameworks based on `http`, `hyper` or `tower`.
+///
+/// The HTTP method, the URL and the parameters to sign are taken from
+/// `request` itself: query parameters of the URL and, for
+/// `application/x-www-form-urlencoded` requests, the pairs in the body.
+/// The URI must be absolute.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let mut request = http::Request::get("http://oauthbin.com/v1/echo?a=1")
+///     .body(Vec::new())
+///     .unwrap();
+/// oauth_client::sign_http_request(&mut request, &consumer, None).unwrap();
+/// assert!(request.headers().contains_key(http::header::AUTHORIZATION));
+/// ```
+pub fn sign_http_request<B: AsRef<[u8]>>(
+    request: &mut http::Request<B>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+) -> Result<()> {
+    let token = token.map(|t| &**t);
+    let url = parse_url(&request.uri().to_string())?;
+    let header = request_header(
+        request.method(),
+        &url,
+        request.headers(),
+        Some(request.body().as_ref()),
+        consumer,
+        token,
+    );
+    sign_headers(request.headers_mut(), header)
+}
+
+/// Add an OAuth `Authorization` header to `headers`, signing a request with
+/// the given method, URL and body.
+/// See [`sign_http_request`](fn.sign_http_request.html).
+pub fn sign_http_parts(
+    method: &Method,
+    url: impl AsRef<str>,
+    headers: &mut HeaderMap,
+    body: &[u8],
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+) -> Result<()> {
+    let url = url.as_ref();
+    let token = token.map(|t| &**t);
+    let url = parse_url(url)?;
+    let header = request_header(method, &url, headers, Some(body), consumer, token);
+    sign_headers(headers, header)
+}
+
+/// The `Authorization` header of a built request, signing the query
+/// parameters of `url` and the pairs of a form-encoded `body`
+pub(crate) fn request_header(
+    method: &Method,
+    url: &url::Url,
+    headers: &HeaderMap,
+    body: Option<&[u8]>,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+) -> String {
+    let mut uri = url.clone();
+    uri.set_query(None);
+    uri.set_fragment(None);
+
+    let mut param = url
+        .query_pairs()
+        .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned())))
+        .collect::<ParamList>();
+    let is_form = headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("application/x-www-form-urlencoded"));
+    if is_form {
+        if let Some(body) = body {
+            param.extend(
+                url::form_urlencoded::parse(body)
+                    .map(|(k, v)| (Cow::Owned(k.into_owned()), Cow::Owned(v.into_owned()))),
+            );
+        }
+    }
+
+    sign_param(
+        method.as_str(),
+        uri.as_str(),
+        consumer,
+        token,
+        Some(&param),
+        &ParamList::new(),
+        &Config::default(),
+    )
+    .into_header()
+}
+
+/// Set the `Authorization` header
+pub(crate) fn sign_headers(headers: &mut HeaderMap, header: String) -> Result<()> {
+    let header = http::HeaderValue::from_str(&header)
+        .map_err(|err| Error::InvalidHeader(err.to_string()))?;
+    let _ = headers.insert(AUTHORIZATION, header);
+    Ok(())
+}
+
+/// Build a signed GET request to the specified URL, to be sent by any HTTP
+/// client. `consumer` is a consumer token.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let request =
+///     oauth_client::core::get_request("http://example.com/a", &consumer, None, None).unwrap();
+/// assert_eq!(request.method, http::Method::GET);
+/// assert!(request.headers.contains_key(http::header::AUTHORIZATION));
+/// ```
+pub fn get_request(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<SignedRequest> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    get_request_with(uri, consumer, token, other_param, &Config::default())
+}
+
+/// Build a signed, form-encoded POST request to the specified URL, to be sent
+/// by any HTTP client. `consumer` is a consumer token.
+pub fn post_request(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<SignedRequest> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    post_request_with(uri, consumer, token, other_param, &Config::default())
+}
+
+pub(crate) fn get_request_with(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    other_param: Option<&ParamList<'_>>,
+    config: &Config,
+) -> Result<SignedRequest> {
+    let header = get_header("GET", uri, consumer, token, other_param, config)?;
+    // The fragment is not sent, and would hide the appended parameters.
+    let uri = uri.split('#').next().unwrap_or(uri);
+    let req_uri = if header.body.is_empty() {
+        uri.to_string()
+    } else {
+        let path = uri.split('?').next().unwrap_or(uri);
+        format!("{}?{}", path, header.query)
+    };
+    let mut request = SignedRequest::new(Method::GET, req_uri);
+    request.headers = config.headers.clone();
+    with_authorization(request, header.header)
+}
+
+pub(crate) fn post_request_with(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    other_param: Option<&ParamList<'_>>,
+    config: &Config,
+) -> Result<SignedRequest> {
+    let header = get_header("POST", uri, consumer, token, other_param, config)?;
+    let mut request = SignedRequest::new(Method::POST, uri.to_string());
+    request.headers = config.headers.clone();
+    request.body = header.body.into_bytes();
+    let _ = request.headers.insert(
+        CONTENT_TYPE,
+        http::HeaderValue::from_static("application/x-www-form-urlencoded"),
+    );
+    with_authorization(request, header.header)
+}
+
+/// Add the `Authorization` header, unless the `oauth_*` parameters are
+/// placed elsewhere
+fn with_authorization(mut request: SignedRequest, header: String) -> Result<SignedRequest> {
+    if !header.is_empty() {
+        sign_headers(&mut request.headers, header)?;
+    }
+    Ok(request)
+}
+
+/// Turn a non-OK response from `url` into the error returned by the HTTP
+/// functions: a `Error::Redirected`, `Error::RateLimited`, `Error::Problem`,
+/// `Error::Challenge` or `Error::Status`
+pub fn check_status(url: &str, status: StatusCode, headers: &HeaderMap, body: &[u8]) -> Result<()> {
+    check_status_at(url, status, headers, body, SystemClock.now())
+}
+
+/// [`check_status`](fn.check_status.html), with `now` as the current Unix
+/// time for the reset of rate limits
+fn check_status_at(
+    url: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+    now: i64,
+) -> Result<()> {
+    if status == StatusCode::OK {
+        return Ok(());
+    }
+
+    let status = status.as_u16();
+    let location = headers.get(LOCATION).and_then(|v| v.to_str().ok());
+    if let (301 | 302 | 303 | 307 | 308, Some(location)) = (status, location) {
+        let location = url::Url::parse(url)
+            .and_then(|url| url.join(location))
+            .map_or_else(|_| location.to_string(), String::from);
+        return Err(Error::Redirected { status, location });
+    }
+    if status == 429 || status == 420 {
+        let rate_limit = RateLimit::from_headers_at(headers, now);
+        return Err(Error::RateLimited {
+            status,
+            reset_at: rate_limit.reset_at(now),
+            rate_limit,
+        });
+    }
+    let challenge = headers
+        .get_all(WWW_AUTHENTICATE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .find_map(Challenge::parse);
+    let problem = ProblemError::from_body(status, body).or_else(|| {
+        let params = challenge.as_ref()?.params.clone();
+        ProblemError::from_params(status, params)
+    });
+    if let Some(mut err) = problem {
+        err.challenge = challenge;
+        return Err(err.into());
+    }
+    if let Some(challenge) = challenge {
+        return Err(ChallengeError { status, challenge }.into());
+    }
+    Err(StatusError::new(status, url, headers, body).into())
+}
+
+/// [`check_status`](fn.check_status.html), classifying `status` and decoding
+/// the body of status errors and timing rate limits as configured in `config`
+#[cfg(feature = "client")]
+pub(crate) fn check_status_with(
+    url: &str,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &[u8],
+    config: &Config,
+) -> Result<()> {
+    let rsp = match config.classify(status) {
+        StatusClass::Success => return Ok(()),
+        StatusClass::Error | StatusClass::Retry if status == StatusCode::OK => {
+            Err(StatusError::new(status.as_u16(), url, headers, body).into())
+        }
+        StatusClass::Error | StatusClass::Retry => {
+            check_status_at(url, status, headers, body, config.server_now())
+        }
+    };
+    match rsp {
+        Err(Error::Status(mut err)) => {
+            err.provider = config
+                .error_decoder
+                .as_ref()
+                .and_then(|decoder| decoder.decode(status, headers, body));
+            Err(Error::Status(err))
+        }
+        rsp => rsp,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::encode;
+    use crate::{ConsumerToken, ParamList};
+
+    #[test]
+    fn query() {
+        let mut map = ParamList::new();
+        let _ = map.insert("aaa".into(), "AAA".into());
+        let _ = map.insert("bbbb".into(), "BBBB".into());
+        let query = super::join_query(&map);
+        assert_eq!("aaa=AAA&bbbb=BBBB", query);
+    }
+
+    #[test]
+    fn test_encode() {
+        let method = "GET";
+        let uri = "http://oauthbin.com/v1/request-token";
+        let encoded_uri = "http%3A%2F%2Foauthbin.com%2Fv1%2Frequest-token";
+        let query = [
+            "oauth_consumer_key=key&",
+            "oauth_nonce=s6HGl3GhmsDsmpgeLo6lGtKs7rQEzzsA&",
+            "oauth_signature_method=HMAC-SHA1&",
+            "oauth_timestamp=1471445561&",
+            "oauth_version=1.0",
+        ]
+        .iter()
+        .cloned()
+        .collect::<String>();
+        let encoded_query = [
+            "oauth_consumer_key%3Dkey%26",
+            "oauth_nonce%3Ds6HGl3GhmsDsmpgeLo6lGtKs7rQEzzsA%26",
+            "oauth_signature_method%3DHMAC-SHA1%26",
+            "oauth_timestamp%3D1471445561%26",
+            "oauth_version%3D1.0",
+        ]
+        .iter()
+        .cloned()
+        .collect::<String>();
+
+        assert_eq!(encode(method), "GET");
+        assert_eq!(encode(uri), encoded_uri);
+        assert_eq!(encode(&query), encoded_query);
+    }
+
+    #[test]
+    fn request_token_header_signs_callback() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut param = ParamList::new();
+        let _ = param.insert("x_auth_access_type".into(), "read".into());
+        let header = super::request_token_header(
+            "POST",
+            "http://oauthbin.com/v1/request-token",
+            &consumer,
+            "oob",
+            Some(&param),
+        )
+        .unwrap();
+        assert!(header.header().contains("oauth_callback=\"oob\""));
+        assert!(!header.header().contains("oauth_token="));
+        assert_eq!(header.body(), "x_auth_access_type=read");
+        assert_eq!(header.signed_params().get("oauth_callback"), Some("oob"));
+    }
+
+    #[test]
+    fn sign_relative_http_request() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut request = http::Request::get("/echo").body(Vec::new()).unwrap();
+        assert!(super::sign_http_request(&mut request, &consumer, None).is_err());
+        assert!(request.headers().is_empty());
+    }
+
+    #[test]
+    fn describe_base_string() {
+        let query = "my_oauth_token=a&oauth_token=t&oauth_verifier=v&x_auth_password=p";
+        let base = super::base_string("POST", "http://example.com/", query, &Default::default());
+        assert_eq!(
+            super::describe_base_string(&base, query),
+            format!(
+                "{} bytes, parameters my_oauth_token, oauth_token, oauth_verifier, x_auth_password",
+                base.len()
+            )
+        );
+    }
+
+    #[test]
+    fn base_uri() {
+        assert_eq!(
+            super::base_uri("HTTP://Example.COM:80/r%20v/X?id=123#top"),
+            "http://example.com/r%20v/X"
+        );
+        assert_eq!(
+            super::base_uri("https://www.example.net:8080/?q=1"),
+            "https://www.example.net:8080/"
+        );
+        assert_eq!(
+            super::base_uri("https://example.com:443"),
+            "https://example.com/"
+        );
+        let param = ParamList::new();
+        assert_eq!(
+            super::signature_base_string("GET", "HTTP://Example.COM:80/a", &param),
+            super::signature_base_string("GET", "http://example.com/a", &param)
+        );
+    }
+
+    #[test]
+    fn sign_as_query() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut param = ParamList::new();
+        let _ = param.insert("a".into(), "1 2".into());
+        let signed = super::sign_as_query(
+            "GET",
+            "http://example.com/items?count=10#top",
+            &consumer,
+            None,
+            Some(&param),
+        )
+        .unwrap();
+        let url = url::Url::parse(&signed).unwrap();
+        assert!(url.fragment().is_none());
+        let mut param = url
+            .query_pairs()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect::<ParamList>();
+        let signature = param.remove("oauth_signature").unwrap();
+        assert_eq!(param.get("count"), Some("10"));
+        assert_eq!(param.get("a"), Some("1 2"));
+        let base = super::signature_base_string("GET", "http://example.com/items", &param);
+        assert_eq!(signature, super::hmac_sha1_signature(&base, "secret", None));
+
+        let header =
+            super::sign_as_header("GET", "http://example.com/", &consumer, None, None).unwrap();
+        assert!(header.starts_with("OAuth "));
+        assert!(super::sign_as_header("GET", "/relative", &consumer, None, None).is_err());
+    }
+
+    #[test]
+    fn query_in_uri() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut param = ParamList::new();
+        let _ = param.insert("count".into(), "10".into());
+        let header = super::sign_with(
+            "GET",
+            "http://example.com/items?count=10",
+            &consumer,
+            None,
+            None,
+            "nonce",
+            1,
+        )
+        .unwrap();
+        let expected = super::sign_with(
+            "GET",
+            "http://example.com/items",
+            &consumer,
+            None,
+            Some(&param),
+            "nonce",
+            1,
+        )
+        .unwrap()
+        .into_header();
+        assert_eq!(header.header(), expected);
+        assert!(header.body().is_empty());
+        assert_eq!(header.query(), "count=10");
+        assert_eq!(header.signed_params().get("count"), Some("10"));
+
+        let mut param = ParamList::new();
+        let _ = param.insert("a".into(), "1".into());
+        let request = super::get_request(
+            "http://example.com/items?count=10#top",
+            &consumer,
+            None,
+            Some(&param),
+        )
+        .unwrap();
+        assert_eq!(request.url, "http://example.com/items?count=10&a=1");
+    }
+
+    #[test]
+    fn url_endpoint() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut url = url::Url::parse("http://example.com/v1/").unwrap();
+        url = url.join("items").unwrap();
+        url.query_pairs_mut().append_pair("q", "a&b");
+        let header = |uri: &str| super::sign_with("GET", uri, &consumer, None, None, "nonce", 1);
+        assert_eq!(
+            super::sign_with("GET", &url, &consumer, None, None, "nonce", 1).unwrap(),
+            header("http://example.com/v1/items?q=a%26b").unwrap()
+        );
+        let request = super::get_request(url, &consumer, None, None).unwrap();
+        assert_eq!(request.url, "http://example.com/v1/items?q=a%26b");
+    }
+
+    #[test]
+    fn repeated_param() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut param = ParamList::new();
+        param.push("tag".into(), "b".into());
+        param.push("tag".into(), "a".into());
+        let request = super::post_request(
+            "http://example.com/items?tag=c",
+            &consumer,
+            None,
+            Some(&param),
+        )
+        .unwrap();
+        let header = super::sign_with(
+            "POST",
+            "http://example.com/items?tag=c",
+            &consumer,
+            None,
+            Some(&param),
+            "nonce",
+            1,
+        )
+        .unwrap()
+        .into_header();
+        let mut all = param.clone();
+        all.push("tag".into(), "c".into());
+        let expected = super::sign_with(
+            "POST",
+            "http://example.com/items",
+            &consumer,
+            None,
+            Some(&all),
+            "nonce",
+            1,
+        )
+        .unwrap()
+        .into_header();
+        assert_eq!(request.body, b"tag=a&tag=b");
+        assert_eq!(header, expected);
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let s = "!*'();:@&=+$,/?#[] ~-._ é€😀";
+        let encoded = super::encode(s);
+        assert!(encoded
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"%-._~".contains(&b)));
+        assert_eq!(super::decode(&encoded).unwrap(), s);
+        assert!(super::decode("%zz").is_err());
+        assert!(super::decode("%ff").is_err());
+    }
+
+    #[test]
+    fn form_body() {
+        let param = super::parse_form_body(b"a=1&b=&c&a=x+y%2B&&d%20e=%E2%82%AC").unwrap();
+        let pairs = param
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v.as_ref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            [
+                ("a", "1"),
+                ("b", ""),
+                ("c", ""),
+                ("a", "x y+"),
+                ("d e", "€")
+            ]
+        );
+        assert!(super::parse_form_body(b"a=%E2%82").is_err());
+        assert!(super::parse_form_body(b"a=%2").is_err());
+    }
+
+    #[test]
+    fn invalid_url() {
+        let consumer = ConsumerToken::new("key", "secret");
+        for uri in &["/a", "ftp://example.com/a", "http://"] {
+            match super::get_request(uri, &consumer, None, None) {
+                Err(crate::Error::InvalidUrl { url, .. }) => assert_eq!(&url, uri),
+                rsp => panic!("unexpected {:?}", rsp),
+            }
+        }
+    }
+
+    #[test]
+    fn status_error_body() {
+        let body = vec![b'a'; crate::ERROR_BODY_LIMIT + 1];
+        let mut headers = http::HeaderMap::new();
+        let _ = headers.insert("x-request-id", "1".parse().unwrap());
+        let status = http::StatusCode::BAD_REQUEST;
+        match super::check_status("http://example.com", status, &headers, &body) {
+            Err(crate::Error::Status(err)) => {
+                assert_eq!(err.status, 400);
+                assert_eq!(err.url, "http://example.com");
+                assert_eq!(err.headers["x-request-id"], "1");
+                assert_eq!(err.body.len(), crate::ERROR_BODY_LIMIT);
+            }
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+    }
+
+    #[test]
+    fn redirected() {
+        let mut headers = http::HeaderMap::new();
+        let _ = headers.insert("location", "/authorize?oauth_token=t".parse().unwrap());
+        let status = http::StatusCode::FOUND;
+        let err = super::check_status("https://example.com/a", status, &headers, b"").unwrap_err();
+        assert_eq!(err.status(), Some(302));
+        assert_eq!(
+            err.redirect_location(),
+            Some("https://example.com/authorize?oauth_token=t")
+        );
+    }
+
+    #[test]
+    fn rate_limited() {
+        use std::time::{Duration, UNIX_EPOCH};
+
+        let mut headers = http::HeaderMap::new();
+        let _ = headers.insert("x-rate-limit-remaining", "0".parse().unwrap());
+        let _ = headers.insert("x-rate-limit-reset", "1318622958".parse().unwrap());
+        let status = http::StatusCode::TOO_MANY_REQUESTS;
+        let err = super::check_status("http://example.com", status, &headers, b"").unwrap_err();
+        assert!(err.is_rate_limited());
+        assert_eq!(err.status(), Some(429));
+        match err {
+            crate::Error::RateLimited {
+                reset_at,
+                rate_limit,
+                ..
+            } => {
+                assert_eq!(reset_at, Some(UNIX_EPOCH + Duration::from_secs(1318622958)));
+                assert_eq!(rate_limit.remaining, Some(0));
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+    }
+}
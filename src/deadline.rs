@@ -0,0 +1,66 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Overall time budget of a request.
+
+use std::time::{Duration, Instant};
+
+/// Point in time by which a request, with all its attempts and the delays
+/// between them, must be complete.
+///
+/// Requests still running at the deadline fail with `Error::Timeout`, and no
+/// retry is scheduled past it. Not available on `wasm32`, which has no
+/// monotonic clock.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::Deadline;
+/// use std::time::Duration;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None);
+/// # #[cfg(feature = "client")]
+/// async {
+///     let bytes = session
+///         .clone()
+///         .deadline(Deadline::after(Duration::from_millis(500)))
+///         .get("http://oauthbin.com/v1/echo", None)
+///         .await;
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Deadline {
+    at: Instant,
+}
+
+impl Deadline {
+    /// The deadline at `at`
+    pub fn at(at: Instant) -> Deadline {
+        Deadline { at }
+    }
+
+    /// The deadline `budget` from now
+    pub fn after(budget: Duration) -> Deadline {
+        Deadline::at(Instant::now() + budget)
+    }
+
+    /// The instant of the deadline
+    pub fn instant(&self) -> Instant {
+        self.at
+    }
+
+    /// The time left before the deadline, zero once it passed
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    /// Whether the deadline passed
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Duration::ZERO
+    }
+}
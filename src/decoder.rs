@@ -0,0 +1,164 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Decoders of the provider error bodies.
+
+use http::{HeaderMap, StatusCode};
+use std::collections::HashMap;
+use std::fmt;
+
+/// An error reported by the provider in the body of an error response, in
+/// its own format.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProviderError {
+    /// Machine-readable error code
+    pub code: Option<String>,
+    /// Human-readable error message
+    pub message: Option<String>,
+    /// Any other fields of the error
+    pub fields: HashMap<String, String>,
+}
+
+/// Decoder of the bodies of error responses, attaching a
+/// [`ProviderError`](struct.ProviderError.html) to the returned
+/// [`StatusError`](../struct.StatusError.html).
+///
+/// Implemented for closures.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::decoder::ProviderError;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).error_decoder(
+///     |_status: http::StatusCode, _headers: &http::HeaderMap, body: &[u8]| {
+///         let body = std::str::from_utf8(body).ok()?;
+///         let code = body.strip_prefix("<error code=\"")?.split('"').next()?;
+///         Some(ProviderError {
+///             code: Some(code.to_string()),
+///             ..ProviderError::default()
+///         })
+///     },
+/// );
+/// ```
+pub trait ErrorDecoder: Send + Sync {
+    /// Decode the body of a response with an error `status`, or `None` when
+    /// it is not in the expected format
+    fn decode(&self, status: StatusCode, headers: &HeaderMap, body: &[u8])
+        -> Option<ProviderError>;
+}
+
+impl fmt::Debug for dyn ErrorDecoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ErrorDecoder")
+    }
+}
+
+impl<F> ErrorDecoder for F
+where
+    F: Fn(StatusCode, &HeaderMap, &[u8]) -> Option<ProviderError> + Send + Sync,
+{
+    fn decode(
+        &self,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Option<ProviderError> {
+        self(status, headers, body)
+    }
+}
+
+/// Decoder of form-encoded error bodies, taking the code and the message
+/// from `error` and `error_description` by default.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::decoder::Form;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None)
+///     .error_decoder(Form::new().code("errorCode").message("errorMessage"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Form {
+    code: String,
+    message: String,
+}
+
+impl Form {
+    /// Create new decoder reading `error` and `error_description`
+    pub fn new() -> Form {
+        Form {
+            code: "error".into(),
+            message: "error_description".into(),
+        }
+    }
+
+    /// Read the code from the field `name`
+    pub fn code<N: Into<String>>(mut self, name: N) -> Form {
+        self.code = name.into();
+        self
+    }
+
+    /// Read the message from the field `name`
+    pub fn message<N: Into<String>>(mut self, name: N) -> Form {
+        self.message = name.into();
+        self
+    }
+}
+
+impl Default for Form {
+    fn default() -> Form {
+        Form::new()
+    }
+}
+
+impl ErrorDecoder for Form {
+    fn decode(
+        &self,
+        _status: StatusCode,
+        _headers: &HeaderMap,
+        body: &[u8],
+    ) -> Option<ProviderError> {
+        let mut fields = crate::parse_form_body(body)
+            .ok()?
+            .into_iter()
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect::<HashMap<_, _>>();
+        let code = fields.remove(&self.code);
+        let message = fields.remove(&self.message);
+        if code.is_none() && message.is_none() {
+            return None;
+        }
+        Some(ProviderError {
+            code,
+            message,
+            fields,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorDecoder, Form};
+    use http::{HeaderMap, StatusCode};
+
+    #[test]
+    fn form() {
+        let status = StatusCode::BAD_REQUEST;
+        let body = b"error=invalid_request&error_description=bad+nonce&id=1";
+        let err = Form::new().decode(status, &HeaderMap::new(), body).unwrap();
+        assert_eq!(err.code.as_deref(), Some("invalid_request"));
+        assert_eq!(err.message.as_deref(), Some("bad nonce"));
+        assert_eq!(err.fields["id"], "1");
+        assert!(Form::new()
+            .decode(status, &HeaderMap::new(), b"<html>")
+            .is_none());
+    }
+}
@@ -0,0 +1,81 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! OAuth Echo, for delegating credential verification to a third party.
+
+use crate::core::get_header;
+use crate::{AccessToken, Config, ConsumerToken, Result};
+
+/// Name of the header carrying the verification endpoint
+pub const AUTH_SERVICE_PROVIDER: &str = "X-Auth-Service-Provider";
+
+/// Name of the header carrying the signed authorization for the endpoint
+pub const VERIFY_CREDENTIALS_AUTHORIZATION: &str = "X-Verify-Credentials-Authorization";
+
+/// Headers to send to the delegator in an OAuth Echo request.
+#[derive(Clone, Debug)]
+pub struct EchoHeaders {
+    /// Value of `X-Auth-Service-Provider`
+    pub service_provider: String,
+    /// Value of `X-Verify-Credentials-Authorization`
+    pub verify_credentials_authorization: String,
+}
+
+/// Create the OAuth Echo headers for `verify_credentials`, the provider
+/// endpoint the delegator will call with a signed GET request.
+///
+/// The authorization is signed with the default settings, not with the
+/// realm, quirks, clock or nonce provider of a session, and no request is
+/// sent.
+///
+/// # Examples
+///
+/// ```
+/// const VERIFY_CREDENTIALS: &str = "https://api.twitter.com/1.1/account/verify_credentials.json";
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let access = oauth_client::AccessToken::new("token", "secret");
+/// let headers = oauth_client::echo_headers(VERIFY_CREDENTIALS, &consumer, &access).unwrap();
+/// assert_eq!(headers.service_provider, VERIFY_CREDENTIALS);
+/// ```
+pub fn echo_headers(
+    verify_credentials: &str,
+    consumer: &ConsumerToken<'_>,
+    token: &AccessToken<'_>,
+) -> Result<EchoHeaders> {
+    let header = get_header(
+        "GET",
+        verify_credentials,
+        consumer,
+        Some(token),
+        None,
+        &Config::default(),
+    )?;
+    Ok(EchoHeaders {
+        service_provider: verify_credentials.to_string(),
+        verify_credentials_authorization: header.into_header(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::echo_headers;
+    use crate::{AccessToken, ConsumerToken};
+
+    #[test]
+    fn echo() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let access = AccessToken::new("token", "secret");
+        let headers = echo_headers("https://example.com/verify", &consumer, &access).unwrap();
+        assert_eq!(headers.service_provider, "https://example.com/verify");
+        assert!(headers
+            .verify_credentials_authorization
+            .starts_with("OAuth "));
+        assert!(headers
+            .verify_credentials_authorization
+            .contains("oauth_token=\"token\""));
+    }
+}
@@ -0,0 +1,537 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Error type of the crate.
+
+use crate::transport::redact_params;
+use crate::{ChallengeError, ProblemError, ProviderError, RateLimit};
+use http::{HeaderMap, Method};
+use std::borrow::Cow;
+use std::fmt;
+use std::time::{Duration, SystemTime};
+use thiserror::Error;
+
+/// Number of bytes of an error response body kept by
+/// [`StatusError`](struct.StatusError.html)
+pub const ERROR_BODY_LIMIT: usize = 4096;
+
+/// Error boxed by transports, such as a `reqwest::Error`.
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// An error happening when sending a request or running an OAuth flow.
+///
+/// The errors of the requests sent by a [`Session`](struct.Session.html) are
+/// wrapped in `Error::Request`, telling which request failed. The `is_*`
+/// methods look through it, and [`inner`](#method.inner) returns the wrapped
+/// error. Secrets and signatures never appear in the `Display` and `Debug`
+/// output of errors.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// An error of a request, with the request it happened on
+    #[error("{context}: {source}")]
+    Request {
+        /// The failed request
+        context: Box<RequestContext>,
+        /// The error
+        source: Box<Error>,
+    },
+    /// The HTTP client failed to send the request or to read the response
+    #[error("HTTP request failed: {0}")]
+    Http(#[source] BoxError),
+    /// The request or the response timed out
+    #[error("HTTP request timed out: {0}")]
+    Timeout(#[source] BoxError),
+    /// The connection to the provider could not be established, for example
+    /// because its name could not be resolved
+    #[error("could not connect: {0}")]
+    Connect(#[source] BoxError),
+    /// The TLS handshake failed, for example on an invalid certificate.
+    /// Transports unable to tell TLS failures apart report them as `Connect`.
+    #[error("TLS handshake failed: {0}")]
+    Tls(#[source] BoxError),
+    /// The provider answered with a status other than 200 OK, and no OAuth
+    /// problem or challenge
+    #[error(transparent)]
+    Status(#[from] Box<StatusError>),
+    /// The provider refused the request because of its quota, with status
+    /// 429 Too Many Requests or 420 Enhance Your Calm
+    #[error("rate limited by the provider (HTTP status {status})")]
+    RateLimited {
+        /// HTTP status code of the response
+        status: u16,
+        /// When requests are accepted again, from `Retry-After` or
+        /// `X-Rate-Limit-Reset`
+        reset_at: Option<SystemTime>,
+        /// The rate limit headers of the response
+        rate_limit: RateLimit,
+    },
+    /// The request was not sent because the circuit breaker of the session
+    /// is open after repeated failures of the provider
+    #[error("circuit breaker is open")]
+    CircuitOpen {
+        /// When probe requests are let through again, or `None` while probes
+        /// are in flight
+        until: Option<SystemTime>,
+    },
+    /// The request was cancelled with its
+    /// [`CancellationToken`](struct.CancellationToken.html)
+    #[error("request was cancelled")]
+    Cancelled,
+    /// The response body is larger than the limit set on the session
+    #[error("response body exceeds {limit} bytes")]
+    BodyTooLarge {
+        /// The maximum size of the body, in bytes
+        limit: usize,
+    },
+    /// The provider redirected the request, with status 301, 302, 303, 307
+    /// or 308, and the transport does not follow redirects. See
+    /// [`ClientOptions::follow_redirects`](transport/struct.ClientOptions.html#method.follow_redirects).
+    #[error("redirected to {location} (HTTP status {status})")]
+    Redirected {
+        /// HTTP status code of the response
+        status: u16,
+        /// The `Location` header, resolved against the URL of the response
+        location: String,
+    },
+    /// The provider reported an `oauth_problem`
+    #[error(transparent)]
+    Problem(#[from] Box<ProblemError>),
+    /// The provider rejected the request with an OAuth challenge, but no
+    /// `oauth_problem`
+    #[error(transparent)]
+    Challenge(#[from] Box<ChallengeError>),
+    /// A signature differs from the expected one
+    #[error("signature mismatch: {0}")]
+    Signature(String),
+    /// A URL is not an absolute `http` or `https` URL, and cannot be signed
+    #[error("invalid URL `{url}`: {reason}")]
+    InvalidUrl {
+        /// The rejected URL
+        url: String,
+        /// Why it was rejected
+        reason: String,
+    },
+    /// A key or a secret given to
+    /// [`Token::try_new`](struct.Token.html#method.try_new) would produce an
+    /// invalid request
+    #[error("invalid token {field}: {reason}")]
+    InvalidCredential {
+        /// `key` or `secret`
+        field: &'static str,
+        /// Why it was rejected
+        reason: String,
+    },
+    /// The `Authorization` header cannot be built, such as with a realm
+    /// containing control characters
+    #[error("invalid `Authorization` header: {0}")]
+    InvalidHeader(String),
+    /// An environment variable read by
+    /// [`Token::from_env`](struct.Token.html#method.from_env) is missing or
+    /// not valid unicode
+    #[error("cannot read `{name}`: {source}")]
+    Env {
+        /// Name of the variable
+        name: String,
+        /// Why it could not be read
+        #[source]
+        source: std::env::VarError,
+    },
+    /// A value could not be serialized into the parameters of a request, see
+    /// [`ParamList::from_form`](struct.ParamList.html#method.from_form)
+    #[error("cannot serialize the parameters: {0}")]
+    Serialize(#[source] BoxError),
+    /// A response body could not be deserialized, see
+    /// [`get_json`](fn.get_json.html)
+    #[error("cannot deserialize the response: {0}")]
+    Deserialize(#[source] BoxError),
+    /// A response has another content type than the one expected, such as a
+    /// HTML error page instead of JSON. Empty when the response has none.
+    #[error("unexpected content type `{0}`")]
+    UnexpectedContentType(String),
+    /// A string is not correctly percent-encoded
+    #[error("invalid percent-encoding: {0}")]
+    InvalidEncoding(String),
+    /// A required parameter is missing from a response
+    #[error("missing parameter `{0}` in the response")]
+    MissingParameter(String),
+    /// A request-token response lacks `oauth_callback_confirmed=true`, i.e. the
+    /// provider does not speak OAuth 1.0a
+    #[error("callback was not confirmed by the provider")]
+    CallbackNotConfirmed,
+    /// A response or a redirect carries another token than the one sent
+    #[error("unexpected `oauth_token` in the response")]
+    UnexpectedToken,
+    /// The user denied the authorization
+    #[error("authorization was denied by the user")]
+    Denied,
+    /// No transport was given and the `reqwest` feature is disabled
+    #[error("no HTTP transport available")]
+    NoTransport,
+    /// A [`TokenStore`](store/trait.TokenStore.html) failed, other than on an
+    /// I/O error
+    #[error("token store failed: {0}")]
+    Store(#[source] BoxError),
+    /// A [`TokenRegistry`](store/struct.TokenRegistry.html) has no token for
+    /// the account
+    #[error("no token for account `{0}`")]
+    UnknownAccount(String),
+    /// An I/O error, such as a failure of the loopback listener
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl Error {
+    /// Wrap an error of the HTTP client
+    pub fn http<E: Into<BoxError>>(err: E) -> Error {
+        Error::Http(err.into())
+    }
+
+    pub(crate) fn invalid_url<R: ToString>(url: &str, reason: R) -> Error {
+        Error::InvalidUrl {
+            url: redact_params(url),
+            reason: reason.to_string(),
+        }
+    }
+
+    #[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+    pub(crate) fn deadline_exceeded() -> Error {
+        Error::Timeout("deadline exceeded".into())
+    }
+
+    pub(crate) fn invalid_credential<R: ToString>(field: &'static str, reason: R) -> Error {
+        Error::InvalidCredential {
+            field,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Wrap the error with the request it happened on, unless it is wrapped
+    /// already
+    #[cfg(feature = "client")]
+    pub(crate) fn with_context(self, context: RequestContext) -> Error {
+        match self {
+            err @ Error::Request { .. } => err,
+            err => Error::Request {
+                context: Box::new(context),
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// The request the error happened on, if known
+    pub fn context(&self) -> Option<&RequestContext> {
+        match self {
+            Error::Request { context, .. } => Some(context),
+            _ => None,
+        }
+    }
+
+    /// The error without the request it happened on
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Request { source, .. } => source.inner(),
+            err => err,
+        }
+    }
+
+    /// The error without the request it happened on
+    pub fn into_inner(self) -> Error {
+        match self {
+            Error::Request { source, .. } => source.into_inner(),
+            err => err,
+        }
+    }
+
+    /// Whether the request or the response timed out
+    pub fn is_timeout(&self) -> bool {
+        matches!(self.inner(), Error::Timeout(_))
+    }
+
+    /// Whether the connection could not be established, including TLS
+    /// handshake failures
+    pub fn is_connect(&self) -> bool {
+        matches!(self.inner(), Error::Connect(_) | Error::Tls(_))
+    }
+
+    /// Whether the TLS handshake failed
+    pub fn is_tls(&self) -> bool {
+        matches!(self.inner(), Error::Tls(_))
+    }
+
+    /// Whether the provider answered with an error status
+    pub fn is_status(&self) -> bool {
+        self.status().is_some()
+    }
+
+    /// The target of a redirect not followed by the transport
+    pub fn redirect_location(&self) -> Option<&str> {
+        match self.inner() {
+            Error::Redirected { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// Whether the provider refused the request because of its quota
+    pub fn is_rate_limited(&self) -> bool {
+        matches!(self.inner(), Error::RateLimited { .. })
+    }
+
+    /// Whether the request was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self.inner(), Error::Cancelled)
+    }
+
+    /// Whether the request failed fast because of an open circuit breaker
+    pub fn is_circuit_open(&self) -> bool {
+        matches!(self.inner(), Error::CircuitOpen { .. })
+    }
+
+    /// The delay requested by the `Retry-After` header of a 429 Too Many
+    /// Requests, 420 Enhance Your Calm or 503 Service Unavailable response
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.inner() {
+            Error::RateLimited { rate_limit, .. } => rate_limit.retry_after,
+            Error::Status(err) if err.status == 503 => {
+                RateLimit::from_headers(&err.headers).retry_after
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the request could not be signed, or a signature did not match
+    pub fn is_signature(&self) -> bool {
+        matches!(self.inner(), Error::Signature(_) | Error::InvalidUrl { .. })
+    }
+
+    /// The HTTP status of the response, for errors caused by one
+    pub fn status(&self) -> Option<u16> {
+        match self.inner() {
+            Error::Status(err) => Some(err.status),
+            Error::RateLimited { status, .. } => Some(*status),
+            Error::Redirected { status, .. } => Some(*status),
+            Error::Problem(err) => Some(err.status),
+            Error::Challenge(err) => Some(err.status),
+            _ => None,
+        }
+    }
+}
+
+impl From<StatusError> for Error {
+    fn from(err: StatusError) -> Error {
+        Error::Status(Box::new(err))
+    }
+}
+
+impl From<ProblemError> for Error {
+    fn from(err: ProblemError) -> Error {
+        Error::Problem(Box::new(err))
+    }
+}
+
+impl From<ChallengeError> for Error {
+    fn from(err: ChallengeError) -> Error {
+        Error::Challenge(Box::new(err))
+    }
+}
+
+/// A request that failed, without its credentials.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RequestContext {
+    /// HTTP method
+    pub method: Method,
+    /// URL, with the values of `oauth_token`, `oauth_token_secret`,
+    /// `oauth_verifier` and `oauth_signature` redacted
+    pub url: String,
+    /// Number of the attempt that failed, from 1
+    pub attempt: u32,
+}
+
+impl RequestContext {
+    #[cfg(feature = "client")]
+    pub(crate) fn new(request: &crate::SignedRequest, attempt: u32) -> RequestContext {
+        RequestContext {
+            method: request.method.clone(),
+            url: redact_params(&request.url),
+            attempt,
+        }
+    }
+}
+
+impl fmt::Display for RequestContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} (attempt {})", self.method, self.url, self.attempt)
+    }
+}
+
+/// An error happening when the provider answers with a status other than
+/// 200 OK.
+///
+/// Its `Debug` output redacts the secrets of the body, which the body
+/// itself keeps.
+#[derive(Error, Clone)]
+#[error("HTTP status error code {status} from {url}")]
+pub struct StatusError {
+    /// HTTP status code of the response
+    pub status: u16,
+    /// The URL of the response, after any redirect
+    pub url: String,
+    /// Headers of the response
+    pub headers: HeaderMap,
+    /// The first [`ERROR_BODY_LIMIT`](constant.ERROR_BODY_LIMIT.html) bytes of
+    /// the body of the response
+    pub body: Vec<u8>,
+    /// The error decoded from the body by the
+    /// [`ErrorDecoder`](decoder/trait.ErrorDecoder.html) of the session, if any
+    pub provider: Option<ProviderError>,
+}
+
+impl StatusError {
+    pub(crate) fn new(status: u16, url: &str, headers: &HeaderMap, body: &[u8]) -> StatusError {
+        StatusError {
+            status,
+            url: redact_params(url),
+            headers: headers.clone(),
+            body: body[..body.len().min(ERROR_BODY_LIMIT)].to_vec(),
+            provider: None,
+        }
+    }
+
+    /// The body of the response as text, replacing invalid UTF-8
+    pub fn body_text(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.body)
+    }
+}
+
+impl fmt::Debug for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatusError")
+            .field("status", &self.status)
+            .field("url", &self.url)
+            .field("headers", &self.headers)
+            .field("body", &redact_params(&self.body_text()))
+            .field("provider", &self.provider)
+            .finish()
+    }
+}
+
+/// The message of `err`, without secrets, for the errors of the HTTP clients
+/// including the URL of the request
+#[cfg(any(feature = "ureq", feature = "surf", feature = "isahc"))]
+pub(crate) fn redacted<E: fmt::Display>(err: E) -> BoxError {
+    redact_params(&err.to_string()).into()
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for Error {
+    fn from(err: reqwest::Error) -> Error {
+        // The URL may carry the signature, and is in `Error::Request`.
+        let err = err.without_url();
+        if err.is_timeout() {
+            return Error::Timeout(err.into());
+        }
+        // Connection errors are not told apart by the browser `fetch`.
+        #[cfg(not(target_arch = "wasm32"))]
+        if err.is_connect() {
+            return Error::Connect(err.into());
+        }
+        Error::http(err)
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl From<hyper::Error> for Error {
+    fn from(err: hyper::Error) -> Error {
+        if err.is_timeout() {
+            Error::Timeout(err.into())
+        } else if err.is_connect() {
+            Error::Connect(err.into())
+        } else {
+            Error::http(err)
+        }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl From<ureq::Error> for Error {
+    fn from(err: ureq::Error) -> Error {
+        use std::error::Error as _;
+
+        let timeout = err
+            .source()
+            .and_then(|source| source.downcast_ref::<std::io::Error>())
+            .is_some_and(|source| {
+                matches!(
+                    source.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                )
+            });
+        // The message includes the URL, which may carry the signature.
+        match err.kind() {
+            _ if timeout => Error::Timeout(redacted(err)),
+            ureq::ErrorKind::Dns | ureq::ErrorKind::ConnectionFailed => {
+                Error::Connect(redacted(err))
+            }
+            _ => Error::Http(redacted(err)),
+        }
+    }
+}
+
+#[cfg(feature = "isahc")]
+impl From<isahc::Error> for Error {
+    fn from(err: isahc::Error) -> Error {
+        // The message may include the URL, which may carry the signature.
+        if err.is_timeout() {
+            Error::Timeout(redacted(err))
+        } else if err.is_tls() {
+            Error::Tls(redacted(err))
+        } else if err.is_network() {
+            Error::Connect(redacted(err))
+        } else {
+            Error::Http(redacted(err))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Error, StatusError};
+
+    #[test]
+    fn classify_status() {
+        let err: Error =
+            StatusError::new(503, "http://example.com", &Default::default(), b"").into();
+        assert!(err.is_status());
+        assert!(!err.is_timeout());
+        assert!(!err.is_connect());
+        assert!(Error::Signature("sig".into()).is_signature());
+    }
+
+    #[test]
+    fn redacted() {
+        let url = "http://example.com/?oauth_token=t&oauth_signature=s";
+        let body = b"oauth_token=t&oauth_token_secret=ts";
+        let err: Error = StatusError::new(201, url, &Default::default(), body).into();
+        let output = format!("{} {:?}", err, err);
+        assert!(!output.contains("=t&") && !output.contains("=s") && !output.contains("ts"));
+        assert!(output.contains("oauth_token_secret=REDACTED"));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "reqwest")]
+    async fn classify_connect() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let err: Error = reqwest::get(format!("http://{}", addr))
+            .await
+            .unwrap_err()
+            .into();
+        assert!(err.is_connect());
+        assert!(!err.is_status());
+    }
+}
@@ -0,0 +1,846 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Helpers for the token exchange steps of the OAuth flow.
+
+#[cfg(not(feature = "json"))]
+use crate::content_type;
+use crate::core::{encode, insert_param, parse_form_body};
+#[cfg(feature = "json")]
+use crate::from_json;
+use crate::transport::Response;
+use crate::{
+    is_json, post_response_with, post_with, AccessToken, Config, ConsumerToken, Error, ParamList,
+    Result, Session, Token,
+};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Callback value for applications that cannot receive a redirect.
+///
+/// The provider then displays a PIN to the user, which is used as the verifier.
+pub const OOB_CALLBACK: &str = "oob";
+
+/// Temporary credentials returned by the request-token endpoint.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RequestTokenResponse {
+    /// The request token and its secret
+    pub token: AccessToken<'static>,
+    /// Value of `oauth_callback_confirmed` in the response
+    ///
+    /// Responses without `oauth_callback_confirmed=true` are rejected with
+    /// [`Error::CallbackNotConfirmed`](enum.Error.html#variant.CallbackNotConfirmed).
+    pub callback_confirmed: bool,
+    /// Any other parameters in the response
+    pub extra: HashMap<String, String>,
+}
+
+impl RequestTokenResponse {
+    /// Parse the form-encoded body of a request-token response
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let body = b"oauth_token=t&oauth_token_secret=s&oauth_callback_confirmed=true";
+    /// let response = oauth_client::RequestTokenResponse::from_body(body).unwrap();
+    /// assert_eq!(response.token.key, "t");
+    /// ```
+    pub fn from_body(body: &[u8]) -> Result<RequestTokenResponse> {
+        let mut extra = parse_response(body)?;
+        let token = take_token(&mut extra)?;
+        let callback_confirmed =
+            extra.remove("oauth_callback_confirmed").as_deref() == Some("true");
+        if !callback_confirmed {
+            return Err(Error::CallbackNotConfirmed);
+        }
+        Ok(RequestTokenResponse {
+            token,
+            callback_confirmed,
+            extra,
+        })
+    }
+}
+
+/// Token credentials returned by the access-token endpoint.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AccessTokenResponse {
+    /// The access token and its secret
+    pub token: AccessToken<'static>,
+    /// Any other parameters in the response, such as `user_id` or `screen_name`
+    pub extra: HashMap<String, String>,
+}
+
+impl AccessTokenResponse {
+    /// Parse the form-encoded body of an access-token response
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let body = b"oauth_token=t&oauth_token_secret=s&screen_name=rust";
+    /// let response = oauth_client::AccessTokenResponse::from_body(body).unwrap();
+    /// assert_eq!(response.extra["screen_name"], "rust");
+    /// ```
+    pub fn from_body(body: &[u8]) -> Result<AccessTokenResponse> {
+        let mut extra = parse_response(body)?;
+        let token = take_token(&mut extra)?;
+        Ok(AccessTokenResponse { token, extra })
+    }
+
+    /// Value of `oauth_session_handle`, used to refresh an expiring token
+    pub fn session_handle(&self) -> Option<&str> {
+        self.extra.get("oauth_session_handle").map(String::as_str)
+    }
+
+    /// Lifetime of the token, from `oauth_expires_in`
+    pub fn expires_in(&self) -> Option<Duration> {
+        self.seconds("oauth_expires_in")
+    }
+
+    /// Lifetime of the session handle, from `oauth_authorization_expires_in`
+    pub fn authorization_expires_in(&self) -> Option<Duration> {
+        self.seconds("oauth_authorization_expires_in")
+    }
+
+    fn seconds(&self, name: &str) -> Option<Duration> {
+        self.extra
+            .get(name)
+            .and_then(|v| v.parse().ok())
+            .map(Duration::from_secs)
+    }
+}
+
+/// Obtain a request token from `endpoint`.
+/// `consumer` is a consumer token and `callback` is sent as `oauth_callback`.
+///
+/// Sent with the default settings and transport, which requires the `reqwest`
+/// feature; [`Session::obtain_request_token`](struct.Session.html#method.obtain_request_token)
+/// sends it with those of a session.
+///
+/// # Examples
+///
+/// ```
+/// async {
+///     const REQUEST_TOKEN: &str = "http://oauthbin.com/v1/request-token";
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let request = oauth_client::obtain_request_token(REQUEST_TOKEN, &consumer, "oob")
+///         .await
+///         .unwrap();
+///     println!("{}", request.token.key);
+/// };
+/// ```
+pub async fn obtain_request_token(
+    endpoint: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    callback: &str,
+) -> Result<RequestTokenResponse> {
+    let endpoint = endpoint.as_ref();
+    obtain_request_token_with(endpoint, consumer, callback, &Config::default()).await
+}
+
+pub(crate) async fn obtain_request_token_with(
+    endpoint: &str,
+    consumer: &Token<'_>,
+    callback: &str,
+    config: &Config,
+) -> Result<RequestTokenResponse> {
+    let mut param = ParamList::new();
+    let _ = insert_param(&mut param, "oauth_callback", callback);
+    let bytes = post_with(endpoint, consumer, None, Some(&param), config).await?;
+    RequestTokenResponse::from_body(&bytes)
+}
+
+/// Exchange an authorized request token for an access token at `endpoint`.
+/// `consumer` is a consumer token and `verifier` is sent as `oauth_verifier`.
+///
+/// Sent with the default settings and transport, which requires the `reqwest`
+/// feature; [`Session::exchange_access_token`](struct.Session.html#method.exchange_access_token)
+/// sends it with those of a session.
+///
+/// # Examples
+///
+/// ```
+/// # let request = oauth_client::AccessToken::new("key", "secret");
+/// async {
+///     const ACCESS_TOKEN: &str = "http://oauthbin.com/v1/access-token";
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let access = oauth_client::exchange_access_token(ACCESS_TOKEN, &consumer, &request, "verifier")
+///         .await
+///         .unwrap();
+///     println!("{:?}", access.extra.get("screen_name"));
+/// };
+/// ```
+///
+/// The consumer token can't be passed as the request token:
+///
+/// ```compile_fail
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let _ = oauth_client::exchange_access_token("http://a", &consumer, &consumer, "verifier");
+/// ```
+pub async fn exchange_access_token(
+    endpoint: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    request_token: &AccessToken<'_>,
+    verifier: &str,
+) -> Result<AccessTokenResponse> {
+    let endpoint = endpoint.as_ref();
+    let config = Config::default();
+    exchange_access_token_with(endpoint, consumer, request_token, verifier, &config).await
+}
+
+pub(crate) async fn exchange_access_token_with(
+    endpoint: &str,
+    consumer: &Token<'_>,
+    request_token: &Token<'_>,
+    verifier: &str,
+    config: &Config,
+) -> Result<AccessTokenResponse> {
+    let mut param = ParamList::new();
+    let _ = insert_param(&mut param, "oauth_verifier", verifier);
+    let bytes = post_with(
+        endpoint,
+        consumer,
+        Some(request_token),
+        Some(&param),
+        config,
+    )
+    .await?;
+    AccessTokenResponse::from_body(&bytes)
+}
+
+/// Refresh an expiring access token at `endpoint` (ScalableOAuth extension).
+/// `consumer` is a consumer token, `access_token` the expired token and
+/// `session_handle` is sent as `oauth_session_handle`.
+///
+/// Sent with the default settings and transport, which requires the `reqwest`
+/// feature; [`Session::refresh_access_token`](struct.Session.html#method.refresh_access_token)
+/// sends it with those of a session.
+///
+/// # Examples
+///
+/// ```
+/// # let access = oauth_client::AccessToken::new("key", "secret");
+/// async {
+///     const ACCESS_TOKEN: &str = "https://api.login.yahoo.com/oauth/v2/get_token";
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let refreshed = oauth_client::refresh_access_token(ACCESS_TOKEN, &consumer, &access, "handle")
+///         .await
+///         .unwrap();
+///     println!("{:?}", refreshed.expires_in());
+/// };
+/// ```
+pub async fn refresh_access_token(
+    endpoint: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    access_token: &AccessToken<'_>,
+    session_handle: &str,
+) -> Result<AccessTokenResponse> {
+    let endpoint = endpoint.as_ref();
+    let config = Config::default();
+    refresh_access_token_with(endpoint, consumer, access_token, session_handle, &config).await
+}
+
+pub(crate) async fn refresh_access_token_with(
+    endpoint: &str,
+    consumer: &Token<'_>,
+    access_token: &Token<'_>,
+    session_handle: &str,
+    config: &Config,
+) -> Result<AccessTokenResponse> {
+    let mut param = ParamList::new();
+    let _ = insert_param(&mut param, "oauth_session_handle", session_handle);
+    let bytes = post_with(endpoint, consumer, Some(access_token), Some(&param), config).await?;
+    AccessTokenResponse::from_body(&bytes)
+}
+
+/// Invalidate `token` at the provider's revocation `endpoint`, such as
+/// `oauth/invalidate_token`. `consumer` is a consumer token.
+///
+/// Succeeds when the provider accepts the request and its response names
+/// `token` as the revoked token, as `oauth_token` or `access_token` in a
+/// form-encoded body or, with the `json` feature, in a JSON object such as
+/// `{"access_token":"..."}`.
+///
+/// Sent with the default settings and transport, which requires the `reqwest`
+/// feature; [`Session::invalidate_token`](struct.Session.html#method.invalidate_token)
+/// sends it with those of a session.
+///
+/// # Examples
+///
+/// ```
+/// # let access = oauth_client::AccessToken::new("key", "secret");
+/// async {
+///     const INVALIDATE: &str = "https://api.twitter.com/1.1/oauth/invalidate_token";
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     oauth_client::invalidate_token(INVALIDATE, &consumer, &access).await.unwrap();
+/// };
+/// ```
+pub async fn invalidate_token(
+    endpoint: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: &AccessToken<'_>,
+) -> Result<()> {
+    let endpoint = endpoint.as_ref();
+    invalidate_token_with(endpoint, consumer, token, &Config::default()).await
+}
+
+pub(crate) async fn invalidate_token_with(
+    endpoint: &str,
+    consumer: &Token<'_>,
+    token: &Token<'_>,
+    config: &Config,
+) -> Result<()> {
+    let rsp = post_response_with(endpoint, consumer, Some(token), None, config).await?;
+    check_invalidated(&rsp, token)
+}
+
+fn check_invalidated(response: &Response, token: &Token<'_>) -> Result<()> {
+    let revoked = if is_json(response) {
+        revoked_json(response)?
+    } else {
+        let mut param = parse_response(&response.body)?;
+        param
+            .remove("oauth_token")
+            .or_else(|| param.remove("access_token"))
+    };
+    match revoked {
+        Some(key) if key == token.key => Ok(()),
+        Some(_) => Err(Error::UnexpectedToken),
+        None => Err(Error::MissingParameter("oauth_token".into())),
+    }
+}
+
+/// The token named by the JSON body of a revocation response
+#[cfg(feature = "json")]
+fn revoked_json(response: &Response) -> Result<Option<String>> {
+    #[derive(serde::Deserialize)]
+    struct Revoked {
+        oauth_token: Option<String>,
+        access_token: Option<String>,
+    }
+
+    let revoked: Revoked = from_json(response)?;
+    Ok(revoked.oauth_token.or(revoked.access_token))
+}
+
+#[cfg(not(feature = "json"))]
+fn revoked_json(response: &Response) -> Result<Option<String>> {
+    Err(Error::UnexpectedContentType(
+        content_type(response).to_string(),
+    ))
+}
+
+/// Build the URL to redirect the user to for authorizing `request_token`.
+/// `other_param` is appended to the query, e.g. `force_login` or `perms`.
+///
+/// # Examples
+///
+/// ```
+/// const AUTHORIZE: &str = "https://api.twitter.com/oauth/authorize";
+/// let request = oauth_client::AccessToken::new("a b", "secret");
+/// let url = oauth_client::authorize_url(AUTHORIZE, &request, None);
+/// assert_eq!(url, "https://api.twitter.com/oauth/authorize?oauth_token=a%20b");
+/// ```
+pub fn authorize_url(
+    endpoint: impl AsRef<str>,
+    request_token: &AccessToken<'_>,
+    other_param: Option<&ParamList<'_>>,
+) -> String {
+    let endpoint = endpoint.as_ref();
+    let mut pairs = vec![format!("oauth_token={}", encode(&request_token.key))];
+    if let Some(ps) = other_param {
+        let mut other = ps
+            .iter()
+            .filter(|&(k, _)| k != "oauth_token")
+            .map(|(k, v)| format!("{}={}", encode(k), encode(v)))
+            .collect::<Vec<_>>();
+        other.sort();
+        pairs.extend(other);
+    }
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", endpoint, separator, pairs.join("&"))
+}
+
+/// Endpoints of an OAuth provider.
+#[derive(Clone, Debug)]
+pub struct Provider<'a> {
+    /// URL of the request-token endpoint
+    pub request_token: Cow<'a, str>,
+    /// URL the user is redirected to for authorization
+    pub authorize: Cow<'a, str>,
+    /// URL of the access-token endpoint
+    pub access_token: Cow<'a, str>,
+}
+
+impl<'a> Provider<'a> {
+    /// Create new provider from its three endpoints
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let provider = oauth_client::Provider::new(
+    ///     "https://api.twitter.com/oauth/request_token",
+    ///     "https://api.twitter.com/oauth/authorize",
+    ///     "https://api.twitter.com/oauth/access_token",
+    /// );
+    /// ```
+    pub fn new<R, U, A>(request_token: R, authorize: U, access_token: A) -> Provider<'a>
+    where
+        R: Into<Cow<'a, str>>,
+        U: Into<Cow<'a, str>>,
+        A: Into<Cow<'a, str>>,
+    {
+        Provider {
+            request_token: request_token.into(),
+            authorize: authorize.into(),
+            access_token: access_token.into(),
+        }
+    }
+}
+
+/// State of a flow waiting for the user to authorize the request token.
+///
+/// It can be stored between the redirect round-trips with
+/// [`to_state_string`](#method.to_state_string) and restored with `parse()`,
+/// or serialized with the `serde` feature. Both carry the secret of the
+/// request token, which `Display` and `Debug` leave out.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FlowState {
+    /// The request token waiting for authorization
+    pub request_token: AccessToken<'static>,
+    /// URL the user must be redirected to
+    pub authorize_url: String,
+}
+
+impl FlowState {
+    /// The state as a string restored with `parse()`, including the secret
+    /// of the request token
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let state = oauth_client::FlowState {
+    ///     request_token: oauth_client::AccessToken::new("tok", "hunter2"),
+    ///     authorize_url: "https://example.com/authorize?oauth_token=tok".into(),
+    /// };
+    /// let saved = state.to_state_string();
+    /// let restored: oauth_client::FlowState = saved.parse().unwrap();
+    /// assert_eq!(restored.request_token.secret, "hunter2");
+    /// assert!(!state.to_string().contains("hunter2"));
+    /// ```
+    pub fn to_state_string(&self) -> String {
+        self.encode(&self.request_token.secret)
+    }
+
+    fn encode(&self, secret: &str) -> String {
+        url::form_urlencoded::Serializer::new(String::new())
+            .append_pair("oauth_token", &self.request_token.key)
+            .append_pair("oauth_token_secret", secret)
+            .append_pair("authorize_url", &self.authorize_url)
+            .finish()
+    }
+}
+
+/// The state with the secret of the request token replaced by `***`
+impl fmt::Display for FlowState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.encode("***"))
+    }
+}
+
+impl FromStr for FlowState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<FlowState> {
+        let mut param = parse_response(s.as_bytes())?;
+        let request_token = take_token(&mut param)?;
+        let authorize_url = take(&mut param, "authorize_url")?;
+        Ok(FlowState {
+            request_token,
+            authorize_url,
+        })
+    }
+}
+
+/// Three-legged OAuth flow against a [`Provider`](struct.Provider.html).
+///
+/// # Examples
+///
+/// ```
+/// async {
+///     let provider = oauth_client::Provider::new(
+///         "http://oauthbin.com/v1/request-token",
+///         "http://oauthbin.com/v1/authorize",
+///         "http://oauthbin.com/v1/access-token",
+///     );
+///     let flow = oauth_client::OAuthFlow::new(provider, oauth_client::ConsumerToken::new("key", "secret"));
+///
+///     let state = flow.start("https://example.com/callback").await.unwrap();
+///     let saved = state.to_state_string();
+///     // ... redirect the user to `state.authorize_url` and wait for the callback ...
+///
+///     let state: oauth_client::FlowState = saved.parse().unwrap();
+///     let access = flow.finish(&state, "verifier").await.unwrap();
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct OAuthFlow<'a> {
+    provider: Provider<'a>,
+    session: Session,
+}
+
+impl<'a> OAuthFlow<'a> {
+    /// Create new flow for `consumer` against `provider`
+    pub fn new(provider: Provider<'a>, consumer: ConsumerToken<'_>) -> OAuthFlow<'a> {
+        OAuthFlow {
+            provider,
+            session: Session::new(consumer, None),
+        }
+    }
+
+    /// Send the requests of the flow with the settings of `session`, such as
+    /// its transport, realm, quirks, clock, retry policy or audit hook, and
+    /// sign them with its consumer. The token of `session` is not used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer.clone(), None).realm("Example");
+    /// let flow = oauth_client::OAuthFlow::new(
+    ///     oauth_client::Provider::new("http://a", "http://b", "http://c"),
+    ///     consumer,
+    /// )
+    /// .session(session);
+    /// ```
+    pub fn session(mut self, session: Session) -> OAuthFlow<'a> {
+        self.session = session;
+        self
+    }
+
+    /// Obtain a request token and build the authorization URL for it
+    pub async fn start(&self, callback: &str) -> Result<FlowState> {
+        let request = obtain_request_token_with(
+            &self.provider.request_token,
+            self.session.consumer(),
+            callback,
+            self.session.config(),
+        )
+        .await?;
+        let authorize_url = authorize_url(&self.provider.authorize, &request.token, None);
+        Ok(FlowState {
+            request_token: request.token,
+            authorize_url,
+        })
+    }
+
+    /// Exchange the authorized request token in `state` for an access token
+    pub async fn finish(&self, state: &FlowState, verifier: &str) -> Result<AccessTokenResponse> {
+        exchange_access_token_with(
+            &self.provider.access_token,
+            self.session.consumer(),
+            &state.request_token,
+            verifier,
+            self.session.config(),
+        )
+        .await
+    }
+
+    /// Refresh `access`, which must carry an `oauth_session_handle`
+    pub async fn refresh(&self, access: &AccessTokenResponse) -> Result<AccessTokenResponse> {
+        let session_handle = access
+            .session_handle()
+            .ok_or_else(|| Error::MissingParameter("oauth_session_handle".into()))?;
+        refresh_access_token_with(
+            &self.provider.access_token,
+            self.session.consumer(),
+            &access.token,
+            session_handle,
+            self.session.config(),
+        )
+        .await
+    }
+
+    /// Start an out-of-band flow, for applications without a redirect URL
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # let flow = oauth_client::OAuthFlow::new(
+    /// #     oauth_client::Provider::new("http://a", "http://b", "http://c"),
+    /// #     oauth_client::ConsumerToken::new("key", "secret"),
+    /// # );
+    /// async {
+    ///     let state = flow.start_oob().await.unwrap();
+    ///     println!("Open {} and enter the PIN:", state.authorize_url);
+    ///     let mut pin = String::new();
+    ///     std::io::stdin().read_line(&mut pin).unwrap();
+    ///     let access = flow.finish_with_pin(&state, &pin).await.unwrap();
+    /// };
+    /// ```
+    pub async fn start_oob(&self) -> Result<FlowState> {
+        self.start(OOB_CALLBACK).await
+    }
+
+    /// Finish an out-of-band flow with the PIN typed by the user
+    pub async fn finish_with_pin(
+        &self,
+        state: &FlowState,
+        pin: &str,
+    ) -> Result<AccessTokenResponse> {
+        self.finish(state, pin.trim()).await
+    }
+}
+
+/// Decode a form-encoded response body, rejecting malformed encoding as
+/// [`parse_form_body`](fn.parse_form_body.html) does
+fn parse_response(bytes: &[u8]) -> Result<HashMap<String, String>> {
+    let param = parse_form_body(bytes)?;
+    Ok(param
+        .into_iter()
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect())
+}
+
+/// Remove `oauth_token` and `oauth_token_secret` from `param`
+fn take_token(param: &mut HashMap<String, String>) -> Result<AccessToken<'static>> {
+    let key = take(param, "oauth_token")?;
+    let secret = take(param, "oauth_token_secret")?;
+    Ok(AccessToken::new(key, secret))
+}
+
+fn take(param: &mut HashMap<String, String>, name: &str) -> Result<String> {
+    param
+        .remove(name)
+        .ok_or_else(|| Error::MissingParameter(name.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        authorize_url, check_invalidated, AccessTokenResponse, FlowState, RequestTokenResponse,
+    };
+    use crate::transport::Response;
+    use crate::{AccessToken, Error, ParamList, Token};
+    use http::header::{HeaderValue, CONTENT_TYPE};
+    use std::time::Duration;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let body = b"oauth_token=t&oauth_token_secret=s&screen_name=rust";
+        let response = AccessTokenResponse::from_body(body).unwrap();
+        let json = serde_json::to_string(&response).unwrap();
+        assert_eq!(
+            json,
+            r#"{"token":{"key":"t","secret":"s"},"extra":{"screen_name":"rust"}}"#
+        );
+        let response: AccessTokenResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(response.token.secret, "s");
+
+        let state = FlowState {
+            request_token: AccessToken::new("tok", "s"),
+            authorize_url: "https://example.com/authorize?oauth_token=tok".into(),
+        };
+        let json = serde_json::to_string(&state).unwrap();
+        let state: FlowState = serde_json::from_str(&json).unwrap();
+        assert_eq!(state.request_token.secret, "s");
+    }
+
+    #[test]
+    fn request_token() {
+        let body = b"oauth_token=abc&oauth_token_secret=s%2Bs&oauth_callback_confirmed=true\
+                     &xoauth_request_auth_url=https%3A%2F%2Fexample.com";
+        let request = RequestTokenResponse::from_body(body).unwrap();
+        assert_eq!(request.token.key, "abc");
+        assert_eq!(request.token.secret, "s+s");
+        assert!(request.callback_confirmed);
+        assert_eq!(request.extra.len(), 1);
+        assert_eq!(
+            request.extra["xoauth_request_auth_url"],
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn request_token_missing_secret() {
+        assert!(RequestTokenResponse::from_body(b"oauth_token=abc").is_err());
+    }
+
+    #[test]
+    fn malformed_encoding() {
+        let body = b"oauth_token=a%zzc&oauth_token_secret=s&oauth_callback_confirmed=true";
+        let err = RequestTokenResponse::from_body(body).unwrap_err();
+        assert!(matches!(err, Error::InvalidEncoding(_)));
+        let err =
+            AccessTokenResponse::from_body(b"oauth_token=a&oauth_token_secret=%FF").unwrap_err();
+        assert!(matches!(err, Error::InvalidEncoding(_)));
+        assert!("oauth_token=a%2&oauth_token_secret=s&authorize_url=x"
+            .parse::<FlowState>()
+            .is_err());
+    }
+
+    #[test]
+    fn request_token_not_confirmed() {
+        let err =
+            RequestTokenResponse::from_body(b"oauth_token=abc&oauth_token_secret=def").unwrap_err();
+        assert!(matches!(err, Error::CallbackNotConfirmed));
+    }
+
+    #[test]
+    fn access_token() {
+        let body = b"oauth_token=abc&oauth_token_secret=def&user_id=42&screen_name=alice";
+        let access = AccessTokenResponse::from_body(body).unwrap();
+        assert_eq!(access.token.key, "abc");
+        assert_eq!(access.token.secret, "def");
+        assert_eq!(access.extra.len(), 2);
+        assert_eq!(access.extra["screen_name"], "alice");
+    }
+
+    #[test]
+    fn access_token_expiry() {
+        let body = b"oauth_token=abc&oauth_token_secret=def&oauth_expires_in=3600\
+                     &oauth_session_handle=h&oauth_authorization_expires_in=864000";
+        let access = AccessTokenResponse::from_body(body).unwrap();
+        assert_eq!(access.session_handle(), Some("h"));
+        assert_eq!(access.expires_in(), Some(Duration::from_secs(3600)));
+        assert_eq!(
+            access.authorization_expires_in(),
+            Some(Duration::from_secs(864000))
+        );
+    }
+
+    /// Response with `content_type` and `body`
+    fn response(content_type: &'static str, body: &[u8]) -> Response {
+        let mut headers = http::HeaderMap::new();
+        let _ = headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        Response {
+            url: "https://example.com/invalidate".into(),
+            status: http::StatusCode::OK,
+            headers,
+            body: body.to_vec(),
+        }
+    }
+
+    #[test]
+    fn invalidated() {
+        let token = Token::new("abc", "def");
+        let form = "application/x-www-form-urlencoded";
+        assert!(check_invalidated(&response(form, b"oauth_token=abc"), &token).is_ok());
+        assert!(check_invalidated(&response(form, b"access_token=abc"), &token).is_ok());
+        let err = check_invalidated(&response(form, b"oauth_token=xyz"), &token).unwrap_err();
+        assert!(matches!(err, Error::UnexpectedToken));
+        let err = check_invalidated(&response(form, b""), &token).unwrap_err();
+        assert!(matches!(err, Error::MissingParameter(_)));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn invalidated_json() {
+        let token = Token::new("abc", "def");
+        let json = "application/json; charset=utf-8";
+        let rsp = response(json, br#"{"access_token":"abc"}"#);
+        assert!(check_invalidated(&rsp, &token).is_ok());
+        let rsp = response(json, br#"{"access_token":"xyz"}"#);
+        assert!(matches!(
+            check_invalidated(&rsp, &token),
+            Err(Error::UnexpectedToken)
+        ));
+        let rsp = response(json, br#"{"errors":[]}"#);
+        assert!(matches!(
+            check_invalidated(&rsp, &token),
+            Err(Error::MissingParameter(_))
+        ));
+    }
+
+    #[test]
+    fn authorize_url_with_params() {
+        let request = AccessToken::new("tok", "secret");
+        let mut param = ParamList::new();
+        let _ = param.insert("perms".into(), "read write".into());
+        let _ = param.insert("force_login".into(), "true".into());
+        assert_eq!(
+            authorize_url("https://example.com/authorize?app=1", &request, Some(&param)),
+            "https://example.com/authorize?app=1&oauth_token=tok&force_login=true&perms=read%20write"
+        );
+    }
+
+    #[tokio::test]
+    async fn flow_session() {
+        use crate::transport::{HttpTransport, SignedRequest};
+        use crate::{ConsumerToken, Provider, Result, Session};
+        use std::sync::{Arc, Mutex};
+
+        /// Token endpoints, recording the `Authorization` headers
+        #[derive(Clone, Default)]
+        struct Endpoints(Arc<Mutex<Vec<String>>>);
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Endpoints {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let header = request.headers["authorization"].to_str().unwrap();
+                self.0.lock().unwrap().push(header.to_string());
+                let body: &[u8] = if request.url.ends_with("/request") {
+                    b"oauth_token=req&oauth_token_secret=s&oauth_callback_confirmed=true"
+                } else {
+                    b"oauth_token=acc&oauth_token_secret=s"
+                };
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::OK,
+                    headers: http::HeaderMap::new(),
+                    body: body.to_vec(),
+                })
+            }
+        }
+
+        let endpoints = Endpoints::default();
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer.clone(), None)
+            .realm("Example")
+            .transport(endpoints.clone());
+        let provider = Provider::new(
+            "http://example.com/request",
+            "http://example.com/authorize",
+            "http://example.com/access",
+        );
+        let flow = super::OAuthFlow::new(provider, consumer).session(session);
+        let state = flow.start("oob").await.unwrap();
+        assert_eq!(
+            state.authorize_url,
+            "http://example.com/authorize?oauth_token=req"
+        );
+        let access = flow.finish(&state, "verifier").await.unwrap();
+        assert_eq!(access.token.key, "acc");
+
+        let headers = endpoints.0.lock().unwrap();
+        assert_eq!(headers.len(), 2);
+        assert!(headers
+            .iter()
+            .all(|h| h.starts_with("OAuth realm=\"Example\"")));
+        assert!(headers[1].contains("oauth_token=\"req\""));
+    }
+
+    #[test]
+    fn flow_state_round_trip() {
+        let state = FlowState {
+            request_token: AccessToken::new("tok", "s&cret"),
+            authorize_url: "https://example.com/authorize?oauth_token=tok".into(),
+        };
+        let restored = state.to_state_string().parse::<FlowState>().unwrap();
+        assert_eq!(restored.request_token.key, "tok");
+        assert_eq!(restored.request_token.secret, "s&cret");
+        assert_eq!(restored.authorize_url, state.authorize_url);
+        assert!(!state.to_string().contains("s%26cret"));
+        assert!(!format!("{:?}", state).contains("s&cret"));
+    }
+}
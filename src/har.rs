@@ -0,0 +1,297 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Recording of signed requests and their responses in the HTTP Archive (HAR)
+//! format, to share them with the support of a provider.
+//!
+//! Requires the `har` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use oauth_client::har::HarRecorder;
+//! use oauth_client::transport::ReqwestTransport;
+//! use std::sync::Arc;
+//!
+//! async {
+//!     let recorder = Arc::new(HarRecorder::new(ReqwestTransport::default()));
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//!     let session = oauth_client::Session::new(consumer, None).transport(recorder.clone());
+//!     let _ = session.get("http://oauthbin.com/v1/request-token", None).await;
+//!     println!("{}", recorder.to_har(true));
+//! };
+//! ```
+
+use crate::transport::{redact_params, HttpTransport, Response, SignedRequest};
+use crate::Result;
+use async_trait::async_trait;
+use http::header::CONTENT_TYPE;
+use http::HeaderMap;
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Transport recording the requests sent through `inner` and their responses.
+#[derive(Debug)]
+pub struct HarRecorder<T> {
+    inner: T,
+    entries: Mutex<Vec<Entry>>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    started: SystemTime,
+    time: Duration,
+    request: SignedRequest,
+    response: std::result::Result<Response, String>,
+}
+
+impl<T> HarRecorder<T> {
+    /// Create new recorder sending the requests with `inner`
+    pub fn new(inner: T) -> HarRecorder<T> {
+        HarRecorder {
+            inner,
+            entries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Forget the recorded requests
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// The recorded requests as a HAR document. With `redact`, the values of
+    /// `oauth_token`, `oauth_token_secret`, `oauth_verifier` and
+    /// `oauth_signature` are replaced by `REDACTED`.
+    pub fn to_har(&self, redact: bool) -> String {
+        let redact = |s: &str| {
+            if redact {
+                redact_params(s)
+            } else {
+                s.to_string()
+            }
+        };
+        let entries = self.entries.lock().unwrap();
+        let entries = entries
+            .iter()
+            .map(|entry| har_entry(entry, &redact))
+            .collect::<Vec<_>>();
+        let har = json!({
+            "log": {
+                "version": "1.2",
+                "creator": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "entries": entries,
+            }
+        });
+        har.to_string()
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: HttpTransport> HttpTransport for HarRecorder<T> {
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        let start = since_epoch();
+        let result = self.inner.execute(request.clone()).await;
+        let entry = Entry {
+            started: UNIX_EPOCH + start,
+            time: since_epoch().saturating_sub(start),
+            request,
+            response: match &result {
+                Ok(response) => Ok(response.clone()),
+                Err(err) => Err(err.to_string()),
+            },
+        };
+        self.entries.lock().unwrap().push(entry);
+        result
+    }
+}
+
+fn har_entry(entry: &Entry, redact: &dyn Fn(&str) -> String) -> Value {
+    let request = &entry.request;
+    let url = redact(&request.url);
+    let query = url::Url::parse(&url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(name, value)| json!({ "name": name, "value": value }))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let mut har_request = json!({
+        "method": request.method.as_str(),
+        "url": url,
+        "httpVersion": "HTTP/1.1",
+        "cookies": [],
+        "headers": headers(&request.headers, redact),
+        "queryString": query,
+        "headersSize": -1,
+        "bodySize": request.body.len(),
+    });
+    if !request.body.is_empty() {
+        har_request["postData"] = json!({
+            "mimeType": mime_type(&request.headers),
+            "text": redact(&String::from_utf8_lossy(&request.body)),
+        });
+    }
+
+    let har_response = match &entry.response {
+        Ok(response) => json!({
+            "status": response.status.as_u16(),
+            "statusText": response.status.canonical_reason().unwrap_or(""),
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": headers(&response.headers, redact),
+            "content": {
+                "size": response.body.len(),
+                "mimeType": mime_type(&response.headers),
+                "text": redact(&String::from_utf8_lossy(&response.body)),
+            },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": response.body.len(),
+        }),
+        // HAR has no place for transport errors: report them as status 0.
+        Err(err) => json!({
+            "status": 0,
+            "statusText": "",
+            "httpVersion": "HTTP/1.1",
+            "cookies": [],
+            "headers": [],
+            "content": { "size": 0, "mimeType": "" },
+            "redirectURL": "",
+            "headersSize": -1,
+            "bodySize": -1,
+            "_error": err,
+        }),
+    };
+
+    let time = entry.time.as_secs_f64() * 1000.0;
+    json!({
+        "startedDateTime": iso8601(entry.started),
+        "time": time,
+        "request": har_request,
+        "response": har_response,
+        "cache": {},
+        "timings": { "send": 0, "wait": time, "receive": 0 },
+    })
+}
+
+fn headers(headers: &HeaderMap, redact: &dyn Fn(&str) -> String) -> Vec<Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = redact(&String::from_utf8_lossy(value.as_bytes()));
+            json!({ "name": name.as_str(), "value": value })
+        })
+        .collect()
+}
+
+fn mime_type(headers: &HeaderMap) -> &str {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+}
+
+/// Current time since the Unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+fn since_epoch() -> Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+/// `SystemTime::now` panics in a browser
+#[cfg(target_arch = "wasm32")]
+fn since_epoch() -> Duration {
+    Duration::from_secs_f64(js_sys::Date::now() / 1000.0)
+}
+
+/// Format `time` as an ISO 8601 date in UTC, with milliseconds
+fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs() as i64;
+    let (days, secs) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+
+    // Civil date from days since the epoch, after Howard Hinnant's algorithm
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{iso8601, HarRecorder};
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{AccessToken, ConsumerToken, Result, Session};
+    use std::sync::Arc;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    struct TokenEndpoint;
+
+    #[async_trait::async_trait]
+    impl HttpTransport for TokenEndpoint {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            Ok(Response {
+                url: request.url,
+                status: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: b"oauth_token=t&oauth_token_secret=s".to_vec(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn record() {
+        let recorder = Arc::new(HarRecorder::new(TokenEndpoint));
+        let consumer = ConsumerToken::new("key", "secret");
+        let token = AccessToken::new("token", "secret");
+        let session = Session::new(consumer, Some(token)).transport(recorder.clone());
+        let _ = session.get("http://example.com/a?b=1", None).await.unwrap();
+
+        let har: serde_json::Value = serde_json::from_str(&recorder.to_har(true)).unwrap();
+        let entry = &har["log"]["entries"][0];
+        assert_eq!(entry["request"]["method"], "GET");
+        assert_eq!(entry["request"]["queryString"][0]["value"], "1");
+        let authorization = entry["request"]["headers"][0]["value"].as_str().unwrap();
+        assert!(authorization.contains("oauth_token=\"REDACTED\""));
+        assert!(authorization.contains("oauth_signature=\"REDACTED\""));
+        assert_eq!(
+            entry["response"]["content"]["text"],
+            "oauth_token=REDACTED&oauth_token_secret=REDACTED"
+        );
+
+        recorder.clear();
+        assert!(recorder.to_har(false).contains("\"entries\":[]"));
+    }
+
+    #[test]
+    fn date() {
+        let time = UNIX_EPOCH + Duration::from_millis(951_782_400_123);
+        assert_eq!(iso8601(time), "2000-02-29T00:00:00.123Z");
+    }
+}
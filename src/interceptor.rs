@@ -0,0 +1,117 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Interceptors of the requests sent by the transport of a session.
+
+use crate::transport::{Response, SignedRequest};
+use std::fmt;
+
+/// Middleware called around every request sent by the transport of a
+/// session, retries included.
+///
+/// The requests are already signed: changes to their headers are sent as
+/// is, while changes to their URL or form body invalidate the signature.
+/// The interceptors of a session are called by
+/// [`before`](#method.before) in the order they were added, and by
+/// [`after`](#method.after) in the reverse order.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::transport::SignedRequest;
+///
+/// struct CorrelationId;
+///
+/// impl oauth_client::Interceptor for CorrelationId {
+///     fn before(&self, request: &mut SignedRequest) {
+///         let id = http::HeaderValue::from_static("request-42");
+///         let _ = request.headers.insert("x-correlation-id", id);
+///     }
+/// }
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).interceptor(CorrelationId);
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// `request` is about to be sent
+    fn before(&self, request: &mut SignedRequest) {
+        let _ = request;
+    }
+
+    /// `response` was received
+    fn after(&self, response: &Response) {
+        let _ = response;
+    }
+}
+
+impl fmt::Debug for dyn Interceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Interceptor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Interceptor;
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{ConsumerToken, Result, Session};
+    use std::sync::{Arc, Mutex};
+
+    /// Echo of the `x-correlation-id` header
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Echo {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            let mut headers = http::HeaderMap::new();
+            if let Some(id) = request.headers.get("x-correlation-id") {
+                let _ = headers.insert("x-correlation-id", id.clone());
+            }
+            Ok(Response {
+                url: request.url,
+                status: http::StatusCode::OK,
+                headers,
+                body: Vec::new(),
+            })
+        }
+    }
+
+    /// Interceptor `name`, logging its calls
+    struct Log(&'static str, Arc<Mutex<Vec<String>>>);
+
+    impl Interceptor for Log {
+        fn before(&self, request: &mut SignedRequest) {
+            let id = format!("{}-1", self.0);
+            let _ = request
+                .headers
+                .insert("x-correlation-id", id.parse().unwrap());
+            self.1.lock().unwrap().push(format!("before {}", self.0));
+        }
+
+        fn after(&self, response: &Response) {
+            let id = response.headers["x-correlation-id"].to_str().unwrap();
+            self.1
+                .lock()
+                .unwrap()
+                .push(format!("after {} {}", self.0, id));
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .transport(Echo)
+            .interceptor(Log("a", calls.clone()))
+            .interceptor(Log("b", calls.clone()));
+        session.get("http://example.com/a", None).await.unwrap();
+
+        let calls = calls.lock().unwrap();
+        let expected = ["before a", "before b", "after b b-1", "after a b-1"];
+        assert_eq!(*calls, expected);
+    }
+}
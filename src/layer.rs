@@ -0,0 +1,137 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Tower middleware signing outgoing requests.
+//!
+//! Requires the `tower` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use oauth_client::layer::OAuthSignLayer;
+//! use tower::Layer;
+//!
+//! # fn client<S>(client: S) {
+//! let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//! let client = OAuthSignLayer::new(consumer, None).layer(client);
+//! # }
+//! ```
+
+use crate::{sign_http_request, AccessToken, BoxError, ConsumerToken};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Layer adding an OAuth `Authorization` header to every request.
+#[derive(Clone, Debug)]
+pub struct OAuthSignLayer {
+    consumer: ConsumerToken<'static>,
+    token: Option<AccessToken<'static>>,
+}
+
+impl OAuthSignLayer {
+    /// Create new layer signing with `consumer` and `token`
+    pub fn new(
+        consumer: ConsumerToken<'static>,
+        token: Option<AccessToken<'static>>,
+    ) -> OAuthSignLayer {
+        OAuthSignLayer { consumer, token }
+    }
+}
+
+impl<S> Layer<S> for OAuthSignLayer {
+    type Service = OAuthSign<S>;
+
+    fn layer(&self, inner: S) -> OAuthSign<S> {
+        OAuthSign {
+            inner,
+            consumer: self.consumer.clone(),
+            token: self.token.clone(),
+        }
+    }
+}
+
+/// Service signing every request before passing it to the inner service.
+///
+/// The query parameters and, for `application/x-www-form-urlencoded`
+/// requests, the pairs of the body are signed, so the body must be readable
+/// as bytes. Requests with a relative URI cannot be signed and fail.
+#[derive(Clone, Debug)]
+pub struct OAuthSign<S> {
+    inner: S,
+    consumer: ConsumerToken<'static>,
+    token: Option<AccessToken<'static>>,
+}
+
+impl<S, B> Service<http::Request<B>> for OAuthSign<S>
+where
+    S: Service<http::Request<B>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+    B: AsRef<[u8]>,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, BoxError>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), BoxError>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        if let Err(err) = sign_http_request(&mut request, &self.consumer, self.token.as_ref()) {
+            let err: BoxError = Box::new(err);
+            return Box::pin(async move { Err(err) });
+        }
+        let response = self.inner.call(request);
+        Box::pin(async move { response.await.map_err(Into::into) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OAuthSignLayer;
+    use crate::ConsumerToken;
+    use std::future::{ready, Ready};
+    use std::task::{Context, Poll};
+    use tower::{Layer, Service};
+
+    /// Service responding with the `Authorization` header of the request
+    struct Authorization;
+
+    impl Service<http::Request<Vec<u8>>> for Authorization {
+        type Response = Option<String>;
+        type Error = std::convert::Infallible;
+        type Future = Ready<Result<Option<String>, std::convert::Infallible>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: http::Request<Vec<u8>>) -> Self::Future {
+            let header = request.headers().get(http::header::AUTHORIZATION);
+            ready(Ok(header.map(|v| v.to_str().unwrap().to_string())))
+        }
+    }
+
+    #[tokio::test]
+    async fn sign() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut service = OAuthSignLayer::new(consumer, None).layer(Authorization);
+
+        let request = http::Request::post("http://example.com/a?b=1")
+            .header("content-type", "application/x-www-form-urlencoded")
+            .body(b"c=2".to_vec())
+            .unwrap();
+        let header = service.call(request).await.unwrap().unwrap();
+        assert!(header.starts_with("OAuth oauth_consumer_key=\"key\""));
+
+        let request = http::Request::get("/a").body(Vec::new()).unwrap();
+        assert!(service.call(request).await.is_err());
+    }
+}
@@ -14,40 +14,249 @@
 //! Send request for request token.
 //!
 //! ```
+//! # #[cfg(feature = "client")]
 //! async {
 //!     const REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
-//!     let consumer = oauth_client::Token::new("key", "secret");
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
 //!     let bytes = oauth_client::get(REQUEST_TOKEN, &consumer, None, None).await.unwrap();
 //! };
 //! ```
+//!
+//! Enable the `blocking` feature (or `ureq`, for a lighter dependency tree)
+//! for synchronous variants of the request functions in the
+//! [`blocking`](blocking/index.html) module, and the
+//! `loopback` feature for the [`loopback`](loopback/index.html) redirect
+//! listener used by native applications. The `har` feature adds a
+//! [`har`](har/index.html) recorder of the requests sent, and the `tower`
+//! feature a [`layer`](layer/index.html) signing the requests of a tower
+//! client stack. The `streaming` feature adds a
+//! [`StreamingConnection`](streaming/struct.StreamingConnection.html) to
+//! long-lived streaming endpoints, and the `transfer` feature
+//! [`transfer`](transfer/index.html) functions streaming large bodies. The
+//! `pagination` feature adds
+//! [`Session::paginate`](struct.Session.html#method.paginate), streaming the
+//! pages of cursored endpoints, and the `batch` feature
+//! [`Session::batch`](struct.Session.html#method.batch), sending many requests
+//! concurrently.
+//!
+//! Enable the `serde` feature to serialize [`Token`](struct.Token.html) and
+//! the token responses of the flows, and to send serialized structs as the
+//! parameters of requests with [`post_form`](fn.post_form.html).
+//!
+//! Enable the `json` feature to deserialize JSON responses with
+//! [`get_json`](fn.get_json.html) and
+//! [`post_json_response`](fn.post_json_response.html).
+//!
+//! Enable the `file-store`, `encrypted-store` or `keyring` feature for the
+//! [`store`](store/index.html) backends persisting tokens across runs.
+//!
+//! Enable the `tracing` feature for an `oauth_request` span around every
+//! request sent, with its method, host, status and duration. Neither the
+//! spans nor the logs of the crate include the secrets, the tokens, the
+//! signatures or the `Authorization` headers.
+//!
+//! Enable the `opentelemetry` feature for an OpenTelemetry client span
+//! around every request sent, a child of the current context, and to
+//! propagate its context in the headers of the request with the global text
+//! map propagator, e.g. a `TraceContextPropagator` to send `traceparent` and
+//! `tracestate`. The spans carry the same fields as with `tracing`.
+//!
+//! Enable the `zeroize` feature to wipe the secrets of tokens from memory
+//! when they are dropped.
+//!
+//! Enable the `secrecy` feature to create tokens from a
+//! `secrecy::SecretString` with
+//! [`Token::from_secret`](struct.Token.html#method.from_secret).
+//!
+//! Enable the `unicode-normalization` feature for
+//! [`Session::normalize_nfc`](struct.Session.html#method.normalize_nfc).
+//!
+//! Enable the `wasm` feature when targeting `wasm32-unknown-unknown` in a
+//! browser, to use its random number generator.
+//!
+//! Endpoints are given as a `&str`, a `String` or a [`Url`](struct.Url.html),
+//! to compose them with `Url::join` and `Url::query_pairs_mut` rather than
+//! by formatting strings. Query parameters of the URL are signed.
+//!
+//! Requests are sent through an [`HttpTransport`](transport/trait.HttpTransport.html),
+//! reqwest by default. Disable the default `reqwest` feature and enable `hyper`
+//! to send them with hyper instead. The reqwest client uses native-tls by
+//! default; disable the default features and enable `client`, `reqwest` and
+//! `rustls-tls` to use rustls instead.
+//!
+//! Signing never performs I/O and the request futures do not depend on any
+//! runtime: only the default reqwest transport requires tokio. On other
+//! executors, such as async-std or smol, give the
+//! [`Session`](struct.Session.html) a transport of their own.
+//!
+//! Existing reqwest code can sign its requests with
+//! [`OAuthSignExt::oauth1`](trait.OAuthSignExt.html) on a
+//! `reqwest::RequestBuilder`.
+//!
+//! The HTTP functions, flows and transports are behind the default `client`
+//! feature. With `default-features = false`, the crate only builds the
+//! signing functions of the [`core`](core/index.html) module.
 
-use failure::*;
-
+#[cfg(feature = "client")]
+use crate::core::{check_status_with, get_request_with, post_request_with};
+#[cfg(feature = "reqwest")]
+use crate::core::{request_header, sign_headers};
+#[cfg(feature = "client")]
+use crate::transport::Response;
+#[cfg(feature = "client")]
+use http::header::{HeaderMap, DATE};
+#[cfg(feature = "client")]
+use http::StatusCode;
+#[cfg(feature = "reqwest")]
 use lazy_static::*;
+#[cfg(feature = "client")]
 use log::*;
-use rand::{distributions::Alphanumeric, Rng};
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use reqwest::{Client, RequestBuilder, StatusCode};
-use ring::hmac;
+#[cfg(feature = "reqwest")]
+use reqwest::{Client, Request};
 use std::borrow::Cow;
-use std::collections::HashMap;
-use std::iter;
-use time::offset;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+#[cfg(feature = "client")]
+use std::time::UNIX_EPOCH;
+
+#[cfg(feature = "client")]
+pub use crate::audit::{AuditHook, AuditRecord};
+#[cfg(feature = "client")]
+pub use crate::cache::ResponseCache;
+#[cfg(feature = "client")]
+pub use crate::cancel::CancellationToken;
+#[cfg(feature = "client")]
+pub use crate::circuit::CircuitBreaker;
+pub use crate::clock::{Clock, SystemClock};
+pub use crate::core::{
+    authorization_header, authorization_header_with_params, check_status, decode, encode,
+    hmac_sha1_signature, parse_form_body, request_token_header, sign_as_header, sign_as_query,
+    sign_http_parts, sign_http_request, sign_with, signature_base_string, AuthorizationHeader,
+};
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub use crate::deadline::Deadline;
+pub use crate::decoder::{ErrorDecoder, ProviderError};
+pub use crate::echo::{
+    echo_headers, EchoHeaders, AUTH_SERVICE_PROVIDER, VERIFY_CREDENTIALS_AUTHORIZATION,
+};
+pub use crate::error::{BoxError, Error, RequestContext, StatusError, ERROR_BODY_LIMIT};
+#[cfg(feature = "client")]
+pub use crate::flow::{
+    authorize_url, exchange_access_token, invalidate_token, obtain_request_token,
+    refresh_access_token, AccessTokenResponse, FlowState, OAuthFlow, Provider,
+    RequestTokenResponse, OOB_CALLBACK,
+};
+#[cfg(feature = "client")]
+pub use crate::interceptor::Interceptor;
+pub use crate::nonce::NonceProvider;
+pub use crate::param::{ParamList, ParamListBuilder};
+pub use crate::problem::{Challenge, ChallengeError, Problem, ProblemError};
+pub use crate::quirks::{ParamPlacement, QuirkProfile};
+pub use crate::rate_limit::RateLimit;
+#[cfg(feature = "client")]
+pub use crate::retry::{RetryPolicy, Retryable};
+pub use crate::session::Session;
+pub use crate::status::{StatusClass, StatusClassifier};
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+pub use crate::timer::SystemTimer;
+#[cfg(feature = "client")]
+pub use crate::timer::Timer;
+#[cfg(feature = "client")]
+pub use crate::transport::HttpTransport;
+pub use crate::transport::SignedRequest;
+pub use crate::two_legged::TwoLegged;
+pub use crate::websocket::websocket_headers;
+pub use url::Url;
+
+#[cfg(feature = "client")]
+mod audit;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+pub mod blocking;
+#[cfg(feature = "client")]
+mod cache;
+#[cfg(feature = "client")]
+mod cancel;
+#[cfg(feature = "client")]
+mod circuit;
+mod clock;
+#[cfg(feature = "conformance")]
+pub mod conformance;
+pub mod core;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+mod deadline;
+pub mod decoder;
+mod echo;
+mod error;
+#[cfg(feature = "client")]
+mod flow;
+#[cfg(feature = "har")]
+pub mod har;
+#[cfg(feature = "client")]
+mod interceptor;
+#[cfg(feature = "tower")]
+pub mod layer;
+#[cfg(feature = "loopback")]
+pub mod loopback;
+pub mod nonce;
+#[cfg(feature = "opentelemetry")]
+mod otel;
+#[cfg(feature = "pagination")]
+pub mod pagination;
+mod param;
+mod problem;
+mod quirks;
+mod rate_limit;
+#[cfg(feature = "client")]
+mod retry;
+mod session;
+mod status;
+pub mod store;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+#[cfg(all(test, any(feature = "streaming", feature = "transfer")))]
+mod test_server;
+#[cfg(feature = "client")]
+mod timer;
+#[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+mod trace;
+#[cfg(all(feature = "transfer", not(target_arch = "wasm32")))]
+pub mod transfer;
+pub mod transport;
+mod two_legged;
+mod websocket;
 
 /// Result type.
 pub type Result<T> = std::result::Result<T, Error>;
 
-/// An error happening due to a HTTP status error.
-#[derive(Debug, Fail, Clone, Copy)]
-#[fail(display = "HTTP status error code {}", _0)]
-pub struct HttpStatusError(pub u16);
-
+#[cfg(feature = "reqwest")]
 lazy_static! {
     static ref CLIENT: Client = Client::new();
+    static ref TRANSPORT: Arc<dyn HttpTransport> =
+        Arc::new(transport::ReqwestTransport::new(CLIENT.clone()));
 }
 
 /// Token structure for the OAuth
-#[derive(Clone, Debug)]
+///
+/// A request without a token, `None`, sends no `oauth_token` and is signed
+/// with the key `consumer_secret&`. A token with an empty secret, as issued
+/// by some providers, sends its `oauth_token` and is signed with the same
+/// key.
+///
+/// With the `serde` feature, tokens implement `Serialize` and `Deserialize`,
+/// secret included, to be persisted in a secured store.
+///
+/// With the `zeroize` feature, owned secrets are wiped from memory when the
+/// token is dropped, as is the signing key derived from them. Borrowed
+/// secrets belong to the caller.
+///
+/// The secret is redacted from the `Debug` output, see
+/// [`expose_secret`](#method.expose_secret).
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Token<'a> {
     /// 'key' field of the token
     pub key: Cow<'a, str>,
@@ -55,13 +264,37 @@ pub struct Token<'a> {
     pub secret: Cow<'a, str>,
 }
 
+impl fmt::Debug for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("key", &self.key)
+            .field("secret", &"***")
+            .finish()
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Token<'_> {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        if let Cow::Owned(secret) = &mut self.secret {
+            secret.zeroize();
+        }
+    }
+}
+
+/// A [`Token`](struct.Token.html) owning its key and secret, to be kept in
+/// long-lived application state.
+pub type OwnedToken = Token<'static>;
+
 impl<'a> Token<'a> {
     /// Create new token from `key` and `secret`
     ///
     /// # Examples
     ///
     /// ```
-    /// let consumer = oauth_client::Token::new("key", "secret");
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
     /// ```
     pub fn new<K, S>(key: K, secret: S) -> Token<'a>
     where
@@ -73,272 +306,1060 @@ impl<'a> Token<'a> {
             secret: secret.into(),
         }
     }
-}
 
-/// Alias for `HashMap<Cow<'a, str>, Cow<'a, str>>`
-pub type ParamList<'a> = HashMap<Cow<'a, str>, Cow<'a, str>>;
+    /// Create new token from `key` and `secret`, rejecting an empty key, and
+    /// control characters or surrounding whitespace, such as a trailing
+    /// newline read from a file
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// assert!(oauth_client::Token::try_new("key", "secret").is_ok());
+    /// assert!(oauth_client::Token::try_new("key", "secret\n").is_err());
+    /// ```
+    pub fn try_new<K, S>(key: K, secret: S) -> Result<Token<'a>>
+    where
+        K: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        let token = Token::new(key, secret);
+        if token.key.is_empty() {
+            return Err(Error::invalid_credential("key", "it is empty"));
+        }
+        for (field, value) in [("key", &token.key), ("secret", &token.secret)] {
+            if value.chars().any(char::is_control) {
+                return Err(Error::invalid_credential(
+                    field,
+                    "it contains control characters",
+                ));
+            }
+            if value.trim() != value.as_ref() {
+                return Err(Error::invalid_credential(
+                    field,
+                    "it has surrounding whitespace",
+                ));
+            }
+        }
+        Ok(token)
+    }
 
-fn insert_param<'a, K, V>(param: &mut ParamList<'a>, key: K, value: V) -> Option<Cow<'a, str>>
-where
-    K: Into<Cow<'a, str>>,
-    V: Into<Cow<'a, str>>,
-{
-    param.insert(key.into(), value.into())
-}
+    /// Create new token from `key`, with an empty secret
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let token = oauth_client::Token::without_secret("token");
+    /// let session = oauth_client::Session::new(consumer, Some(token.into()));
+    /// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+    /// assert!(header.header().contains("oauth_token=\"token\""));
+    /// ```
+    pub fn without_secret<K: Into<Cow<'a, str>>>(key: K) -> Token<'a> {
+        Token::new(key, "")
+    }
 
-fn join_query(param: &ParamList<'_>) -> String {
-    let mut pairs = param
-        .iter()
-        .map(|(k, v)| format!("{}={}", encode(&k), encode(&v)))
-        .collect::<Vec<_>>();
-    pairs.sort();
-    pairs.join("&")
+    /// The token with an owned copy of its key and secret, if borrowed
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let key = String::from("key");
+    /// let token: oauth_client::OwnedToken = oauth_client::Token::new(&*key, "secret").into_owned();
+    /// drop(key);
+    /// assert_eq!(token.key, "key");
+    /// ```
+    pub fn into_owned(mut self) -> Token<'static> {
+        // Taken rather than moved, as tokens implement `Drop` with `zeroize`.
+        let key = std::mem::take(&mut self.key).into_owned();
+        let secret = std::mem::take(&mut self.secret).into_owned();
+        Token::new(key, secret)
+    }
+
+    /// The raw secret, which `Debug` redacts
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let token = oauth_client::Token::new("key", "secret");
+    /// assert_eq!(format!("{:?}", token), r#"Token { key: "key", secret: "***" }"#);
+    /// assert_eq!(token.expose_secret(), "secret");
+    /// ```
+    pub fn expose_secret(&self) -> &str {
+        &self.secret
+    }
+
+    /// Create new token from the environment variables `key` and `secret`,
+    /// checked as by [`try_new`](#method.try_new)
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// std::env::set_var("TWITTER_KEY", "key");
+    /// std::env::set_var("TWITTER_SECRET", "secret");
+    /// let consumer = oauth_client::Token::from_env("TWITTER_KEY", "TWITTER_SECRET").unwrap();
+    /// assert_eq!(consumer.key, "key");
+    /// ```
+    pub fn from_env(key: &str, secret: &str) -> Result<Token<'static>> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|source| Error::Env {
+                name: name.to_string(),
+                source,
+            })
+        };
+        Token::try_new(var(key)?, var(secret)?)
+    }
+
+    /// Create new token from `key` and a `secret` kept in a
+    /// `secrecy::SecretString`, borrowing it rather than copying it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use secrecy::SecretString;
+    ///
+    /// let secret = SecretString::new("secret".into());
+    /// let consumer = oauth_client::Token::from_secret("key", &secret);
+    /// assert_eq!(consumer.expose_secret(), "secret");
+    /// ```
+    #[cfg(feature = "secrecy")]
+    pub fn from_secret<K>(key: K, secret: &'a secrecy::SecretString) -> Token<'a>
+    where
+        K: Into<Cow<'a, str>>,
+    {
+        use secrecy::ExposeSecret;
+
+        Token::new(key, secret.expose_secret().as_str())
+    }
 }
 
-// Encode all but the unreserved characters defined in
-// RFC 3986, section 2.3. "Unreserved Characters"
-// https://tools.ietf.org/html/rfc3986#page-12
+/// Consumer credentials of an application, as registered with the provider.
+///
+/// Distinct from [`AccessToken`](struct.AccessToken.html) so the two can't
+/// be swapped when calling [`get`](fn.get.html), [`post`](fn.post.html) or
+/// [`Session::new`](struct.Session.html#method.new). Dereferences to the
+/// underlying [`Token`](struct.Token.html).
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// assert_eq!(consumer.key, "key");
+/// let consumer: oauth_client::ConsumerToken = oauth_client::Token::new("key", "secret").into();
+/// ```
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct ConsumerToken<'a>(Token<'a>);
 
-const URL: &percent_encoding::AsciiSet = &percent_encoding::NON_ALPHANUMERIC
-    .remove(b'-')
-    .remove(b'.')
-    .remove(b'_')
-    .remove(b'~');
+impl<'a> ConsumerToken<'a> {
+    /// Create new consumer token from `key` and `secret`
+    pub fn new<K, S>(key: K, secret: S) -> ConsumerToken<'a>
+    where
+        K: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        ConsumerToken(Token::new(key, secret))
+    }
 
-/// Percent encode string
-fn encode(s: &str) -> String {
-    percent_encoding::percent_encode(s.as_bytes(), URL).collect()
+    /// Create new consumer token from the environment variables
+    /// `OAUTH_CONSUMER_KEY` and `OAUTH_CONSUMER_SECRET`.
+    /// See [`Token::from_env`](struct.Token.html#method.from_env).
+    pub fn from_default_env() -> Result<ConsumerToken<'static>> {
+        Token::from_env("OAUTH_CONSUMER_KEY", "OAUTH_CONSUMER_SECRET").map(ConsumerToken)
+    }
+
+    /// The consumer token with an owned copy of its key and secret.
+    /// See [`Token::into_owned`](struct.Token.html#method.into_owned).
+    pub fn into_owned(self) -> ConsumerToken<'static> {
+        ConsumerToken(self.0.into_owned())
+    }
+
+    /// The underlying token
+    pub fn into_inner(self) -> Token<'a> {
+        self.0
+    }
 }
 
-/// Create signature. See https://dev.twitter.com/oauth/overview/creating-signatures
-fn signature(
-    method: &str,
-    uri: &str,
-    query: &str,
-    consumer_secret: &str,
-    token_secret: Option<&str>,
-) -> String {
-    let base = format!("{}&{}&{}", encode(method), encode(uri), encode(query));
-    let key = format!(
-        "{}&{}",
-        encode(consumer_secret),
-        encode(token_secret.unwrap_or(""))
-    );
-    debug!("Signature base string: {}", base);
-    debug!("Authorization header: Authorization: {}", base);
-    let signing_key = hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key.as_bytes());
-    let signature = hmac::sign(&signing_key, base.as_bytes());
-    base64::encode(signature.as_ref())
-}
-
-/// Constuct plain-text header
-fn header(param: &ParamList) -> String {
-    let mut pairs = param
-        .iter()
-        .filter(|&(k, _)| k.starts_with("oauth_"))
-        .map(|(k, v)| format!("{}=\"{}\"", k, encode(&v)))
-        .collect::<Vec<_>>();
-    pairs.sort();
-    format!("OAuth {}", pairs.join(", "))
-}
-
-/// Construct plain-text body from 'ParamList'
-fn body(param: &ParamList) -> String {
-    let mut pairs = param
-        .iter()
-        .filter(|&(k, _)| !k.starts_with("oauth_"))
-        .map(|(k, v)| format!("{}={}", k, encode(&v)))
-        .collect::<Vec<_>>();
-    pairs.sort();
-    pairs.join("&")
-}
-
-/// Create header and body
-fn get_header(
-    method: &str,
-    uri: &str,
-    consumer: &Token,
-    token: Option<&Token>,
-    other_param: Option<&ParamList>,
-) -> (String, String) {
-    let mut param = HashMap::new();
-    let timestamp = format!(
-        "{}",
-        (time::OffsetDateTime::now_utc().to_offset(offset!(UTC))
-            - time::OffsetDateTime::unix_epoch())
-        .whole_seconds()
-    );
-    let mut rng = rand::thread_rng();
-    let nonce = iter::repeat(())
-        .map(|()| rng.sample(Alphanumeric))
-        .map(char::from)
-        .take(32)
-        .collect::<String>();
-
-    let _ = insert_param(&mut param, "oauth_consumer_key", consumer.key.to_string());
-    let _ = insert_param(&mut param, "oauth_nonce", nonce);
-    let _ = insert_param(&mut param, "oauth_signature_method", "HMAC-SHA1");
-    let _ = insert_param(&mut param, "oauth_timestamp", timestamp);
-    let _ = insert_param(&mut param, "oauth_version", "1.0");
-    if let Some(tk) = token {
-        let _ = insert_param(&mut param, "oauth_token", tk.key.as_ref());
-    }
-
-    if let Some(ps) = other_param {
-        for (k, v) in ps.iter() {
-            let _ = insert_param(&mut param, k.as_ref(), v.as_ref());
-        }
+impl<'a> From<Token<'a>> for ConsumerToken<'a> {
+    fn from(token: Token<'a>) -> ConsumerToken<'a> {
+        ConsumerToken(token)
     }
+}
 
-    let sign = signature(
-        method,
-        uri,
-        join_query(&param).as_ref(),
-        consumer.secret.as_ref(),
-        token.map(|t| t.secret.as_ref()),
-    );
-    let _ = insert_param(&mut param, "oauth_signature", sign);
+impl<'a> Deref for ConsumerToken<'a> {
+    type Target = Token<'a>;
 
-    (header(&param), body(&param))
+    fn deref(&self) -> &Token<'a> {
+        &self.0
+    }
 }
 
-/// Create an authorization header.
-/// See https://dev.twitter.com/oauth/overview/authorizing-requests
+/// Token credentials a request is signed with on behalf of a user: the
+/// access token, or the temporary request token while it is exchanged.
+///
+/// Distinct from [`ConsumerToken`](struct.ConsumerToken.html), see there.
+/// Dereferences to the underlying [`Token`](struct.Token.html).
 ///
 /// # Examples
 ///
 /// ```
-/// # extern crate oauth_client;
-/// # fn main() {
-/// const REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
-/// let consumer = oauth_client::Token::new("key", "secret");
-/// let header = oauth_client::authorization_header("GET", REQUEST_TOKEN, &consumer, None, None);
-/// # }
+/// let access = oauth_client::AccessToken::new("token", "secret");
+/// assert_eq!(access.key, "token");
 /// ```
-pub fn authorization_header(
-    method: &str,
-    uri: &str,
-    consumer: &Token,
-    token: Option<&Token>,
-    other_param: Option<&ParamList>,
-) -> (String, String) {
-    get_header(method, uri, consumer, token, other_param)
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct AccessToken<'a>(Token<'a>);
+
+impl<'a> AccessToken<'a> {
+    /// Create new access token from `key` and `secret`
+    pub fn new<K, S>(key: K, secret: S) -> AccessToken<'a>
+    where
+        K: Into<Cow<'a, str>>,
+        S: Into<Cow<'a, str>>,
+    {
+        AccessToken(Token::new(key, secret))
+    }
+
+    /// Create new access token from the environment variables `OAUTH_TOKEN`
+    /// and `OAUTH_TOKEN_SECRET`.
+    /// See [`Token::from_env`](struct.Token.html#method.from_env).
+    pub fn from_default_env() -> Result<AccessToken<'static>> {
+        Token::from_env("OAUTH_TOKEN", "OAUTH_TOKEN_SECRET").map(AccessToken)
+    }
+
+    /// The access token with an owned copy of its key and secret.
+    /// See [`Token::into_owned`](struct.Token.html#method.into_owned).
+    pub fn into_owned(self) -> AccessToken<'static> {
+        AccessToken(self.0.into_owned())
+    }
+
+    /// The underlying token
+    pub fn into_inner(self) -> Token<'a> {
+        self.0
+    }
+}
+
+impl<'a> From<Token<'a>> for AccessToken<'a> {
+    fn from(token: Token<'a>) -> AccessToken<'a> {
+        AccessToken(token)
+    }
+}
+
+impl<'a> Deref for AccessToken<'a> {
+    type Target = Token<'a>;
+
+    fn deref(&self) -> &Token<'a> {
+        &self.0
+    }
+}
+
+/// Settings affecting how requests are signed
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Config {
+    /// Provider deviations from the specification
+    pub(crate) quirks: QuirkProfile,
+    /// Fixed `oauth_timestamp`, used when re-signing a refused request
+    pub(crate) timestamp: Option<i64>,
+    /// Offset in seconds between the server clock and ours, updated from
+    /// the `Date` header of responses when skew correction is enabled
+    pub(crate) clock_skew: Option<Arc<AtomicI64>>,
+    /// Source of the current time, the system clock if `None`
+    pub(crate) clock: Option<Arc<dyn Clock>>,
+    /// Generator of `oauth_nonce`, 32 alphanumeric characters if `None`
+    pub(crate) nonce: Option<Arc<dyn NonceProvider>>,
+    /// Headers added to every request, such as `User-Agent`
+    pub(crate) headers: http::HeaderMap,
+    /// Normalize the names and values of the parameters to NFC
+    #[cfg(feature = "unicode-normalization")]
+    pub(crate) normalize_nfc: bool,
+    /// Decoder of the bodies of error responses
+    pub(crate) error_decoder: Option<Arc<dyn ErrorDecoder>>,
+    /// Classifier of the response statuses, 200 OK being the only success
+    /// if `None`
+    pub(crate) status_classifier: Option<Arc<dyn StatusClassifier>>,
+    /// Transport sending the requests, reqwest if `None`
+    #[cfg(feature = "client")]
+    pub(crate) transport: Option<Arc<dyn HttpTransport>>,
+    /// Source of the delays between retries, the system timer if `None`
+    #[cfg(feature = "client")]
+    pub(crate) timer: Option<Arc<dyn Timer>>,
+    /// Maximum number of attempts of a request refused with `Retry-After`,
+    /// no retry if 0 or 1
+    #[cfg(feature = "client")]
+    pub(crate) retry_after: u32,
+    /// Retries of the requests failing with a transient error
+    #[cfg(feature = "client")]
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    /// Circuit breaker shared by the clones of the session
+    #[cfg(feature = "client")]
+    pub(crate) circuit_breaker: Option<CircuitBreaker>,
+    /// Cache of the GET responses, shared by the clones of the session
+    #[cfg(feature = "client")]
+    pub(crate) cache: Option<ResponseCache>,
+    /// Deadline of the whole request, retries included
+    #[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+    pub(crate) deadline: Option<Deadline>,
+    /// Token cancelling the requests in flight
+    #[cfg(feature = "client")]
+    pub(crate) cancellation: Option<CancellationToken>,
+    /// Whether POST and PATCH requests without an `Idempotency-Key` are
+    /// retried
+    #[cfg(feature = "client")]
+    pub(crate) retry_non_idempotent: bool,
+    /// Maximum size of the response bodies, in bytes
+    #[cfg(feature = "client")]
+    pub(crate) max_response_size: Option<usize>,
+    /// Interceptors of the requests sent, in the order they were added
+    #[cfg(feature = "client")]
+    pub(crate) interceptors: Vec<Arc<dyn Interceptor>>,
+    /// Recipient of the audit records of the requests sent
+    #[cfg(feature = "client")]
+    pub(crate) audit: Option<Arc<dyn AuditHook>>,
+    /// Observer of the progress of transfers
+    #[cfg(all(feature = "transfer", not(target_arch = "wasm32")))]
+    pub(crate) progress: Option<Arc<dyn transfer::ProgressHook>>,
+}
+
+impl Config {
+    /// The transport sending the requests
+    #[cfg(feature = "client")]
+    fn transport(&self) -> Result<Arc<dyn HttpTransport>> {
+        if let Some(transport) = &self.transport {
+            return Ok(transport.clone());
+        }
+        #[cfg(feature = "reqwest")]
+        return Ok(TRANSPORT.clone());
+        #[cfg(not(feature = "reqwest"))]
+        Err(Error::NoTransport)
+    }
+
+    /// The timer of the delays between retries, if any
+    #[cfg(feature = "client")]
+    fn timer(&self) -> Option<Arc<dyn Timer>> {
+        if let Some(timer) = &self.timer {
+            return Some(timer.clone());
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        return Some(Arc::new(SystemTimer));
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+
+    /// What `status` means for the request
+    #[cfg(feature = "client")]
+    pub(crate) fn classify(&self, status: StatusCode) -> StatusClass {
+        match &self.status_classifier {
+            Some(classifier) => classifier.classify(status),
+            None if status == StatusCode::OK => StatusClass::Success,
+            None => StatusClass::Error,
+        }
+    }
+
+    /// A fresh `oauth_nonce`
+    fn nonce(&self) -> String {
+        match &self.nonce {
+            Some(nonce) => nonce.nonce(),
+            None => nonce::Alphanumeric::default().nonce(),
+        }
+    }
+
+    /// Current Unix time according to the clock
+    pub(crate) fn now(&self) -> i64 {
+        match &self.clock {
+            Some(clock) => clock.now(),
+            None => SystemClock.now(),
+        }
+    }
+
+    /// Current Unix time at the server: the clock corrected by the recorded
+    /// clock skew
+    pub(crate) fn server_now(&self) -> i64 {
+        self.now()
+            + self
+                .clock_skew
+                .as_ref()
+                .map_or(0, |skew| skew.load(Ordering::Relaxed))
+    }
+
+    /// Unix time to send as `oauth_timestamp`
+    fn timestamp(&self) -> i64 {
+        self.timestamp.unwrap_or_else(|| self.server_now())
+    }
+
+    /// Record the clock skew from the `Date` header of a response
+    #[cfg(feature = "client")]
+    fn update_clock_skew(&self, headers: &HeaderMap) {
+        let skew = match &self.clock_skew {
+            Some(skew) => skew,
+            None => return,
+        };
+        let date = headers
+            .get(DATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| httpdate::parse_http_date(v).ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+        if let Some(date) = date {
+            let offset = date.as_secs() as i64 - self.now();
+            debug!("Clock skew from the server: {}s", offset);
+            skew.store(offset, Ordering::Relaxed);
+        }
+    }
 }
 
 /// Send authorized GET request to the specified URL.
-/// `consumer` is a consumer token.
+/// `consumer` is a consumer token and `token` the access token, if any.
 ///
 /// # Examples
 ///
 /// ```
 /// async {
 ///     let REQUEST_TOKEN: &'static str = "http://oauthbin.com/v1/request-token";
-///     let consumer = oauth_client::Token::new("key", "secret");
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
 ///     let bytes = oauth_client::get(REQUEST_TOKEN, &consumer, None, None).await.unwrap();
 ///     let resp = String::from_utf8(bytes).unwrap();
 /// };
 /// ```
+#[cfg(feature = "client")]
 pub async fn get(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    get_with(uri, consumer, token, other_param, &Config::default()).await
+}
+
+/// [`get`](fn.get.html), with the parameters given as any pairs of strings,
+/// such as a slice, a `Vec` or a map, instead of a `ParamList`.
+///
+/// # Examples
+///
+/// ```
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let bytes = oauth_client::get_with_params(
+///         "http://oauthbin.com/v1/echo",
+///         &consumer,
+///         None,
+///         [("count", "10")],
+///     )
+///     .await
+///     .unwrap();
+/// };
+/// ```
+#[cfg(feature = "client")]
+pub async fn get_with_params<I, K, V>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    params: I,
+) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let uri = uri.as_ref();
+    get(uri, consumer, token, Some(&param::collect_params(params))).await
+}
+
+#[cfg(feature = "client")]
+pub(crate) async fn get_with(
     uri: &str,
     consumer: &Token<'_>,
     token: Option<&Token<'_>>,
     other_param: Option<&ParamList<'_>>,
+    config: &Config,
 ) -> Result<Vec<u8>> {
-    let (header, body) = get_header("GET", uri, consumer, token, other_param);
-    let req_uri = if !body.is_empty() {
-        format!("{}?{}", uri, body)
-    } else {
-        uri.to_string()
-    };
-
-    let rsp = send(CLIENT.get(&req_uri).header(AUTHORIZATION, header)).await?;
-    Ok(rsp)
+    let request = |config: &Config| get_request_with(uri, consumer, token, other_param, config);
+    let rsp = send_with_retry(request, config).await?;
+    Ok(rsp.body)
 }
 
 /// Send authorized POST request to the specified URL.
-/// `consumer` is a consumer token.
+/// `consumer` is a consumer token and `token` the access token, if any.
 ///
 /// # Examples
 ///
 /// ```
-/// # let request = oauth_client::Token::new("key", "secret");
+/// # let request = oauth_client::AccessToken::new("key", "secret");
 /// async {
 ///     let ACCESS_TOKEN: &'static str = "http://oauthbin.com/v1/access-token";
-///     let consumer = oauth_client::Token::new("key", "secret");
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
 ///     let bytes = oauth_client::post(ACCESS_TOKEN, &consumer, Some(&request), None).await.unwrap();
 ///     let resp = String::from_utf8(bytes).unwrap();
 /// };
 /// ```
+#[cfg(feature = "client")]
 pub async fn post(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    post_with(uri, consumer, token, other_param, &Config::default()).await
+}
+
+/// [`post`](fn.post.html), with the parameters given as any pairs of
+/// strings, such as a slice, a `Vec` or a map, instead of a `ParamList`.
+#[cfg(feature = "client")]
+pub async fn post_with_params<I, K, V>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    params: I,
+) -> Result<Vec<u8>>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let uri = uri.as_ref();
+    post(uri, consumer, token, Some(&param::collect_params(params))).await
+}
+
+/// Send authorized POST request to the specified URL, with the fields of
+/// `form` as the parameters.
+/// See [`ParamList::from_form`](struct.ParamList.html#method.from_form).
+///
+/// # Examples
+///
+/// ```
+/// #[derive(serde::Serialize)]
+/// struct Update<'a> {
+///     status: &'a str,
+/// }
+///
+/// # let access = oauth_client::AccessToken::new("token", "secret");
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let update = Update { status: "Hello" };
+///     let bytes = oauth_client::post_form(
+///         "https://api.twitter.com/1.1/statuses/update.json",
+///         &consumer,
+///         Some(&access),
+///         &update,
+///     )
+///     .await
+///     .unwrap();
+/// };
+/// ```
+#[cfg(all(feature = "client", feature = "serde"))]
+pub async fn post_form<T: serde::Serialize + ?Sized>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    form: &T,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let param = ParamList::from_form(form)?;
+    post(uri, consumer, token, Some(&param)).await
+}
+
+/// Send authorized GET request to the specified URL, and deserialize its
+/// JSON body. Fails with `Error::UnexpectedContentType` unless the response
+/// is `application/json` or another `+json` type.
+///
+/// # Examples
+///
+/// ```
+/// #[derive(serde::Deserialize)]
+/// struct User {
+///     screen_name: String,
+/// }
+///
+/// # let access = oauth_client::AccessToken::new("token", "secret");
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let user: User = oauth_client::get_json(
+///         "https://api.twitter.com/1.1/account/verify_credentials.json",
+///         &consumer,
+///         Some(&access),
+///         None,
+///     )
+///     .await
+///     .unwrap();
+/// };
+/// ```
+#[cfg(feature = "json")]
+pub async fn get_json<T: serde::de::DeserializeOwned>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<T> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    get_json_with(uri, consumer, token, other_param, &Config::default()).await
+}
+
+#[cfg(feature = "json")]
+pub(crate) async fn get_json_with<T: serde::de::DeserializeOwned>(
     uri: &str,
     consumer: &Token<'_>,
     token: Option<&Token<'_>>,
     other_param: Option<&ParamList<'_>>,
+    config: &Config,
+) -> Result<T> {
+    let request = |config: &Config| get_request_with(uri, consumer, token, other_param, config);
+    let rsp = send_with_retry(request, config).await?;
+    from_json(&rsp)
+}
+
+/// Send authorized POST request to the specified URL, and deserialize its
+/// JSON body, as [`get_json`](fn.get_json.html)
+#[cfg(feature = "json")]
+pub async fn post_json_response<T: serde::de::DeserializeOwned>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    other_param: Option<&ParamList<'_>>,
+) -> Result<T> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    post_json_response_with(uri, consumer, token, other_param, &Config::default()).await
+}
+
+#[cfg(feature = "json")]
+pub(crate) async fn post_json_response_with<T: serde::de::DeserializeOwned>(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    other_param: Option<&ParamList<'_>>,
+    config: &Config,
+) -> Result<T> {
+    let request = |config: &Config| post_request_with(uri, consumer, token, other_param, config);
+    let rsp = send_with_retry(request, config).await?;
+    from_json(&rsp)
+}
+
+/// Deserialize the body of a JSON response
+#[cfg(feature = "json")]
+fn from_json<T: serde::de::DeserializeOwned>(response: &Response) -> Result<T> {
+    if !is_json(response) {
+        return Err(Error::UnexpectedContentType(
+            content_type(response).to_string(),
+        ));
+    }
+    serde_json::from_slice(&response.body).map_err(|err| Error::Deserialize(Box::new(err)))
+}
+
+/// Value of the `Content-Type` header of `response`, empty if missing
+#[cfg(feature = "client")]
+fn content_type(response: &Response) -> &str {
+    response
+        .headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+}
+
+/// Whether `response` is `application/json` or another `+json` type
+#[cfg(feature = "client")]
+fn is_json(response: &Response) -> bool {
+    let essence = content_type(response)
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim();
+    let essence = essence.to_ascii_lowercase();
+    essence == "application/json" || essence.ends_with("+json")
+}
+
+#[cfg(feature = "client")]
+pub(crate) async fn post_with(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    other_param: Option<&ParamList<'_>>,
+    config: &Config,
 ) -> Result<Vec<u8>> {
-    let (header, body) = get_header("POST", uri, consumer, token, other_param);
+    let rsp = post_response_with(uri, consumer, token, other_param, config).await?;
+    Ok(rsp.body)
+}
 
-    let rsp = send(
-        CLIENT
-            .post(uri)
-            .body(body)
-            .header(AUTHORIZATION, header)
-            .header(CONTENT_TYPE, "application/x-www-form-urlencoded"),
-    )
-    .await?;
-    Ok(rsp)
+/// Send authorized POST request, and return the whole response
+#[cfg(feature = "client")]
+pub(crate) async fn post_response_with(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    other_param: Option<&ParamList<'_>>,
+    config: &Config,
+) -> Result<Response> {
+    let request = |config: &Config| post_request_with(uri, consumer, token, other_param, config);
+    send_with_retry(request, config).await
 }
 
-/// Send request to the server
-async fn send(builder: RequestBuilder) -> Result<Vec<u8>> {
-    let response = builder.send().await?;
-    if response.status() != StatusCode::OK {
-        bail!(HttpStatusError(response.status().into()));
+/// Add an OAuth `Authorization` header to an already built request.
+///
+/// The HTTP method, the URL and the parameters to sign are taken from
+/// `request` itself: query parameters of the URL and, for
+/// `application/x-www-form-urlencoded` requests, the pairs in the body.
+///
+/// # Examples
+///
+/// ```
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let mut request = reqwest::Client::new()
+///     .get("http://oauthbin.com/v1/echo?a=1")
+///     .build()?;
+/// oauth_client::sign(&mut request, &consumer, None)?;
+/// assert!(request.headers().contains_key("authorization"));
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "reqwest")]
+pub fn sign(
+    request: &mut Request,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+) -> Result<()> {
+    let header = request_header(
+        request.method(),
+        request.url(),
+        request.headers(),
+        request.body().and_then(|b| b.as_bytes()),
+        consumer,
+        token.map(|t| &**t),
+    );
+    sign_headers(request.headers_mut(), header)
+}
+
+/// Sign a `reqwest::RequestBuilder` in place of this crate's request
+/// functions.
+///
+/// The request is built and signed as by [`sign`](fn.sign.html), so the
+/// method, the URL, the headers and any form body must be set before calling
+/// `oauth1`.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::OAuthSignExt;
+///
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let token = oauth_client::AccessToken::new("token", "secret");
+///     let response = reqwest::Client::new()
+///         .post("http://oauthbin.com/v1/echo")
+///         .form(&[("a", "1")])
+///         .oauth1(&consumer, Some(&token))
+///         .unwrap()
+///         .send()
+///         .await;
+/// };
+/// ```
+#[cfg(feature = "reqwest")]
+pub trait OAuthSignExt: Sized {
+    /// Add an OAuth `Authorization` header to the request
+    fn oauth1(self, consumer: &ConsumerToken<'_>, token: Option<&AccessToken<'_>>) -> Result<Self>;
+}
+
+#[cfg(feature = "reqwest")]
+impl OAuthSignExt for reqwest::RequestBuilder {
+    fn oauth1(self, consumer: &ConsumerToken<'_>, token: Option<&AccessToken<'_>>) -> Result<Self> {
+        let (client, request) = self.build_split();
+        let mut request = request?;
+        sign(&mut request, consumer, token)?;
+        Ok(reqwest::RequestBuilder::from_parts(client, request))
+    }
+}
+
+/// Send a request previously signed with [`sign`](fn.sign.html).
+///
+/// # Examples
+///
+/// ```
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let mut request = reqwest::Client::new()
+///         .get("http://oauthbin.com/v1/request-token")
+///         .build()
+///         .unwrap();
+///     oauth_client::sign(&mut request, &consumer, None).unwrap();
+///     let bytes = oauth_client::send_signed(request).await.unwrap();
+/// };
+/// ```
+#[cfg(feature = "reqwest")]
+pub async fn send_signed(request: Request) -> Result<Vec<u8>> {
+    let response = CLIENT.execute(request).await?;
+    let url = response.url().to_string();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    check_status(&url, status, &headers, &body)?;
+    Ok(body.to_vec())
+}
+
+/// Send the request built by `request`, retrying it as configured, until the
+/// cancellation token if any is cancelled
+#[cfg(feature = "client")]
+async fn send_with_retry<F>(request: F, config: &Config) -> Result<Response>
+where
+    F: Fn(&Config) -> Result<SignedRequest>,
+{
+    match &config.cancellation {
+        Some(token) => token.run(send_attempts(request, config)).await,
+        None => send_attempts(request, config).await,
+    }
+}
+
+/// Send the request built by `request`, retrying it as configured
+#[cfg(feature = "client")]
+async fn send_attempts<F>(request: F, config: &Config) -> Result<Response>
+where
+    F: Fn(&Config) -> Result<SignedRequest>,
+{
+    let mut attempt = 1;
+    loop {
+        let signed = request(config)?;
+        let retry_allowed = config.retry_non_idempotent || retry::is_idempotent(&signed);
+        let context = RequestContext::new(&signed, attempt);
+        let err = match send_fixing_timestamp(signed, &request, config).await {
+            Err(err) => err,
+            rsp => return rsp,
+        };
+        let delay = match retry_delay(&err, attempt, config) {
+            Some(delay) if retry_allowed || retry::is_unprocessed(&err) => delay,
+            _ => return Err(err.with_context(context)),
+        };
+        match config.timer() {
+            Some(timer) => {
+                debug!("Retrying in {:?} after error: {}", delay, err);
+                timer.sleep(delay).await;
+                attempt += 1;
+            }
+            None => return Err(err.with_context(context)),
+        }
     }
-    Ok(response.bytes().await?.to_vec())
+}
+
+/// The delay before retrying a request failing with `err` on its attempt
+/// number `attempt`, `None` if it is not retried
+#[cfg(feature = "client")]
+fn retry_delay(err: &Error, attempt: u32, config: &Config) -> Option<std::time::Duration> {
+    let delay = match (err.retry_after(), &config.retry_policy) {
+        (Some(delay), _) if attempt < config.retry_after => delay,
+        (_, Some(policy))
+            if attempt < policy.max_attempts() && is_retryable(policy, err, config) =>
+        {
+            policy.delay(attempt)
+        }
+        _ => return None,
+    };
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(deadline) = config.deadline {
+        if delay >= deadline.remaining() {
+            debug!("No time left before the deadline to retry");
+            return None;
+        }
+    }
+    Some(delay)
+}
+
+/// Whether `err` is retried by `policy`, or has a status classified as
+/// [`StatusClass::Retry`](enum.StatusClass.html#variant.Retry)
+#[cfg(feature = "client")]
+fn is_retryable(policy: &RetryPolicy, err: &Error, config: &Config) -> bool {
+    let status = err
+        .status()
+        .and_then(|status| StatusCode::from_u16(status).ok());
+    policy.is_retryable(err)
+        || status.is_some_and(|status| config.classify(status) == StatusClass::Retry)
+}
+
+/// Send `signed`, signing it again with `request` and the timestamp of the
+/// provider if it refused ours
+#[cfg(feature = "client")]
+async fn send_fixing_timestamp<F>(
+    signed: SignedRequest,
+    request: &F,
+    config: &Config,
+) -> Result<Response>
+where
+    F: Fn(&Config) -> Result<SignedRequest>,
+{
+    match send(signed, config).await {
+        Err(Error::Problem(problem)) => match problem.acceptable_timestamp(config.now()) {
+            Some(timestamp) => {
+                debug!("Timestamp refused, retrying with {}", timestamp);
+                let mut config = config.clone();
+                config.timestamp = Some(timestamp);
+                send(request(&config)?, &config).await
+            }
+            None => Err(problem.into()),
+        },
+        rsp => rsp,
+    }
+}
+
+/// Send request to the server and check the response status, within the
+/// deadline if any
+#[cfg(feature = "client")]
+async fn send(request: SignedRequest, config: &Config) -> Result<Response> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(deadline) = config.deadline {
+        let mut request = request;
+        let remaining = deadline.remaining();
+        if remaining == std::time::Duration::ZERO {
+            return Err(Error::deadline_exceeded());
+        }
+        request.timeout = Some(remaining);
+        let send = send_guarded(request, config);
+        return match timer::timeout(&*config.timer().unwrap(), remaining, send).await {
+            Some(rsp) => rsp,
+            None => Err(Error::deadline_exceeded()),
+        };
+    }
+    send_guarded(request, config).await
+}
+
+/// Send request to the server and check the response status, unless the
+/// circuit breaker is open
+#[cfg(feature = "client")]
+async fn send_guarded(request: SignedRequest, config: &Config) -> Result<Response> {
+    let breaker = match &config.circuit_breaker {
+        Some(breaker) => breaker,
+        None => return send_unguarded(request, config).await,
+    };
+    let probe = breaker.acquire(config.now())?;
+    let rsp = send_unguarded(request, config).await;
+    probe.record(&rsp, config.now());
+    rsp
+}
+
+/// Send request to the server and check the response status, ignoring the
+/// circuit breaker
+#[cfg(feature = "client")]
+async fn send_unguarded(mut request: SignedRequest, config: &Config) -> Result<Response> {
+    request.max_response_size = config.max_response_size;
+    let cached = config
+        .cache
+        .as_ref()
+        .and_then(|cache| Some((cache, cache.key(&request)?)));
+    if let Some((cache, key)) = &cached {
+        cache.add_conditions(key, &mut request.headers);
+    }
+    let transport = config.transport()?;
+    for interceptor in &config.interceptors {
+        interceptor.before(&mut request);
+    }
+    let audit = audit::Audit::start(config, &request);
+    #[cfg(feature = "opentelemetry")]
+    let span = otel::start(&mut request);
+    #[cfg(all(feature = "tracing", not(target_arch = "wasm32")))]
+    let response = trace::execute(&*transport, request).await;
+    #[cfg(not(all(feature = "tracing", not(target_arch = "wasm32"))))]
+    let response = transport.execute(request).await;
+    #[cfg(feature = "opentelemetry")]
+    otel::end(span, &response);
+    if let Some(audit) = audit {
+        audit.finish(response.as_ref().ok().map(|response| response.status));
+    }
+    let mut response = response?;
+    if let Some((cache, key)) = cached {
+        response = cache.update(key, response);
+    }
+    for interceptor in config.interceptors.iter().rev() {
+        interceptor.after(&response);
+    }
+    // Custom transports may ignore the limit of the request.
+    transport::check_size(config.max_response_size, response.body.len() as u64)?;
+    config.update_clock_skew(&response.headers);
+    check_status_with(
+        &response.url,
+        response.status,
+        &response.headers,
+        &response.body,
+        config,
+    )?;
+    Ok(response)
 }
 
 #[cfg(test)]
 mod tests {
-    use super::encode;
-    use std::collections::HashMap;
+    #[test]
+    fn try_new() {
+        use super::{Error, Token};
+
+        assert!(Token::try_new("key", "").is_ok());
+        for (key, secret, field) in [
+            ("", "s", "key"),
+            (" key", "s", "key"),
+            ("k", "a\rb", "secret"),
+        ] {
+            match Token::try_new(key, secret) {
+                Err(Error::InvalidCredential { field: f, .. }) => assert_eq!(f, field),
+                rsp => panic!("unexpected {:?}", rsp),
+            }
+        }
+    }
 
     #[test]
-    fn query() {
-        let mut map = HashMap::new();
-        let _ = map.insert("aaa".into(), "AAA".into());
-        let _ = map.insert("bbbb".into(), "BBBB".into());
-        let query = super::join_query(&map);
-        assert_eq!("aaa=AAA&bbbb=BBBB", query);
+    fn from_env() {
+        use super::{Error, Token};
+
+        std::env::set_var("OAUTH_CLIENT_TEST_KEY", "key");
+        std::env::remove_var("OAUTH_CLIENT_TEST_SECRET");
+        match Token::from_env("OAUTH_CLIENT_TEST_KEY", "OAUTH_CLIENT_TEST_SECRET") {
+            Err(Error::Env { name, .. }) => assert_eq!(name, "OAUTH_CLIENT_TEST_SECRET"),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        std::env::set_var("OAUTH_CLIENT_TEST_SECRET", "secret");
+        let token = Token::from_env("OAUTH_CLIENT_TEST_KEY", "OAUTH_CLIENT_TEST_SECRET").unwrap();
+        assert_eq!(token.expose_secret(), "secret");
     }
 
     #[test]
-    fn test_encode() {
-        let method = "GET";
-        let uri = "http://oauthbin.com/v1/request-token";
-        let encoded_uri = "http%3A%2F%2Foauthbin.com%2Fv1%2Frequest-token";
-        let query = [
-            "oauth_consumer_key=key&",
-            "oauth_nonce=s6HGl3GhmsDsmpgeLo6lGtKs7rQEzzsA&",
-            "oauth_signature_method=HMAC-SHA1&",
-            "oauth_timestamp=1471445561&",
-            "oauth_version=1.0",
-        ]
-        .iter()
-        .cloned()
-        .collect::<String>();
-        let encoded_query = [
-            "oauth_consumer_key%3Dkey%26",
-            "oauth_nonce%3Ds6HGl3GhmsDsmpgeLo6lGtKs7rQEzzsA%26",
-            "oauth_signature_method%3DHMAC-SHA1%26",
-            "oauth_timestamp%3D1471445561%26",
-            "oauth_version%3D1.0",
-        ]
-        .iter()
-        .cloned()
-        .collect::<String>();
-
-        assert_eq!(encode(method), "GET");
-        assert_eq!(encode(uri), encoded_uri);
-        assert_eq!(encode(&query), encoded_query);
+    #[cfg(feature = "reqwest")]
+    fn oauth1() {
+        use super::{AccessToken, ConsumerToken, OAuthSignExt};
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let token = AccessToken::new("token", "secret");
+        let client = reqwest::Client::new();
+        let request = client
+            .post("http://example.com/echo")
+            .form(&[("a", "1")])
+            .oauth1(&consumer, Some(&token))
+            .unwrap()
+            .build()
+            .unwrap();
+        let header = request.headers()["authorization"].to_str().unwrap();
+        assert!(header.starts_with("OAuth "));
+        assert!(header.contains("oauth_token=\"token\""));
+        assert_eq!(request.body().and_then(|b| b.as_bytes()), Some(&b"a=1"[..]));
+
+        let rsp = client
+            .get("http://example.com/")
+            .header("x-bad", "a\nb")
+            .oauth1(&consumer, None);
+        assert!(rsp.is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn clock_skew() {
+        use super::{Config, HeaderMap, DATE};
+        use std::sync::atomic::AtomicI64;
+        use std::sync::Arc;
+        use std::time::{Duration, SystemTime};
+
+        let config = Config {
+            clock_skew: Some(Arc::new(AtomicI64::new(0))),
+            ..Config::default()
+        };
+        let server = SystemTime::now() + Duration::from_secs(3600);
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert(DATE, httpdate::fmt_http_date(server).parse().unwrap());
+        config.update_clock_skew(&headers);
+        assert!((config.timestamp() - config.now() - 3600).abs() <= 1);
     }
 }
@@ -0,0 +1,167 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Loopback redirect listener for native applications.
+//!
+//! Requires the `loopback` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # let flow = oauth_client::OAuthFlow::new(
+//! #     oauth_client::Provider::new("http://a", "http://b", "http://c"),
+//! #     oauth_client::ConsumerToken::new("key", "secret"),
+//! # );
+//! async {
+//!     let access = oauth_client::loopback::authorize(&flow).await.unwrap();
+//! };
+//! ```
+
+use crate::{AccessTokenResponse, Error, OAuthFlow, Result};
+use log::*;
+use std::collections::HashMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpListener;
+
+const CALLBACK_PATH: &str = "/callback";
+
+const RESPONSE: &str = "HTTP/1.1 200 OK\r\n\
+                        Content-Type: text/html; charset=utf-8\r\n\
+                        Connection: close\r\n\r\n\
+                        <html><body>Authorization complete. You can close this window.</body></html>";
+
+const NOT_FOUND: &str = "HTTP/1.1 404 Not Found\r\nConnection: close\r\n\r\n";
+
+/// Run the whole `flow`, using a loopback listener as the callback.
+///
+/// The authorization URL is opened in the default browser and the verifier is
+/// taken from the provider redirect.
+pub async fn authorize(flow: &OAuthFlow<'_>) -> Result<AccessTokenResponse> {
+    let listener = Listener::bind().await?;
+    let state = flow.start(&listener.callback_url()).await?;
+    webbrowser::open(&state.authorize_url)?;
+
+    let redirect = listener.wait().await?;
+    if redirect.token != state.request_token.key {
+        return Err(Error::UnexpectedToken);
+    }
+    flow.finish(&state, &redirect.verifier).await
+}
+
+/// Parameters of the provider redirect.
+#[derive(Clone, Debug)]
+pub struct Redirect {
+    /// Value of `oauth_token`
+    pub token: String,
+    /// Value of `oauth_verifier`
+    pub verifier: String,
+}
+
+/// HTTP listener bound to an ephemeral port on localhost.
+#[derive(Debug)]
+pub struct Listener {
+    listener: TcpListener,
+    addr: SocketAddr,
+}
+
+impl Listener {
+    /// Bind to an ephemeral port on `127.0.0.1`
+    pub async fn bind() -> Result<Listener> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        Ok(Listener { listener, addr })
+    }
+
+    /// URL to send as `oauth_callback`
+    pub fn callback_url(&self) -> String {
+        format!("http://{}{}", self.addr, CALLBACK_PATH)
+    }
+
+    /// Wait for the provider redirect to the callback URL
+    pub async fn wait(self) -> Result<Redirect> {
+        loop {
+            let (mut stream, peer) = self.listener.accept().await?;
+            let mut reader = BufReader::new(&mut stream);
+            let mut request_line = String::new();
+            let _ = reader.read_line(&mut request_line).await?;
+            // Drain the headers; the request line is all we need.
+            let mut line = String::new();
+            while reader.read_line(&mut line).await? > 2 {
+                line.clear();
+            }
+            debug!(
+                "Loopback request from {}: {}",
+                peer,
+                request_line.trim_end()
+            );
+
+            match parse_request_line(&request_line) {
+                Some(param) => {
+                    stream.write_all(RESPONSE.as_bytes()).await?;
+                    return redirect(param);
+                }
+                None => stream.write_all(NOT_FOUND.as_bytes()).await?,
+            }
+        }
+    }
+}
+
+/// Extract the query of a request to the callback path
+fn parse_request_line(line: &str) -> Option<HashMap<String, String>> {
+    let target = line.split(' ').nth(1)?;
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (target, ""),
+    };
+    if path != CALLBACK_PATH {
+        return None;
+    }
+    Some(
+        url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect(),
+    )
+}
+
+fn redirect(mut param: HashMap<String, String>) -> Result<Redirect> {
+    if param.contains_key("denied") {
+        return Err(Error::Denied);
+    }
+    let token = param
+        .remove("oauth_token")
+        .ok_or_else(|| Error::MissingParameter("oauth_token".into()))?;
+    let verifier = param
+        .remove("oauth_verifier")
+        .ok_or_else(|| Error::MissingParameter("oauth_verifier".into()))?;
+    Ok(Redirect { token, verifier })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_request_line, redirect};
+
+    #[test]
+    fn callback_request() {
+        let line = "GET /callback?oauth_token=tok&oauth_verifier=v%2F1 HTTP/1.1\r\n";
+        let redirect = redirect(parse_request_line(line).unwrap()).unwrap();
+        assert_eq!(redirect.token, "tok");
+        assert_eq!(redirect.verifier, "v/1");
+    }
+
+    #[test]
+    fn other_request() {
+        assert!(parse_request_line("GET /favicon.ico HTTP/1.1\r\n").is_none());
+    }
+
+    #[test]
+    fn denied() {
+        assert!(
+            redirect(parse_request_line("GET /callback?denied=tok HTTP/1.1").unwrap()).is_err()
+        );
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Generators of `oauth_nonce` values.
+//!
+//! The built-in generators draw from `rand::thread_rng()`, a
+//! cryptographically secure generator seeded by the operating system.
+
+use rand::{CryptoRng, Rng};
+use std::fmt;
+use std::iter;
+
+/// Generator of `oauth_nonce` values.
+///
+/// Implemented for closures returning a fresh nonce.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::nonce::Hex;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).nonce(Hex::new(16));
+/// ```
+pub trait NonceProvider: Send + Sync {
+    /// Generate a new nonce
+    fn nonce(&self) -> String;
+}
+
+impl fmt::Debug for dyn NonceProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("NonceProvider")
+    }
+}
+
+impl<F> NonceProvider for F
+where
+    F: Fn() -> String + Send + Sync,
+{
+    fn nonce(&self) -> String {
+        self()
+    }
+}
+
+/// Shortest nonce generated by [`Alphanumeric`](struct.Alphanumeric.html)
+/// and [`Hex`](struct.Hex.html): an empty or short nonce defeats the replay
+/// protection
+pub const MIN_LEN: usize = 8;
+
+fn rng() -> impl Rng + CryptoRng {
+    rand::thread_rng()
+}
+
+/// Alphanumeric nonces, 32 characters long by default.
+#[derive(Clone, Copy, Debug)]
+pub struct Alphanumeric {
+    len: usize,
+}
+
+impl Alphanumeric {
+    /// Create new generator of `len` characters long nonces, at least
+    /// [`MIN_LEN`](constant.MIN_LEN.html)
+    pub fn new(len: usize) -> Alphanumeric {
+        Alphanumeric {
+            len: len.max(MIN_LEN),
+        }
+    }
+}
+
+impl Default for Alphanumeric {
+    fn default() -> Alphanumeric {
+        Alphanumeric::new(32)
+    }
+}
+
+impl NonceProvider for Alphanumeric {
+    fn nonce(&self) -> String {
+        let mut rng = rng();
+        iter::repeat(())
+            .map(|()| rng.sample(rand::distributions::Alphanumeric))
+            .map(char::from)
+            .take(self.len)
+            .collect()
+    }
+}
+
+/// Lowercase hexadecimal nonces, 32 characters long by default.
+#[derive(Clone, Copy, Debug)]
+pub struct Hex {
+    len: usize,
+}
+
+impl Hex {
+    /// Create new generator of `len` characters long nonces, at least
+    /// [`MIN_LEN`](constant.MIN_LEN.html)
+    pub fn new(len: usize) -> Hex {
+        Hex {
+            len: len.max(MIN_LEN),
+        }
+    }
+}
+
+impl Default for Hex {
+    fn default() -> Hex {
+        Hex::new(32)
+    }
+}
+
+impl NonceProvider for Hex {
+    fn nonce(&self) -> String {
+        let mut rng = rng();
+        iter::repeat(())
+            .map(|()| char::from_digit(rng.gen_range(0..16), 16).unwrap())
+            .take(self.len)
+            .collect()
+    }
+}
+
+/// Random (version 4) UUID nonces, such as `a8098c1a-f86e-41da-9b7c-00c04fd430c8`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UuidV4;
+
+impl NonceProvider for UuidV4 {
+    fn nonce(&self) -> String {
+        let mut bytes: [u8; 16] = rng().gen();
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        let hex = bytes
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>();
+        format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alphanumeric, Hex, NonceProvider, UuidV4, MIN_LEN};
+
+    #[test]
+    fn alphanumeric() {
+        let nonce = Alphanumeric::new(10).nonce();
+        assert_eq!(nonce.len(), 10);
+        assert!(nonce.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(Alphanumeric::default().nonce().len(), 32);
+    }
+
+    #[test]
+    fn hex() {
+        let nonce = Hex::new(16).nonce();
+        assert_eq!(nonce.len(), 16);
+        assert!(nonce.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn min_len() {
+        assert_eq!(Alphanumeric::new(0).nonce().len(), MIN_LEN);
+        assert_eq!(Hex::new(0).nonce().len(), MIN_LEN);
+        assert_eq!(Hex::new(3).nonce().len(), MIN_LEN);
+    }
+
+    #[test]
+    fn uuid() {
+        let nonce = UuidV4.nonce();
+        let groups = nonce.split('-').map(str::len).collect::<Vec<_>>();
+        assert_eq!(groups, [8, 4, 4, 4, 12]);
+        assert_eq!(&nonce[14..15], "4");
+        assert!("89ab".contains(&nonce[19..20]));
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! OpenTelemetry client spans around the requests sent.
+//!
+//! As with `tracing`, a span records the method, the host and the status of
+//! a request, never its URL, headers or body, which carry the tokens and the
+//! signature.
+
+use crate::transport::{Response, SignedRequest};
+use crate::Result;
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::global::{self, BoxedSpan};
+use opentelemetry::propagation::Injector;
+use opentelemetry::trace::{Span, SpanKind, Status, TraceContextExt, Tracer};
+use opentelemetry::{Context, KeyValue};
+
+/// Start the client span of `request`, a child of the current context, and
+/// add its context to the headers of `request`
+pub(crate) fn start(request: &mut SignedRequest) -> BoxedSpan {
+    let host = url::Url::parse(&request.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let tracer = global::tracer("oauth-client");
+    let span = tracer
+        .span_builder(request.method.to_string())
+        .with_kind(SpanKind::Client)
+        .with_attributes([
+            KeyValue::new("http.request.method", request.method.to_string()),
+            KeyValue::new("server.address", host),
+        ])
+        .start_with_context(&tracer, &Context::current());
+    let cx = Context::current().with_remote_span_context(span.span_context().clone());
+    global::get_text_map_propagator(|propagator| {
+        propagator.inject_context(&cx, &mut Headers(&mut request.headers))
+    });
+    span
+}
+
+/// Record the outcome of a request on its span, and end it
+pub(crate) fn end(mut span: BoxedSpan, response: &Result<Response>) {
+    match response {
+        Ok(response) => {
+            let status = response.status.as_u16();
+            span.set_attribute(KeyValue::new("http.response.status_code", status as i64));
+            if status >= 400 {
+                span.set_status(Status::error(status.to_string()));
+            }
+        }
+        Err(err) => span.set_status(Status::error(err.to_string())),
+    }
+    span.end();
+}
+
+/// The headers of a request, as an `Injector`
+struct Headers<'a>(&'a mut HeaderMap);
+
+impl Injector for Headers<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            let _ = self.0.insert(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{ConsumerToken, Result, Session};
+    use opentelemetry::trace::{Span, TraceContextExt, Tracer, TracerProvider};
+    use opentelemetry::{global, Context};
+    use opentelemetry_sdk::propagation::TraceContextPropagator;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::sync::{Arc, Mutex};
+
+    /// Record of the `traceparent` headers
+    struct Traceparent(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Traceparent {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            let header = request.headers["traceparent"].to_str().unwrap();
+            self.0.lock().unwrap().push(header.to_string());
+            Ok(Response {
+                url: request.url,
+                status: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: Vec::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn traceparent() {
+        let provider = SdkTracerProvider::builder().build();
+        global::set_tracer_provider(provider.clone());
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let parent = provider.tracer("test").start("parent");
+        let parent = parent.span_context().clone();
+        let _guard = Context::current()
+            .with_remote_span_context(parent.clone())
+            .attach();
+
+        let headers = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .transport(Traceparent(headers.clone()));
+        session.get("http://example.com/a", None).await.unwrap();
+
+        let headers = headers.lock().unwrap();
+        let fields = headers[0].split('-').collect::<Vec<_>>();
+        assert_eq!(fields[0], "00");
+        assert_eq!(fields[1], parent.trace_id().to_string());
+        assert_ne!(fields[2], parent.span_id().to_string());
+    }
+}
@@ -0,0 +1,147 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Cursor-based pagination, as a stream of pages.
+//!
+//! Requires the `pagination` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use futures_util::StreamExt;
+//! use oauth_client::pagination::PageRequest;
+//!
+//! async {
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//!     let access = oauth_client::AccessToken::new("token", "secret");
+//!     let session = oauth_client::Session::new(consumer, Some(access));
+//!     let request = PageRequest::new("https://api.twitter.com/1.1/followers/ids.json", "cursor")
+//!         .param(oauth_client::params! { "cursor" => "-1" });
+//!     let pages = session.paginate(request, |body| {
+//!         let page: serde_json::Value = serde_json::from_slice(body).ok()?;
+//!         let cursor = page["next_cursor_str"].as_str()?;
+//!         Some(cursor.to_string()).filter(|cursor| cursor != "0")
+//!     });
+//!     futures_util::pin_mut!(pages);
+//!     while let Some(page) = pages.next().await {
+//!         println!("{} bytes", page.unwrap().len());
+//!     }
+//! };
+//! ```
+
+use crate::{ParamList, Result, Session};
+use futures_util::stream::{self, Stream};
+
+/// The GET request of the first page, and the parameter carrying the cursor
+/// of the next ones.
+#[derive(Clone, Debug)]
+pub struct PageRequest {
+    uri: String,
+    param: ParamList<'static>,
+    cursor: String,
+}
+
+impl PageRequest {
+    /// Create new request of `uri`, paginated with the parameter `cursor`
+    pub fn new<U: Into<String>, C: Into<String>>(uri: U, cursor: C) -> PageRequest {
+        PageRequest {
+            uri: uri.into(),
+            param: ParamList::new(),
+            cursor: cursor.into(),
+        }
+    }
+
+    /// Set the parameters of the first page, kept on the next ones but for
+    /// the cursor
+    pub fn param(mut self, param: ParamList<'static>) -> PageRequest {
+        self.param = param;
+        self
+    }
+}
+
+/// The bodies of the pages of `request`, until `next_cursor` returns `None`
+/// or a request fails
+pub(crate) fn paginate<'a, F>(
+    session: &'a Session,
+    request: PageRequest,
+    next_cursor: F,
+) -> impl Stream<Item = Result<Vec<u8>>> + 'a
+where
+    F: FnMut(&[u8]) -> Option<String> + 'a,
+{
+    let PageRequest { uri, param, cursor } = request;
+    stream::unfold(
+        (Some(param), next_cursor),
+        move |(param, mut next_cursor)| {
+            let uri = uri.clone();
+            let cursor = cursor.clone();
+            async move {
+                let mut param = param?;
+                let body = match session.get(&uri, Some(&param)).await {
+                    Ok(body) => body,
+                    Err(err) => return Some((Err(err), (None, next_cursor))),
+                };
+                let next = next_cursor(&body).map(|next| {
+                    let _ = param.insert(cursor.into(), next.into());
+                    param
+                });
+                Some((Ok(body), (next, next_cursor)))
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PageRequest;
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{ConsumerToken, Result, Session};
+    use futures_util::StreamExt;
+    use std::sync::{Arc, Mutex};
+
+    /// Pages `a`, `b` and `c`, the cursor being the next page
+    struct Pages(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Pages {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            let authorization = request.headers["authorization"].to_str().unwrap();
+            self.0.lock().unwrap().push(authorization.to_string());
+            let url = url::Url::parse(&request.url).unwrap();
+            let page = url
+                .query_pairs()
+                .find(|(k, _)| k == "page")
+                .map_or("a".to_string(), |(_, v)| v.into_owned());
+            Ok(Response {
+                url: request.url,
+                status: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: page.into_bytes(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn paginate() {
+        let headers = Arc::new(Mutex::new(Vec::new()));
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .transport(Pages(headers.clone()));
+        let request = PageRequest::new("http://example.com/items", "page");
+        let pages = session.paginate(request, |body| match body {
+            b"a" => Some("b".to_string()),
+            b"b" => Some("c".to_string()),
+            _ => None,
+        });
+        let pages = pages.map(Result::unwrap).collect::<Vec<_>>().await;
+        assert_eq!(pages, [b"a", b"b", b"c"]);
+
+        let headers = headers.lock().unwrap();
+        assert_eq!(headers.len(), 3);
+        assert_ne!(headers[0], headers[1]);
+        assert_ne!(headers[1], headers[2]);
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Request parameters.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::iter::FromIterator;
+use std::{slice, vec};
+
+/// Parameters of a request, as name/value pairs in insertion order.
+///
+/// A name may appear several times, as in `tag=a&tag=b`: all the pairs are
+/// signed and sent. See RFC 5849, section 3.4.1.3.
+///
+/// # Examples
+///
+/// ```
+/// let mut param = oauth_client::ParamList::new();
+/// param.push("tag".into(), "a".into());
+/// param.push("tag".into(), "b".into());
+/// let _ = param.insert("count".into(), "10".into());
+/// assert_eq!(param.get_all("tag").collect::<Vec<_>>(), ["a", "b"]);
+/// assert_eq!(param.get("count"), Some("10"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParamList<'a> {
+    pairs: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl<'a> ParamList<'a> {
+    /// Create an empty list
+    pub fn new() -> ParamList<'a> {
+        ParamList { pairs: Vec::new() }
+    }
+
+    /// Create a builder of a list
+    pub fn builder() -> ParamListBuilder<'a> {
+        ParamListBuilder::default()
+    }
+
+    /// Create a list from the fields of `value`, serialized as a form.
+    /// Fields set to `None` are left out; nested structs and sequences are
+    /// not supported.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// #[derive(serde::Serialize)]
+    /// struct Update<'a> {
+    ///     status: &'a str,
+    ///     in_reply_to: Option<u64>,
+    ///     trim_user: bool,
+    /// }
+    ///
+    /// let update = Update { status: "Hello", in_reply_to: None, trim_user: true };
+    /// let param = oauth_client::ParamList::from_form(&update).unwrap();
+    /// assert_eq!(param.get("status"), Some("Hello"));
+    /// assert_eq!(param.get("trim_user"), Some("true"));
+    /// assert!(!param.contains_key("in_reply_to"));
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn from_form<T: serde::Serialize + ?Sized>(value: &T) -> crate::Result<ParamList<'static>> {
+        let form = serde_urlencoded::to_string(value)
+            .map_err(|err| crate::Error::Serialize(err.into()))?;
+        Ok(url::form_urlencoded::parse(form.as_bytes())
+            .map(|(k, v)| (k.into_owned(), v.into_owned()))
+            .collect())
+    }
+
+    /// Add a pair, keeping the pairs with the same name
+    pub fn push(&mut self, name: Cow<'a, str>, value: Cow<'a, str>) {
+        self.pairs.push((name, value));
+    }
+
+    /// Set the value of `name`, replacing all its previous values.
+    /// Returns the first replaced value, like `HashMap::insert`.
+    pub fn insert(&mut self, name: Cow<'a, str>, value: Cow<'a, str>) -> Option<Cow<'a, str>> {
+        let old = self.remove(&name);
+        self.pairs.push((name, value));
+        old
+    }
+
+    /// Remove all the values of `name`, returning the first one
+    pub fn remove(&mut self, name: &str) -> Option<Cow<'a, str>> {
+        let mut old = None;
+        let mut i = 0;
+        while i < self.pairs.len() {
+            if self.pairs[i].0 == name {
+                let (_, value) = self.pairs.remove(i);
+                old = old.or(Some(value));
+            } else {
+                i += 1;
+            }
+        }
+        old
+    }
+
+    /// The first value of `name`
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.pairs
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// All the values of `name`, in insertion order
+    pub fn get_all<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b str> + 'b {
+        self.pairs
+            .iter()
+            .filter(move |(k, _)| k == name)
+            .map(|(_, v)| v.as_ref())
+    }
+
+    /// Whether `name` has a value
+    pub fn contains_key(&self, name: &str) -> bool {
+        self.pairs.iter().any(|(k, _)| k == name)
+    }
+
+    /// The pairs, in insertion order
+    pub fn iter(&self) -> slice::Iter<'_, (Cow<'a, str>, Cow<'a, str>)> {
+        self.pairs.iter()
+    }
+
+    /// Number of pairs
+    pub fn len(&self) -> usize {
+        self.pairs.len()
+    }
+
+    /// Whether the list has no pair
+    pub fn is_empty(&self) -> bool {
+        self.pairs.is_empty()
+    }
+}
+
+impl<'a, K, V> FromIterator<(K, V)> for ParamList<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    fn from_iter<I>(iter: I) -> ParamList<'a>
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        ParamList {
+            pairs: iter
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+impl<'a, K, V> Extend<(K, V)> for ParamList<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        self.pairs
+            .extend(iter.into_iter().map(|(k, v)| (k.into(), v.into())));
+    }
+}
+
+impl<'a> From<HashMap<Cow<'a, str>, Cow<'a, str>>> for ParamList<'a> {
+    fn from(map: HashMap<Cow<'a, str>, Cow<'a, str>>) -> ParamList<'a> {
+        map.into_iter().collect()
+    }
+}
+
+/// Copy the pairs of `params`, such as a slice, a `Vec` or a map of strings
+pub(crate) fn collect_params<I, K, V>(params: I) -> ParamList<'static>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    params
+        .into_iter()
+        .map(|(k, v)| (k.as_ref().to_string(), v.as_ref().to_string()))
+        .collect()
+}
+
+/// Builder of a [`ParamList`](struct.ParamList.html).
+///
+/// # Examples
+///
+/// ```
+/// let text = String::from("Hello");
+/// let param = oauth_client::ParamList::builder()
+///     .add("status", text)
+///     .add("tag", "a")
+///     .add("tag", "b")
+///     .build();
+/// assert_eq!(param.get("status"), Some("Hello"));
+/// assert_eq!(param.len(), 3);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ParamListBuilder<'a> {
+    param: ParamList<'a>,
+}
+
+impl<'a> ParamListBuilder<'a> {
+    /// Add a pair, keeping the pairs with the same name
+    pub fn add<K, V>(mut self, name: K, value: V) -> ParamListBuilder<'a>
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        self.param.push(name.into(), value.into());
+        self
+    }
+
+    /// Add a pair if `value` is some, for optional parameters
+    pub fn add_opt<K, V>(self, name: K, value: Option<V>) -> ParamListBuilder<'a>
+    where
+        K: Into<Cow<'a, str>>,
+        V: Into<Cow<'a, str>>,
+    {
+        match value {
+            Some(value) => self.add(name, value),
+            None => self,
+        }
+    }
+
+    /// The list of the pairs added
+    pub fn build(self) -> ParamList<'a> {
+        self.param
+    }
+}
+
+/// Create a [`ParamList`](struct.ParamList.html) from `name => value` pairs,
+/// in order. Names and values are anything convertible to `Cow<str>`.
+///
+/// # Examples
+///
+/// ```
+/// let text = String::from("Hello");
+/// let param = oauth_client::params! {
+///     "status" => text,
+///     "count" => 10.to_string(),
+/// };
+/// assert_eq!(param.get("count"), Some("10"));
+/// assert!(oauth_client::params! {}.is_empty());
+/// ```
+#[macro_export]
+macro_rules! params {
+    () => {
+        $crate::ParamList::new()
+    };
+    ($($name:expr => $value:expr),+ $(,)?) => {{
+        let mut param = $crate::ParamList::new();
+        $(param.push(::std::convert::Into::into($name), ::std::convert::Into::into($value));)+
+        param
+    }};
+}
+
+impl<'a> IntoIterator for ParamList<'a> {
+    type Item = (Cow<'a, str>, Cow<'a, str>);
+    type IntoIter = vec::IntoIter<(Cow<'a, str>, Cow<'a, str>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.into_iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b ParamList<'a> {
+    type Item = &'b (Cow<'a, str>, Cow<'a, str>);
+    type IntoIter = slice::Iter<'b, (Cow<'a, str>, Cow<'a, str>)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.pairs.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParamList;
+
+    #[test]
+    fn insert_replaces() {
+        let mut param = ParamList::new();
+        param.push("a".into(), "1".into());
+        param.push("b".into(), "2".into());
+        param.push("a".into(), "3".into());
+        assert_eq!(param.insert("a".into(), "4".into()).as_deref(), Some("1"));
+        assert_eq!(param.get_all("a").collect::<Vec<_>>(), ["4"]);
+        assert_eq!(param.len(), 2);
+        assert_eq!(param.remove("b").as_deref(), Some("2"));
+        assert!(!param.contains_key("b"));
+    }
+
+    #[test]
+    fn build() {
+        use std::collections::HashMap;
+
+        let built = ParamList::builder()
+            .add("a", "1")
+            .add_opt("b", None::<&str>)
+            .add("c", String::from("3"))
+            .build();
+        let collected = vec![("a", "1"), ("c", "3")]
+            .into_iter()
+            .collect::<ParamList>();
+        assert_eq!(built, collected);
+        assert_eq!(
+            built,
+            crate::params! { "a" => "1", "c" => String::from("3") }
+        );
+
+        let map = HashMap::from([("a".into(), "1".into())]);
+        assert_eq!(ParamList::from(map).get("a"), Some("1"));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn from_form() {
+        #[derive(serde::Serialize)]
+        struct Search {
+            q: &'static str,
+            count: u32,
+            until: Option<&'static str>,
+        }
+
+        #[derive(serde::Serialize)]
+        struct Tags {
+            tags: Vec<&'static str>,
+        }
+
+        let search = Search {
+            q: "a&b c",
+            count: 10,
+            until: None,
+        };
+        let param = ParamList::from_form(&search).unwrap();
+        assert_eq!(param, crate::params! { "q" => "a&b c", "count" => "10" });
+        let tags = Tags { tags: vec!["a"] };
+        match ParamList::from_form(&tags) {
+            Err(crate::Error::Serialize(_)) => {}
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+    }
+}
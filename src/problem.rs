@@ -0,0 +1,306 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! OAuth Problem Reporting extension.
+//! See http://wiki.oauth.net/w/page/12238543/ProblemReporting
+
+use std::collections::HashMap;
+use std::fmt;
+use thiserror::Error;
+
+/// Value of `oauth_problem`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Problem {
+    /// `version_rejected`
+    VersionRejected,
+    /// `parameter_absent`
+    ParameterAbsent,
+    /// `parameter_rejected`
+    ParameterRejected,
+    /// `timestamp_refused`
+    TimestampRefused,
+    /// `nonce_used`
+    NonceUsed,
+    /// `signature_method_rejected`
+    SignatureMethodRejected,
+    /// `signature_invalid`
+    SignatureInvalid,
+    /// `consumer_key_unknown`
+    ConsumerKeyUnknown,
+    /// `consumer_key_rejected`
+    ConsumerKeyRejected,
+    /// `consumer_key_refused`
+    ConsumerKeyRefused,
+    /// `token_used`
+    TokenUsed,
+    /// `token_expired`
+    TokenExpired,
+    /// `token_revoked`
+    TokenRevoked,
+    /// `token_rejected`
+    TokenRejected,
+    /// `additional_authorization_required`
+    AdditionalAuthorizationRequired,
+    /// `permission_unknown`
+    PermissionUnknown,
+    /// `permission_denied`
+    PermissionDenied,
+    /// `user_refused`
+    UserRefused,
+    /// Any problem not defined by the extension
+    Other(String),
+}
+
+impl Problem {
+    /// The problem as sent in `oauth_problem`
+    pub fn as_str(&self) -> &str {
+        match self {
+            Problem::VersionRejected => "version_rejected",
+            Problem::ParameterAbsent => "parameter_absent",
+            Problem::ParameterRejected => "parameter_rejected",
+            Problem::TimestampRefused => "timestamp_refused",
+            Problem::NonceUsed => "nonce_used",
+            Problem::SignatureMethodRejected => "signature_method_rejected",
+            Problem::SignatureInvalid => "signature_invalid",
+            Problem::ConsumerKeyUnknown => "consumer_key_unknown",
+            Problem::ConsumerKeyRejected => "consumer_key_rejected",
+            Problem::ConsumerKeyRefused => "consumer_key_refused",
+            Problem::TokenUsed => "token_used",
+            Problem::TokenExpired => "token_expired",
+            Problem::TokenRevoked => "token_revoked",
+            Problem::TokenRejected => "token_rejected",
+            Problem::AdditionalAuthorizationRequired => "additional_authorization_required",
+            Problem::PermissionUnknown => "permission_unknown",
+            Problem::PermissionDenied => "permission_denied",
+            Problem::UserRefused => "user_refused",
+            Problem::Other(s) => s,
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Problem {
+    fn from(s: &'a str) -> Problem {
+        match s {
+            "version_rejected" => Problem::VersionRejected,
+            "parameter_absent" => Problem::ParameterAbsent,
+            "parameter_rejected" => Problem::ParameterRejected,
+            "timestamp_refused" => Problem::TimestampRefused,
+            "nonce_used" => Problem::NonceUsed,
+            "signature_method_rejected" => Problem::SignatureMethodRejected,
+            "signature_invalid" => Problem::SignatureInvalid,
+            "consumer_key_unknown" => Problem::ConsumerKeyUnknown,
+            "consumer_key_rejected" => Problem::ConsumerKeyRejected,
+            "consumer_key_refused" => Problem::ConsumerKeyRefused,
+            "token_used" => Problem::TokenUsed,
+            "token_expired" => Problem::TokenExpired,
+            "token_revoked" => Problem::TokenRevoked,
+            "token_rejected" => Problem::TokenRejected,
+            "additional_authorization_required" => Problem::AdditionalAuthorizationRequired,
+            "permission_unknown" => Problem::PermissionUnknown,
+            "permission_denied" => Problem::PermissionDenied,
+            "user_refused" => Problem::UserRefused,
+            s => Problem::Other(s.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// An OAuth challenge from a `WWW-Authenticate` header.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Challenge {
+    /// Value of `realm`
+    pub realm: Option<String>,
+    /// Other parameters of the challenge, such as `oauth_problem`
+    pub params: HashMap<String, String>,
+}
+
+impl Challenge {
+    /// Parse a `WWW-Authenticate` header value using the `OAuth` scheme
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let header = r#"OAuth realm="Example", oauth_problem="token_expired""#;
+    /// let challenge = oauth_client::Challenge::parse(header).unwrap();
+    /// assert_eq!(challenge.realm.as_deref(), Some("Example"));
+    /// assert_eq!(challenge.params["oauth_problem"], "token_expired");
+    /// ```
+    pub fn parse(header: &str) -> Option<Challenge> {
+        let header = header.trim_start();
+        let (scheme, mut rest) = header.split_at(header.find(' ').unwrap_or(header.len()));
+        if !scheme.eq_ignore_ascii_case("OAuth") {
+            return None;
+        }
+
+        let mut realm = None;
+        let mut params = HashMap::new();
+        loop {
+            rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+            let (name, after) = match rest.split_once('=') {
+                Some(pair) => pair,
+                None => break,
+            };
+            let (value, after) = parse_value(after.trim_start());
+            rest = after;
+            let name = name.trim();
+            if name.eq_ignore_ascii_case("realm") {
+                realm = Some(value);
+            } else {
+                let value = percent_encoding::percent_decode_str(&value)
+                    .decode_utf8_lossy()
+                    .into_owned();
+                let _ = params.insert(name.to_string(), value);
+            }
+        }
+        Some(Challenge { realm, params })
+    }
+}
+
+/// Parse a quoted-string or a token, returning it with the remaining input
+fn parse_value(s: &str) -> (String, &str) {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => value.extend(chars.next().map(|(_, c)| c)),
+                '"' => return (value, &quoted[i + 1..]),
+                c => value.push(c),
+            }
+        }
+        (value, "")
+    } else {
+        let end = s.find(',').unwrap_or(s.len());
+        (s[..end].trim().to_string(), &s[end..])
+    }
+}
+
+/// An error happening when the provider reports an `oauth_problem`.
+#[derive(Debug, Error, Clone)]
+#[error("OAuth problem `{problem}` (HTTP status error code {status})")]
+pub struct ProblemError {
+    /// HTTP status code of the response
+    pub status: u16,
+    /// The reported problem
+    pub problem: Problem,
+    /// Value of `oauth_problem_advice`
+    pub advice: Option<String>,
+    /// Other parameters of the report, such as `oauth_parameters_absent`
+    pub extra: HashMap<String, String>,
+    /// The `WWW-Authenticate` challenge of the response, if any
+    pub challenge: Option<Challenge>,
+}
+
+/// An error happening when the provider rejects the request with an OAuth
+/// challenge but no `oauth_problem`.
+#[derive(Debug, Error, Clone)]
+#[error("HTTP status error code {status} with an OAuth challenge")]
+pub struct ChallengeError {
+    /// HTTP status code of the response
+    pub status: u16,
+    /// The `WWW-Authenticate` challenge of the response
+    pub challenge: Challenge,
+}
+
+impl ProblemError {
+    /// Range of timestamps accepted by the provider, from
+    /// `oauth_acceptable_timestamps`
+    pub fn acceptable_timestamps(&self) -> Option<(i64, i64)> {
+        let (min, max) = self
+            .extra
+            .get("oauth_acceptable_timestamps")?
+            .split_once('-')?;
+        Some((min.trim().parse().ok()?, max.trim().parse().ok()?))
+    }
+
+    /// A timestamp to retry with after `timestamp_refused`: `now` moved into
+    /// the accepted range
+    #[cfg(feature = "client")]
+    pub(crate) fn acceptable_timestamp(&self, now: i64) -> Option<i64> {
+        if self.problem != Problem::TimestampRefused {
+            return None;
+        }
+        let (min, max) = self.acceptable_timestamps()?;
+        Some(now.max(min).min(max))
+    }
+
+    /// Parse a form-encoded problem report from an error response body
+    pub(crate) fn from_body(status: u16, body: &[u8]) -> Option<ProblemError> {
+        let param = url::form_urlencoded::parse(body).into_owned().collect();
+        ProblemError::from_params(status, param)
+    }
+
+    pub(crate) fn from_params(
+        status: u16,
+        mut param: HashMap<String, String>,
+    ) -> Option<ProblemError> {
+        let problem = Problem::from(param.remove("oauth_problem")?.as_str());
+        let advice = param.remove("oauth_problem_advice");
+        Some(ProblemError {
+            status,
+            problem,
+            advice,
+            extra: param,
+            challenge: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Challenge, Problem, ProblemError};
+
+    #[test]
+    fn problem_body() {
+        let body = b"oauth_problem=parameter_absent&oauth_parameters_absent=oauth_nonce\
+                     &oauth_problem_advice=send%20a%20nonce";
+        let err = ProblemError::from_body(401, body).unwrap();
+        assert_eq!(err.status, 401);
+        assert_eq!(err.problem, Problem::ParameterAbsent);
+        assert_eq!(err.advice.as_deref(), Some("send a nonce"));
+        assert_eq!(err.extra["oauth_parameters_absent"], "oauth_nonce");
+    }
+
+    #[test]
+    fn timestamp_refused() {
+        let body = b"oauth_problem=timestamp_refused&oauth_acceptable_timestamps=100-200";
+        let err = ProblemError::from_body(401, body).unwrap();
+        assert_eq!(err.acceptable_timestamps(), Some((100, 200)));
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn acceptable_timestamp() {
+        let body = b"oauth_problem=timestamp_refused&oauth_acceptable_timestamps=100-200";
+        let err = ProblemError::from_body(401, body).unwrap();
+        assert_eq!(err.acceptable_timestamp(50), Some(100));
+        assert_eq!(err.acceptable_timestamp(150), Some(150));
+        assert_eq!(err.acceptable_timestamp(250), Some(200));
+    }
+
+    #[test]
+    fn other_body() {
+        assert!(ProblemError::from_body(500, b"Internal Server Error").is_none());
+        let err = ProblemError::from_body(400, b"oauth_problem=custom").unwrap();
+        assert_eq!(err.problem, Problem::Other("custom".into()));
+    }
+
+    #[test]
+    fn challenge() {
+        let header = r#"OAuth realm="a \"b\", c", oauth_problem=signature_invalid,oauth_problem_advice="bad%20key""#;
+        let challenge = Challenge::parse(header).unwrap();
+        assert_eq!(challenge.realm.as_deref(), Some("a \"b\", c"));
+        assert_eq!(challenge.params["oauth_problem"], "signature_invalid");
+        assert_eq!(challenge.params["oauth_problem_advice"], "bad key");
+        assert!(Challenge::parse("Basic realm=\"x\"").is_none());
+    }
+}
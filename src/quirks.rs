@@ -0,0 +1,117 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Provider deviations from the specification.
+
+use percent_encoding::AsciiSet;
+
+/// Where the `oauth_*` parameters are sent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ParamPlacement {
+    /// In the `Authorization` header (RFC 5849, section 3.5.1)
+    #[default]
+    Header,
+    /// With the other parameters, in the query string of GET requests and the
+    /// form-encoded body of POST requests (RFC 5849, sections 3.5.2 and 3.5.3)
+    Query,
+}
+
+/// A bundle of provider deviations from the specification.
+///
+/// The default profile follows RFC 5849.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None)
+///     .quirks(oauth_client::QuirkProfile::netsuite("1234567_SB1"));
+/// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+/// assert!(header.header().starts_with("OAuth realm=\"1234567_SB1\", "));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QuirkProfile {
+    /// `realm` placed first in the header and excluded from the signature
+    pub realm: Option<String>,
+    /// Leave `oauth_version` out of the signature and the header
+    pub omit_version: bool,
+    /// Send an empty `oauth_token` on requests without a token
+    pub empty_token: bool,
+    /// Where the `oauth_*` parameters are sent
+    pub placement: ParamPlacement,
+    /// Percent-encode `~`, as pre-RFC 3986 implementations do
+    pub encode_tilde: bool,
+    /// Leave `*` unencoded, as implementations based on Java's `URLEncoder` do
+    pub raw_asterisk: bool,
+}
+
+impl QuirkProfile {
+    /// NetSuite, which requires the account ID as the realm
+    pub fn netsuite<R: Into<String>>(account_id: R) -> QuirkProfile {
+        QuirkProfile {
+            realm: Some(account_id.into()),
+            ..QuirkProfile::default()
+        }
+    }
+
+    /// Providers built on pre-RFC 3986 libraries, which encode `~`
+    pub fn legacy_encoding() -> QuirkProfile {
+        QuirkProfile {
+            encode_tilde: true,
+            ..QuirkProfile::default()
+        }
+    }
+
+    /// Providers built on Java's `URLEncoder`, which encode `~` but not `*`
+    pub fn java_url_encoder() -> QuirkProfile {
+        QuirkProfile {
+            encode_tilde: true,
+            raw_asterisk: true,
+            ..QuirkProfile::default()
+        }
+    }
+
+    /// Providers ignoring the `Authorization` header, which need the
+    /// `oauth_*` parameters in the query string or the body
+    pub fn query_string() -> QuirkProfile {
+        QuirkProfile {
+            placement: ParamPlacement::Query,
+            ..QuirkProfile::default()
+        }
+    }
+
+    /// Characters to percent-encode
+    pub(crate) fn encode_set(&self) -> &'static AsciiSet {
+        match (self.encode_tilde, self.raw_asterisk) {
+            (false, false) => crate::core::URL,
+            (true, false) => URL_TILDE,
+            (false, true) => URL_ASTERISK,
+            (true, true) => URL_TILDE_ASTERISK,
+        }
+    }
+}
+
+const URL_TILDE: &AsciiSet = &crate::core::URL.add(b'~');
+const URL_ASTERISK: &AsciiSet = &crate::core::URL.remove(b'*');
+const URL_TILDE_ASTERISK: &AsciiSet = &URL_TILDE.remove(b'*');
+
+#[cfg(test)]
+mod tests {
+    use super::QuirkProfile;
+    use crate::core::encode_with;
+
+    #[test]
+    fn encoding() {
+        let s = "a~b*c";
+        assert_eq!(encode_with(s, &QuirkProfile::default()), "a~b%2Ac");
+        assert_eq!(
+            encode_with(s, &QuirkProfile::legacy_encoding()),
+            "a%7Eb%2Ac"
+        );
+        assert_eq!(encode_with(s, &QuirkProfile::java_url_encoder()), "a%7Eb*c");
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Rate limit headers of the responses.
+
+use crate::{Clock, SystemClock};
+use http::header::RETRY_AFTER;
+use http::HeaderMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The quota of the provider, from the `X-Rate-Limit-Limit`,
+/// `X-Rate-Limit-Remaining`, `X-Rate-Limit-Reset` and `Retry-After` headers
+/// of a response.
+///
+/// The `X-RateLimit-*` spelling is accepted as well. Missing or malformed
+/// headers are left as `None`.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mut headers = http::HeaderMap::new();
+/// let _ = headers.insert("x-rate-limit-remaining", "0".parse().unwrap());
+/// let _ = headers.insert("x-rate-limit-reset", "1318622958".parse().unwrap());
+/// let limit = oauth_client::RateLimit::from_headers(&headers);
+/// assert_eq!(limit.remaining, Some(0));
+/// assert_eq!(limit.reset, Some(UNIX_EPOCH + Duration::from_secs(1318622958)));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// Number of requests allowed in the current window
+    pub limit: Option<u64>,
+    /// Number of requests left in the current window
+    pub remaining: Option<u64>,
+    /// When the current window ends
+    pub reset: Option<SystemTime>,
+    /// How long to wait before sending another request
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimit {
+    /// Read the rate limit headers
+    pub fn from_headers(headers: &HeaderMap) -> RateLimit {
+        RateLimit::from_headers_at(headers, SystemClock.now())
+    }
+
+    /// Read the rate limit headers, converting a `Retry-After` date to a
+    /// delay from the Unix time `now`
+    pub(crate) fn from_headers_at(headers: &HeaderMap, now: i64) -> RateLimit {
+        let number = |name: &str| {
+            [
+                format!("x-rate-limit-{}", name),
+                format!("x-ratelimit-{}", name),
+            ]
+            .iter()
+            .filter_map(|name| headers.get(name.as_str()))
+            .find_map(|v| v.to_str().ok()?.trim().parse::<u64>().ok())
+        };
+        let retry_after = headers
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| parse_retry_after(v.trim(), now));
+        RateLimit {
+            limit: number("limit"),
+            remaining: number("remaining"),
+            reset: number("reset").map(|t| UNIX_EPOCH + Duration::from_secs(t)),
+            retry_after,
+        }
+    }
+
+    /// Whether no rate limit header was found
+    pub fn is_empty(&self) -> bool {
+        *self == RateLimit::default()
+    }
+
+    /// When requests are accepted again, `Retry-After` taking precedence
+    /// over `X-Rate-Limit-Reset`
+    pub(crate) fn reset_at(&self, now: i64) -> Option<SystemTime> {
+        self.retry_after
+            .map(|delay| UNIX_EPOCH + Duration::from_secs(now.max(0) as u64) + delay)
+            .or(self.reset)
+    }
+}
+
+/// Parse `Retry-After`, either a number of seconds or an HTTP date
+fn parse_retry_after(value: &str, now: i64) -> Option<Duration> {
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    let date = date.duration_since(UNIX_EPOCH).ok()?.as_secs() as i64;
+    Some(Duration::from_secs((date - now).max(0) as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimit;
+    use http::HeaderMap;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn headers() {
+        let mut headers = HeaderMap::new();
+        let _ = headers.insert("x-ratelimit-limit", "15".parse().unwrap());
+        let _ = headers.insert("x-rate-limit-remaining", "3".parse().unwrap());
+        let _ = headers.insert("x-rate-limit-reset", "oops".parse().unwrap());
+        let _ = headers.insert(
+            "retry-after",
+            "Fri, 14 Oct 2011 20:10:00 GMT".parse().unwrap(),
+        );
+        let limit = RateLimit::from_headers_at(&headers, 1318622958);
+        assert_eq!(limit.limit, Some(15));
+        assert_eq!(limit.remaining, Some(3));
+        assert_eq!(limit.reset, None);
+        assert_eq!(limit.retry_after, Some(Duration::from_secs(42)));
+        assert_eq!(
+            limit.reset_at(1318622958),
+            Some(UNIX_EPOCH + Duration::from_secs(1318623000))
+        );
+        assert!(RateLimit::from_headers(&HeaderMap::new()).is_empty());
+    }
+}
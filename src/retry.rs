@@ -0,0 +1,205 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Retries of failed requests.
+
+use crate::transport::SignedRequest;
+use crate::Error;
+use http::Method;
+use rand::Rng;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Classifier of the errors worth retrying.
+///
+/// Implemented for closures.
+pub trait Retryable: Send + Sync {
+    /// Whether the request failing with `err` may succeed when sent again
+    fn is_retryable(&self, err: &Error) -> bool;
+}
+
+impl fmt::Debug for dyn Retryable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Retryable")
+    }
+}
+
+impl<F> Retryable for F
+where
+    F: Fn(&Error) -> bool + Send + Sync,
+{
+    fn is_retryable(&self, err: &Error) -> bool {
+        self(err)
+    }
+}
+
+/// How the requests failing with a transient error are retried, with an
+/// exponential backoff.
+///
+/// By default, timeouts, connection failures, rate limits and 5xx statuses
+/// are retried. Each attempt is signed again, with a fresh nonce and
+/// timestamp.
+///
+/// Requests with a method other than `POST` or `PATCH`, or with an
+/// `Idempotency-Key` header, may be retried after any error. The others are
+/// only retried when the provider cannot have processed them, on
+/// connection failures and rate limits, unless
+/// [`Session::retry_non_idempotent`](struct.Session.html#method.retry_non_idempotent)
+/// is set.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(4)
+///     .base_delay(Duration::from_millis(200))
+///     .retryable(|err: &oauth_client::Error| err.is_timeout());
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).retry_policy(policy);
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable: Option<Arc<dyn Retryable>>,
+}
+
+impl RetryPolicy {
+    /// Create new policy sending a request up to `max_attempts` times in
+    /// all, waiting 100ms before the first retry and twice as long before
+    /// each of the next ones, with jitter
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retryable: None,
+        }
+    }
+
+    /// Wait `delay` before the first retry
+    pub fn base_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Never wait longer than `delay` between two attempts, 30s by default
+    pub fn max_delay(mut self, delay: Duration) -> RetryPolicy {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Wait a random delay between half and all of the backoff, so that
+    /// clients failing together do not retry together
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Retry the errors accepted by `retryable` instead of the transient
+    /// ones
+    pub fn retryable<R: Retryable + 'static>(mut self, retryable: R) -> RetryPolicy {
+        self.retryable = Some(Arc::new(retryable));
+        self
+    }
+
+    /// Maximum number of attempts of a request
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Whether a request failing with `err` is retried
+    pub fn is_retryable(&self, err: &Error) -> bool {
+        match &self.retryable {
+            Some(retryable) => retryable.is_retryable(err),
+            None => is_transient(err),
+        }
+    }
+
+    /// The delay before sending the attempt following the failed attempt
+    /// number `attempt`, starting from 1
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self
+            .base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay));
+        if self.jitter {
+            rand::thread_rng().gen_range(delay / 2..=delay)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Whether `err` is a timeout, a connection failure, a rate limit or a 5xx
+/// status
+pub(crate) fn is_transient(err: &Error) -> bool {
+    err.is_timeout()
+        || matches!(err, Error::Connect(_))
+        || err.is_rate_limited()
+        || err.status().is_some_and(|status| status >= 500)
+}
+
+/// Whether sending `request` twice has the effect of sending it once: its
+/// method is idempotent, or it carries an `Idempotency-Key` header
+pub(crate) fn is_idempotent(request: &SignedRequest) -> bool {
+    !matches!(request.method, Method::POST | Method::PATCH)
+        || request.headers.contains_key("idempotency-key")
+}
+
+/// Whether the request failing with `err` was surely not processed by the
+/// provider
+pub(crate) fn is_unprocessed(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Connect(_) | Error::RateLimited { .. } | Error::CircuitOpen { .. }
+    )
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RetryPolicy;
+    use crate::{Error, StatusError};
+    use std::time::Duration;
+
+    #[test]
+    fn backoff() {
+        let policy = RetryPolicy::new(5)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .jitter(false);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(300));
+        assert_eq!(policy.delay(40), Duration::from_millis(300));
+        let delay = policy.jitter(true).delay(2);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn retryable() {
+        let headers = http::HeaderMap::new();
+        let status = |status| Error::from(StatusError::new(status, "http://a", &headers, b""));
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(&status(502)));
+        assert!(!policy.is_retryable(&status(404)));
+        assert!(!policy.is_retryable(&Error::Denied));
+    }
+}
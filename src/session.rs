@@ -0,0 +1,1277 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Credentials and signing settings shared by a series of requests.
+
+use crate::core::{get_header, get_request_with, post_request_with};
+use crate::param::collect_params;
+#[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+use crate::Deadline;
+#[cfg(feature = "json")]
+use crate::{get_json_with, post_json_response_with};
+#[cfg(feature = "client")]
+use crate::{
+    get_with, post_with, AccessTokenResponse, AuditHook, CancellationToken, CircuitBreaker,
+    HttpTransport, Interceptor, RequestTokenResponse, ResponseCache, RetryPolicy, Timer,
+};
+use crate::{
+    AccessToken, AuthorizationHeader, Clock, Config, ConsumerToken, ErrorDecoder, NonceProvider,
+    ParamList, QuirkProfile, Result, SignedRequest, StatusClassifier,
+};
+use http::header::{HeaderName, HeaderValue, USER_AGENT};
+use std::sync::atomic::AtomicI64;
+use std::sync::Arc;
+
+/// Credentials and signing settings shared by a series of requests.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "client")]
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let access = oauth_client::AccessToken::new("token", "secret");
+///     let session = oauth_client::Session::new(consumer, Some(access)).realm("Example");
+///     let bytes = session.get("http://oauthbin.com/v1/echo", None).await.unwrap();
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct Session {
+    consumer: ConsumerToken<'static>,
+    token: Option<AccessToken<'static>>,
+    config: Config,
+}
+
+impl Session {
+    /// Create new session signing with `consumer` and `token`, copying the
+    /// borrowed keys and secrets so the session can be kept in long-lived
+    /// state
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let key = String::from("key");
+    /// let consumer = oauth_client::ConsumerToken::new(key.as_str(), "secret");
+    /// let session = oauth_client::Session::new(consumer, None);
+    /// drop(key);
+    /// assert_eq!(session.consumer().key, "key");
+    /// ```
+    pub fn new(consumer: ConsumerToken<'_>, token: Option<AccessToken<'_>>) -> Session {
+        Session {
+            consumer: consumer.into_owned(),
+            token: token.map(AccessToken::into_owned),
+            config: Config::default(),
+        }
+    }
+
+    /// Set the `realm` sent first in the `Authorization` header.
+    /// The realm is not part of the signature.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).realm("12345");
+    /// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+    /// assert!(header.header().starts_with("OAuth realm=\"12345\", "));
+    /// ```
+    pub fn realm<R: Into<String>>(mut self, realm: R) -> Session {
+        self.config.quirks.realm = Some(realm.into());
+        self
+    }
+
+    /// Leave the optional `oauth_version` parameter out of the signature
+    /// and the header, for providers rejecting it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).omit_version(true);
+    /// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+    /// assert!(!header.header().contains("oauth_version"));
+    /// ```
+    pub fn omit_version(mut self, omit: bool) -> Session {
+        self.config.quirks.omit_version = omit;
+        self
+    }
+
+    /// Send and sign an empty `oauth_token` when the session has no token,
+    /// for legacy providers requiring it even on the request-token step.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).include_empty_token(true);
+    /// let header = session.authorization_header("GET", "http://example.com", None).unwrap();
+    /// assert!(header.header().contains("oauth_token=\"\""));
+    /// ```
+    pub fn include_empty_token(mut self, include: bool) -> Session {
+        self.config.quirks.empty_token = include;
+        self
+    }
+
+    /// Normalize the names and values of the parameters to Unicode
+    /// Normalization Form C before signing and sending them, for providers
+    /// comparing signatures over NFC. Query parameters already in the URL
+    /// are sent, and signed, unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).normalize_nfc(true);
+    /// let mut param = oauth_client::ParamList::new();
+    /// param.push("q".into(), "cafe\u{301}".into());
+    /// let request = session.post_request("http://example.com", Some(&param)).unwrap();
+    /// assert_eq!(request.body, b"q=caf%C3%A9");
+    /// ```
+    #[cfg(feature = "unicode-normalization")]
+    pub fn normalize_nfc(mut self, normalize: bool) -> Session {
+        self.config.normalize_nfc = normalize;
+        self
+    }
+
+    /// Follow the deviations from the specification in `quirks`.
+    /// This replaces any realm, version or empty token setting.
+    pub fn quirks(mut self, quirks: QuirkProfile) -> Session {
+        self.config.quirks = quirks;
+        self
+    }
+
+    /// Correct `oauth_timestamp` for the difference between the local clock
+    /// and the server clock, as measured from the `Date` header of the
+    /// responses to previous requests of this session and its clones.
+    pub fn correct_clock_skew(mut self, correct: bool) -> Session {
+        self.config.clock_skew = if correct {
+            Some(Arc::new(AtomicI64::new(0)))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Take `oauth_timestamp` from `clock` instead of the system clock
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Session {
+        self.config.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Take `oauth_nonce` from `nonce` instead of 32 alphanumeric characters.
+    /// See the [`nonce`](nonce/index.html) module for built-in generators.
+    pub fn nonce<N: NonceProvider + 'static>(mut self, nonce: N) -> Session {
+        self.config.nonce = Some(Arc::new(nonce));
+        self
+    }
+
+    /// Add the header `name` to every request
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Session {
+        let _ = self.config.headers.insert(name, value);
+        self
+    }
+
+    /// Send `user_agent` as the `User-Agent` of every request, for the
+    /// providers rejecting requests without one
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use http::HeaderValue;
+    ///
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None)
+    ///     .user_agent(HeaderValue::from_static("MyCollection/1.0 +https://example.com"));
+    /// let request = session.get_request("https://api.discogs.com/oauth/identity", None).unwrap();
+    /// assert_eq!(request.headers["user-agent"], "MyCollection/1.0 +https://example.com");
+    /// ```
+    pub fn user_agent(self, user_agent: HeaderValue) -> Session {
+        self.default_header(USER_AGENT, user_agent)
+    }
+
+    /// Decode the bodies of error responses with `decoder`.
+    /// See the [`decoder`](decoder/index.html) module for built-in decoders.
+    pub fn error_decoder<D: ErrorDecoder + 'static>(mut self, decoder: D) -> Session {
+        self.config.error_decoder = Some(Arc::new(decoder));
+        self
+    }
+
+    /// Classify the response statuses with `classifier` instead of
+    /// accepting 200 OK only
+    pub fn status_classifier<C: StatusClassifier + 'static>(mut self, classifier: C) -> Session {
+        self.config.status_classifier = Some(Arc::new(classifier));
+        self
+    }
+
+    /// Send the requests with `transport` instead of the default reqwest client
+    #[cfg(feature = "client")]
+    pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> Session {
+        self.config.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Wait with `timer` before retrying a request instead of sleeping in a
+    /// thread
+    #[cfg(feature = "client")]
+    pub fn timer<T: Timer + 'static>(mut self, timer: T) -> Session {
+        self.config.timer = Some(Arc::new(timer));
+        self
+    }
+
+    /// Retry the requests refused with a `Retry-After` header, with status
+    /// 429 Too Many Requests, 420 Enhance Your Calm or 503 Service
+    /// Unavailable, after the requested delay and up to `max_attempts`
+    /// attempts in all. Each attempt is signed again, with a fresh nonce and
+    /// timestamp. Non-idempotent requests are not retried on 503, see
+    /// [`RetryPolicy`](struct.RetryPolicy.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).retry_after(3);
+    /// ```
+    #[cfg(feature = "client")]
+    pub fn retry_after(mut self, max_attempts: u32) -> Session {
+        self.config.retry_after = max_attempts;
+        self
+    }
+
+    /// Retry the requests failing with a transient error according to
+    /// `policy`. A `Retry-After` delay enabled with
+    /// [`retry_after`](#method.retry_after) takes precedence over the backoff
+    /// of the policy.
+    #[cfg(feature = "client")]
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Session {
+        self.config.retry_policy = Some(policy);
+        self
+    }
+
+    /// Fail the requests fast with `Error::CircuitOpen` while `breaker` is
+    /// open. Each attempt of a retried request counts.
+    #[cfg(feature = "client")]
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Session {
+        self.config.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Call `interceptor` around every request sent by the transport, after
+    /// the interceptors added before.
+    /// See [`Interceptor`](trait.Interceptor.html).
+    #[cfg(feature = "client")]
+    pub fn interceptor<I: Interceptor + 'static>(mut self, interceptor: I) -> Session {
+        self.config.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    /// Report an [`AuditRecord`](struct.AuditRecord.html) of every request
+    /// sent to `hook`
+    #[cfg(feature = "client")]
+    pub fn audit<A: AuditHook + 'static>(mut self, hook: A) -> Session {
+        self.config.audit = Some(Arc::new(hook));
+        self
+    }
+
+    /// Send GET requests conditionally, answering `304 Not Modified`
+    /// responses from `cache`.
+    /// See [`ResponseCache`](struct.ResponseCache.html).
+    #[cfg(feature = "client")]
+    pub fn cache(mut self, cache: ResponseCache) -> Session {
+        self.config.cache = Some(cache);
+        self
+    }
+
+    /// Complete each request, with all its attempts, by `deadline`. Clone
+    /// the session to give a request a deadline of its own.
+    #[cfg(all(feature = "client", not(target_arch = "wasm32")))]
+    pub fn deadline(mut self, deadline: Deadline) -> Session {
+        self.config.deadline = Some(deadline);
+        self
+    }
+
+    /// Cancel the requests of the session, and of its clones, when `token`
+    /// is cancelled
+    #[cfg(feature = "client")]
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Session {
+        self.config.cancellation = Some(token);
+        self
+    }
+
+    /// Retry `POST` and `PATCH` requests without an `Idempotency-Key` header
+    /// after errors the provider may have processed them despite, such as
+    /// timeouts and 5xx statuses, at the risk of duplicate side effects
+    #[cfg(feature = "client")]
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Session {
+        self.config.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Fail with `Error::BodyTooLarge` instead of reading response bodies
+    /// larger than `bytes`
+    #[cfg(feature = "client")]
+    pub fn max_response_size(mut self, bytes: usize) -> Session {
+        self.config.max_response_size = Some(bytes);
+        self
+    }
+
+    /// Report the progress of [`download`](#method.download) and
+    /// [`upload_file`](#method.upload_file) to `hook`
+    #[cfg(all(feature = "transfer", not(target_arch = "wasm32")))]
+    pub fn progress<P: crate::transfer::ProgressHook + 'static>(mut self, hook: P) -> Session {
+        self.config.progress = Some(Arc::new(hook));
+        self
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The consumer token requests are signed with
+    pub fn consumer(&self) -> &ConsumerToken<'static> {
+        &self.consumer
+    }
+
+    /// The token requests are signed with, if any
+    pub fn token(&self) -> Option<&AccessToken<'static>> {
+        self.token.as_ref()
+    }
+
+    /// A copy of the session signing with `token` instead, to send a request
+    /// on behalf of another user. See also
+    /// [`TokenRegistry`](store/struct.TokenRegistry.html).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None);
+    /// let alice = session.with_token(Some(oauth_client::AccessToken::new("alice", "secret")));
+    /// assert_eq!(alice.token().unwrap().key, "alice");
+    /// ```
+    pub fn with_token(&self, token: Option<AccessToken<'_>>) -> Session {
+        Session {
+            consumer: self.consumer.clone(),
+            token: token.map(AccessToken::into_owned),
+            config: self.config.clone(),
+        }
+    }
+
+    /// Create an authorization header.
+    /// See [`authorization_header`](fn.authorization_header.html).
+    pub fn authorization_header(
+        &self,
+        method: &str,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList>,
+    ) -> Result<AuthorizationHeader> {
+        let uri = uri.as_ref();
+        get_header(
+            method,
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+    }
+
+    /// Create an authorization header, with the parameters given as any
+    /// pairs of strings.
+    /// See [`authorization_header_with_params`](fn.authorization_header_with_params.html).
+    pub fn authorization_header_with_params<I, K, V>(
+        &self,
+        method: &str,
+        uri: impl AsRef<str>,
+        params: I,
+    ) -> Result<AuthorizationHeader>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let uri = uri.as_ref();
+        self.authorization_header(method, uri, Some(&collect_params(params)))
+    }
+
+    /// Build the GET request [`get`](#method.get) would send, without sending
+    /// it
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None);
+    /// let request = session.get_request("http://example.com", None).unwrap();
+    /// assert_eq!(request.url, "http://example.com");
+    /// assert!(request.headers.contains_key(http::header::AUTHORIZATION));
+    /// ```
+    pub fn get_request(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<SignedRequest> {
+        let uri = uri.as_ref();
+        get_request_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+    }
+
+    /// Build the POST request [`post`](#method.post) would send, without
+    /// sending it
+    pub fn post_request(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<SignedRequest> {
+        let uri = uri.as_ref();
+        post_request_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+    }
+
+    /// Send authorized GET request to the specified URL
+    #[cfg(feature = "client")]
+    pub async fn get(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        get_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Send authorized POST request to the specified URL
+    #[cfg(feature = "client")]
+    pub async fn post(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        post_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Send authorized POST request to the specified URL, with the fields of
+    /// `form` as the parameters.
+    /// See [`post_form`](fn.post_form.html).
+    #[cfg(all(feature = "client", feature = "serde"))]
+    pub async fn post_form<T: serde::Serialize + ?Sized>(
+        &self,
+        uri: impl AsRef<str>,
+        form: &T,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        let param = ParamList::from_form(form)?;
+        self.post(uri, Some(&param)).await
+    }
+
+    /// Send authorized GET request to the specified URL, and write its body
+    /// into `writer` as it is received.
+    /// Sent with reqwest whatever the transport of the session.
+    /// See [`transfer::download`](transfer/fn.download.html).
+    #[cfg(all(feature = "transfer", not(target_arch = "wasm32")))]
+    pub async fn download<W: tokio::io::AsyncWrite + Unpin>(
+        &self,
+        uri: impl AsRef<str>,
+        writer: W,
+    ) -> Result<u64> {
+        let uri = uri.as_ref();
+        crate::transfer::download_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            &self.config,
+            writer,
+        )
+        .await
+    }
+
+    /// Send authorized multipart POST request to the specified URL, with the
+    /// file at `path` streamed as the part `field_name`.
+    /// Sent with reqwest whatever the transport of the session.
+    /// See [`transfer::upload_file`](transfer/fn.upload_file.html).
+    #[cfg(all(feature = "transfer", not(target_arch = "wasm32")))]
+    pub async fn upload_file(
+        &self,
+        uri: impl AsRef<str>,
+        path: impl AsRef<std::path::Path>,
+        field_name: &str,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        let path = path.as_ref();
+        crate::transfer::upload_file_with(
+            uri,
+            path,
+            field_name,
+            &self.consumer,
+            self.token.as_deref(),
+            &self.config,
+        )
+        .await
+    }
+
+    /// Obtain a request token from `endpoint`, sending `callback` as
+    /// `oauth_callback`. The token of the session is not used.
+    /// See [`obtain_request_token`](fn.obtain_request_token.html).
+    #[cfg(feature = "client")]
+    pub async fn obtain_request_token(
+        &self,
+        endpoint: impl AsRef<str>,
+        callback: &str,
+    ) -> Result<RequestTokenResponse> {
+        let endpoint = endpoint.as_ref();
+        crate::flow::obtain_request_token_with(endpoint, &self.consumer, callback, &self.config)
+            .await
+    }
+
+    /// Exchange an authorized `request_token` for an access token at
+    /// `endpoint`. The token of the session is not used.
+    /// See [`exchange_access_token`](fn.exchange_access_token.html).
+    #[cfg(feature = "client")]
+    pub async fn exchange_access_token(
+        &self,
+        endpoint: impl AsRef<str>,
+        request_token: &AccessToken<'_>,
+        verifier: &str,
+    ) -> Result<AccessTokenResponse> {
+        let endpoint = endpoint.as_ref();
+        crate::flow::exchange_access_token_with(
+            endpoint,
+            &self.consumer,
+            request_token,
+            verifier,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Refresh an expiring `access_token` at `endpoint`. The token of the
+    /// session is not used.
+    /// See [`refresh_access_token`](fn.refresh_access_token.html).
+    #[cfg(feature = "client")]
+    pub async fn refresh_access_token(
+        &self,
+        endpoint: impl AsRef<str>,
+        access_token: &AccessToken<'_>,
+        session_handle: &str,
+    ) -> Result<AccessTokenResponse> {
+        let endpoint = endpoint.as_ref();
+        crate::flow::refresh_access_token_with(
+            endpoint,
+            &self.consumer,
+            access_token,
+            session_handle,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Invalidate `token` at the revocation `endpoint`. The token of the
+    /// session is not used.
+    /// See [`invalidate_token`](fn.invalidate_token.html).
+    #[cfg(feature = "client")]
+    pub async fn invalidate_token(
+        &self,
+        endpoint: impl AsRef<str>,
+        token: &AccessToken<'_>,
+    ) -> Result<()> {
+        let endpoint = endpoint.as_ref();
+        crate::flow::invalidate_token_with(endpoint, &self.consumer, token, &self.config).await
+    }
+
+    /// Send the GET requests of the pages of `request`, each signed anew,
+    /// and stream their bodies. `next_cursor` reads the cursor of the next
+    /// page out of a body, `None` on the last page.
+    /// See the [`pagination`](pagination/index.html) module.
+    #[cfg(feature = "pagination")]
+    pub fn paginate<'a, F>(
+        &'a self,
+        request: crate::pagination::PageRequest,
+        next_cursor: F,
+    ) -> impl futures_util::Stream<Item = Result<Vec<u8>>> + 'a
+    where
+        F: FnMut(&[u8]) -> Option<String> + 'a,
+    {
+        crate::pagination::paginate(self, request, next_cursor)
+    }
+
+    /// Send `requests`, each signed when it is sent, with at most
+    /// `max_in_flight` of them at a time. The results are in the order of
+    /// `requests`.
+    /// See the [`batch`](batch/index.html) module.
+    #[cfg(feature = "batch")]
+    pub async fn batch<I>(&self, requests: I, max_in_flight: usize) -> Vec<Result<Vec<u8>>>
+    where
+        I: IntoIterator<Item = crate::batch::BatchRequest>,
+    {
+        crate::batch::batch(self, requests, max_in_flight).await
+    }
+
+    /// Send authorized GET request to the specified URL, and deserialize its
+    /// JSON body.
+    /// See [`get_json`](fn.get_json.html).
+    #[cfg(feature = "json")]
+    pub async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<T> {
+        let uri = uri.as_ref();
+        get_json_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Send authorized POST request to the specified URL, and deserialize
+    /// its JSON body.
+    /// See [`post_json_response`](fn.post_json_response.html).
+    #[cfg(feature = "json")]
+    pub async fn post_json_response<T: serde::de::DeserializeOwned>(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<T> {
+        let uri = uri.as_ref();
+        post_json_response_with(
+            uri,
+            &self.consumer,
+            self.token.as_deref(),
+            other_param,
+            &self.config,
+        )
+        .await
+    }
+
+    /// Send authorized GET request to the specified URL, with the parameters
+    /// given as any pairs of strings, such as a slice, a `Vec` or a map
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// async {
+    ///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    ///     let session = oauth_client::Session::new(consumer, None);
+    ///     let tags = vec![("tag", "a"), ("tag", "b")];
+    ///     let bytes = session.get_with_params("http://oauthbin.com/v1/echo", tags).await.unwrap();
+    /// };
+    /// ```
+    #[cfg(feature = "client")]
+    pub async fn get_with_params<I, K, V>(&self, uri: impl AsRef<str>, params: I) -> Result<Vec<u8>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let uri = uri.as_ref();
+        self.get(uri, Some(&collect_params(params))).await
+    }
+
+    /// Send authorized POST request to the specified URL, with the
+    /// parameters given as any pairs of strings, such as a slice, a `Vec` or
+    /// a map
+    #[cfg(feature = "client")]
+    pub async fn post_with_params<I, K, V>(
+        &self,
+        uri: impl AsRef<str>,
+        params: I,
+    ) -> Result<Vec<u8>>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let uri = uri.as_ref();
+        self.post(uri, Some(&collect_params(params))).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Session;
+    use crate::{AccessToken, ConsumerToken, ParamList, QuirkProfile, Token};
+
+    #[test]
+    fn realm_not_signed() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None).realm("a \"b\"");
+        let header = session
+            .authorization_header("GET", "http://example.com", None)
+            .unwrap()
+            .into_header();
+        assert!(header.starts_with("OAuth realm=\"a \\\"b\\\"\", oauth_consumer_key="));
+        assert_eq!(header.matches("realm").count(), 1);
+    }
+
+    #[test]
+    fn invalid_realm() {
+        use crate::Error;
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None).realm("a\r\nx-injected: 1");
+        match session.get_request("http://example.com", None) {
+            Err(Error::InvalidHeader(_)) => {}
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        assert!(session
+            .authorization_header("GET", "http://example.com", None)
+            .is_err());
+    }
+
+    #[test]
+    fn query_placement() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None).quirks(QuirkProfile::query_string());
+        let mut param = ParamList::new();
+        let _ = param.insert("a".into(), "1".into());
+        let header = session
+            .authorization_header("GET", "http://example.com", Some(&param))
+            .unwrap();
+        assert!(header.header().is_empty());
+        assert!(header
+            .body()
+            .starts_with("a=1&oauth_consumer_key=key&oauth_nonce="));
+        assert!(header.body().contains("&oauth_signature="));
+        assert_eq!(header.query(), header.body());
+    }
+
+    #[test]
+    #[cfg(feature = "client")]
+    fn without_runtime() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::Result;
+        use std::future::Future;
+        use std::pin::pin;
+        use std::task::{Context, Poll, Waker};
+
+        struct Echo;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Echo {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                Ok(Response {
+                    url: request.url.clone(),
+                    status: http::StatusCode::OK,
+                    headers: http::HeaderMap::new(),
+                    body: request.url.into_bytes(),
+                })
+            }
+        }
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None).transport(Echo);
+        let mut future = pin!(session.get("http://example.com", None));
+        let mut context = Context::from_waker(Waker::noop());
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(body) => assert_eq!(body.unwrap(), b"http://example.com"),
+            Poll::Pending => panic!("pending without any I/O"),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "json")]
+    async fn json() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Error, Result};
+
+        struct Json;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Json {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let (content_type, body) = match request.url.as_str() {
+                    "http://example.com/user" => ("application/json; charset=utf-8", "{\"id\":1}"),
+                    "http://example.com/vnd" => ("application/vnd.api+json", "{\"id\":2}"),
+                    _ => ("text/html", "<html></html>"),
+                };
+                let mut headers = http::HeaderMap::new();
+                let _ = headers.insert(http::header::CONTENT_TYPE, content_type.parse().unwrap());
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::OK,
+                    headers,
+                    body: body.into(),
+                })
+            }
+        }
+
+        #[derive(serde::Deserialize)]
+        struct User {
+            id: u32,
+        }
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None).transport(Json);
+        let user: User = session
+            .get_json("http://example.com/user", None)
+            .await
+            .unwrap();
+        assert_eq!(user.id, 1);
+        let user: User = session
+            .post_json_response("http://example.com/vnd", None)
+            .await
+            .unwrap();
+        assert_eq!(user.id, 2);
+        match session.get_json::<User>("http://example.com/", None).await {
+            Err(Error::UnexpectedContentType(content_type)) => {
+                assert_eq!(content_type, "text/html")
+            }
+            rsp => panic!("unexpected {:?}", rsp.map(|user| user.id)),
+        }
+        let rsp = session
+            .get_json::<String>("http://example.com/user", None)
+            .await;
+        assert!(matches!(rsp, Err(Error::Deserialize(_))));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn error_decoder() {
+        use crate::decoder::Form;
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Error, Result};
+
+        struct BadRequest;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for BadRequest {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::BAD_REQUEST,
+                    headers: http::HeaderMap::new(),
+                    body: b"error=rate_limited&error_description=slow+down".to_vec(),
+                })
+            }
+        }
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .transport(BadRequest)
+            .error_decoder(Form::new());
+        let rsp = session.get("http://example.com", None).await;
+        match rsp.map_err(Error::into_inner) {
+            Err(Error::Status(err)) => {
+                let provider = err.provider.unwrap();
+                assert_eq!(provider.code.as_deref(), Some("rate_limited"));
+                assert_eq!(provider.message.as_deref(), Some("slow down"));
+            }
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn rate_limit_clock() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Error, Result};
+        use std::time::{Duration, UNIX_EPOCH};
+
+        /// 429 Too Many Requests from a server 100 seconds ahead
+        struct Ahead;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Ahead {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let date = UNIX_EPOCH + Duration::from_secs(1_500_000_100);
+                let mut headers = http::HeaderMap::new();
+                let _ = headers.insert("retry-after", "30".parse().unwrap());
+                let _ = headers.insert("date", httpdate::fmt_http_date(date).parse().unwrap());
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::TOO_MANY_REQUESTS,
+                    headers,
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .clock(|| 1_500_000_000)
+            .correct_clock_skew(true)
+            .transport(Ahead);
+        let rsp = session.get("http://example.com", None).await;
+        match rsp.map_err(Error::into_inner) {
+            Err(Error::RateLimited { reset_at, .. }) => {
+                let expected = UNIX_EPOCH + Duration::from_secs(1_500_000_130);
+                assert_eq!(reset_at, Some(expected));
+            }
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn retry_after() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Error, Result};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Busy {
+            headers: Arc<Mutex<Vec<http::HeaderValue>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Busy {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let mut seen = self.headers.lock().unwrap();
+                seen.push(request.headers["authorization"].clone());
+                let mut headers = http::HeaderMap::new();
+                let _ = headers.insert("retry-after", "0".parse().unwrap());
+                let status = match seen.len() {
+                    1 => http::StatusCode::TOO_MANY_REQUESTS,
+                    2 => http::StatusCode::SERVICE_UNAVAILABLE,
+                    _ => http::StatusCode::OK,
+                };
+                Ok(Response {
+                    url: request.url,
+                    status,
+                    headers,
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let transport = Busy::default();
+        let session = Session::new(consumer.clone(), None)
+            .transport(transport.clone())
+            .retry_after(3);
+        session.get("http://example.com", None).await.unwrap();
+        let seen = transport.headers.lock().unwrap().clone();
+        assert_eq!(seen.len(), 3);
+        assert_ne!(seen[0], seen[1]);
+
+        let transport = Busy::default();
+        let session = Session::new(consumer, None)
+            .transport(transport.clone())
+            .retry_after(2);
+        let err = session.get("http://example.com", None).await.unwrap_err();
+        assert_eq!(err.context().unwrap().attempt, 2);
+        match err.into_inner() {
+            err @ Error::Status(_) => assert_eq!(err.status(), Some(503)),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn retry_policy() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Error, Result, RetryPolicy};
+        use std::sync::{Arc, Mutex};
+        use std::time::Duration;
+
+        #[derive(Clone, Default)]
+        struct Flaky {
+            nonces: Arc<Mutex<Vec<String>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Flaky {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let mut nonces = self.nonces.lock().unwrap();
+                let header = request.headers["authorization"].to_str().unwrap();
+                nonces.push(header.split("oauth_nonce=").nth(1).unwrap().to_string());
+                if nonces.len() < 3 {
+                    return Err(Error::Connect("refused".into()));
+                }
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::OK,
+                    headers: http::HeaderMap::new(),
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let slept = delays.clone();
+        let transport = Flaky::default();
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .transport(transport.clone())
+            .timer(move |delay: Duration| {
+                slept.lock().unwrap().push(delay);
+                async {}
+            })
+            .retry_policy(RetryPolicy::new(3).jitter(false));
+        session.get("http://example.com", None).await.unwrap();
+        let nonces = transport.nonces.lock().unwrap();
+        assert_eq!(nonces.len(), 3);
+        assert_ne!(nonces[0], nonces[1]);
+        assert_eq!(
+            *delays.lock().unwrap(),
+            [Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn deadline() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Deadline, Result, RetryPolicy};
+        use std::time::Duration;
+
+        struct Hanging;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Hanging {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                assert!(request.timeout.unwrap() <= Duration::from_millis(50));
+                std::future::pending().await
+            }
+        }
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .transport(Hanging)
+            .retry_policy(RetryPolicy::new(10))
+            .deadline(Deadline::after(Duration::from_millis(50)));
+        let err = session.get("http://example.com", None).await.unwrap_err();
+        assert!(err.is_timeout());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn cancellation() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{CancellationToken, Result};
+        use std::time::Duration;
+
+        struct Hanging;
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Hanging {
+            async fn execute(&self, _request: SignedRequest) -> Result<Response> {
+                std::future::pending().await
+            }
+        }
+
+        let token = CancellationToken::new();
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .transport(Hanging)
+            .cancellation_token(token.clone());
+        let request = tokio::spawn(async move { session.get("http://example.com", None).await });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        token.cancel();
+        assert!(request.await.unwrap().unwrap_err().is_cancelled());
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn retry_post() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Result, RetryPolicy};
+        use http::HeaderValue;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        #[derive(Clone, Default)]
+        struct Unavailable {
+            attempts: Arc<AtomicUsize>,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Unavailable {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let _ = self.attempts.fetch_add(1, Ordering::SeqCst);
+                Ok(Response {
+                    url: request.url,
+                    status: http::StatusCode::SERVICE_UNAVAILABLE,
+                    headers: http::HeaderMap::new(),
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        let attempts = |session: Session| async move {
+            let transport = Unavailable::default();
+            let session = session
+                .transport(transport.clone())
+                .timer(|_| async {})
+                .retry_policy(RetryPolicy::new(3));
+            assert!(session.post("http://example.com", None).await.is_err());
+            transport.attempts.load(Ordering::SeqCst)
+        };
+        let session = Session::new(ConsumerToken::new("key", "secret"), None);
+        assert_eq!(attempts(session.clone()).await, 1);
+        assert_eq!(
+            attempts(session.clone().retry_non_idempotent(true)).await,
+            3
+        );
+        let key = HeaderValue::from_static("1");
+        let session = session.default_header("idempotency-key".parse().unwrap(), key);
+        assert_eq!(attempts(session).await, 3);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn status_classifier() {
+        use crate::transport::{HttpTransport, Response, SignedRequest};
+        use crate::{Result, RetryPolicy, StatusClass};
+        use http::StatusCode;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Statuses {
+            sent: Arc<Mutex<Vec<StatusCode>>>,
+        }
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Statuses {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let mut sent = self.sent.lock().unwrap();
+                let status = [StatusCode::CONFLICT, StatusCode::NOT_FOUND][sent.len() % 2];
+                sent.push(status);
+                Ok(Response {
+                    url: request.url,
+                    status,
+                    headers: http::HeaderMap::new(),
+                    body: b"empty".to_vec(),
+                })
+            }
+        }
+
+        let transport = Statuses::default();
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .transport(transport.clone())
+            .timer(|_| async {})
+            .retry_policy(RetryPolicy::new(3))
+            .status_classifier(|status| match status {
+                StatusCode::NOT_FOUND => StatusClass::Success,
+                StatusCode::CONFLICT => StatusClass::Retry,
+                _ => StatusClass::Error,
+            });
+        let body = session.get("http://example.com", None).await.unwrap();
+        assert_eq!(body, b"empty");
+        assert_eq!(transport.sent.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn empty_token_secret() {
+        let sign = |token: Option<Token<'static>>| {
+            let session = Session::new(
+                ConsumerToken::new("key", "secret"),
+                token.map(AccessToken::from),
+            )
+            .nonce(|| "nonce".to_string())
+            .clock(|| 1);
+            session
+                .authorization_header("GET", "http://example.com", None)
+                .unwrap()
+                .into_header()
+        };
+        let absent = sign(None);
+        let empty = sign(Some(Token::without_secret("token")));
+        assert!(!absent.contains("oauth_token"));
+        assert!(empty.contains("oauth_token=\"token\""));
+
+        let base = "GET&http%3A%2F%2Fexample.com%2F&oauth_consumer_key%3Dkey\
+                    %26oauth_nonce%3Dnonce%26oauth_signature_method%3DHMAC-SHA1\
+                    %26oauth_timestamp%3D1%26oauth_token%3Dtoken%26oauth_version%3D1.0";
+        let signature = crate::encode(&crate::hmac_sha1_signature(base, "secret", None));
+        assert!(empty.contains(&format!("oauth_signature=\"{}\"", signature)));
+    }
+
+    #[test]
+    #[cfg(feature = "unicode-normalization")]
+    fn normalize_nfc() {
+        let sign = |value: &'static str, normalize: bool| {
+            let mut param = ParamList::new();
+            param.push("q".into(), value.into());
+            Session::new(ConsumerToken::new("key", "secret"), None)
+                .nonce(|| "nonce".to_string())
+                .clock(|| 1)
+                .normalize_nfc(normalize)
+                .authorization_header("POST", "http://example.com", Some(&param))
+                .unwrap()
+        };
+        assert_eq!(sign("cafe\u{301}", true), sign("caf\u{e9}", false));
+        assert_ne!(sign("cafe\u{301}", false), sign("caf\u{e9}", false));
+    }
+
+    #[test]
+    fn with_params() {
+        use std::collections::HashMap;
+
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .nonce(|| "nonce".to_string())
+            .clock(|| 1);
+        let mut param = ParamList::new();
+        param.push("a".into(), "1".into());
+        param.push("b".into(), "2".into());
+        let expected = session
+            .authorization_header("POST", "http://example.com", Some(&param))
+            .unwrap();
+        let map = HashMap::from([("a".to_string(), "1"), ("b".to_string(), "2")]);
+        let sign = |params: Vec<(String, &str)>| {
+            session.authorization_header_with_params("POST", "http://example.com", params)
+        };
+        for header in [
+            session.authorization_header_with_params(
+                "POST",
+                "http://example.com",
+                [("a", "1"), ("b", "2")],
+            ),
+            session.authorization_header_with_params("POST", "http://example.com", &map),
+            sign(vec![("a".into(), "1"), ("b".into(), "2")]),
+        ] {
+            assert_eq!(header.unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn post_request() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let session = Session::new(consumer, None)
+            .nonce(|| "nonce".to_string())
+            .clock(|| 1);
+        let mut param = ParamList::new();
+        let _ = param.insert("a".into(), "1".into());
+        let request = session
+            .post_request("http://example.com", Some(&param))
+            .unwrap();
+        let header = session
+            .authorization_header("POST", "http://example.com", Some(&param))
+            .unwrap()
+            .into_header();
+        assert_eq!(request.method, http::Method::POST);
+        assert_eq!(request.headers["authorization"], header.as_str());
+        assert_eq!(request.body, b"a=1");
+    }
+}
@@ -0,0 +1,65 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Classification of the response statuses.
+
+use http::StatusCode;
+use std::fmt;
+
+/// What a response status means for the request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum StatusClass {
+    /// The request succeeded, and the body is returned
+    Success,
+    /// The request failed, and an error is returned
+    Error,
+    /// The request failed, and is retried according to the
+    /// [`RetryPolicy`](struct.RetryPolicy.html) of the session whatever its
+    /// classifier of retryable errors
+    Retry,
+}
+
+/// Classifier of the response statuses, for providers giving them another
+/// meaning than the specification.
+///
+/// Implemented for closures. Without a classifier, 200 OK is the only
+/// success.
+///
+/// # Examples
+///
+/// ```
+/// use http::StatusCode;
+/// use oauth_client::StatusClass;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).status_classifier(
+///     |status: StatusCode| match status {
+///         StatusCode::OK | StatusCode::ACCEPTED | StatusCode::NOT_FOUND => StatusClass::Success,
+///         StatusCode::BAD_GATEWAY => StatusClass::Retry,
+///         _ => StatusClass::Error,
+///     },
+/// );
+/// ```
+pub trait StatusClassifier: Send + Sync {
+    /// Classify `status`
+    fn classify(&self, status: StatusCode) -> StatusClass;
+}
+
+impl fmt::Debug for dyn StatusClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("StatusClassifier")
+    }
+}
+
+impl<F> StatusClassifier for F
+where
+    F: Fn(StatusCode) -> StatusClass + Send + Sync,
+{
+    fn classify(&self, status: StatusCode) -> StatusClass {
+        self(status)
+    }
+}
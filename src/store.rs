@@ -0,0 +1,640 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Persistent storage of tokens across runs.
+//!
+//! A [`TokenRegistry`](struct.TokenRegistry.html) keeps the tokens of
+//! several accounts, each in a store of its own.
+//!
+//! The `file-store` feature adds [`JsonFile`](struct.JsonFile.html), the
+//! `encrypted-store` feature [`EncryptedFile`](struct.EncryptedFile.html),
+//! and the `keyring` feature [`Keyring`](struct.Keyring.html), backed by the
+//! credential store of the operating system.
+
+use crate::{AccessToken, Error, Result, Session, Token};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Storage of a single token, such as the access token of a CLI or desktop
+/// application.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::store::TokenStore;
+///
+/// fn access_token(store: &dyn TokenStore) -> oauth_client::Result<oauth_client::AccessToken<'static>> {
+///     match store.load()? {
+///         Some(token) => Ok(token.into()),
+///         None => unimplemented!("run the authorization flow and save the token"),
+///     }
+/// }
+/// ```
+pub trait TokenStore: Send + Sync {
+    /// The stored token, or `None` if no token was saved
+    fn load(&self) -> Result<Option<Token<'static>>>;
+
+    /// Store `token`, replacing the previous one
+    fn save(&self, token: &Token<'_>) -> Result<()>;
+
+    /// Remove the stored token, if any
+    fn delete(&self) -> Result<()>;
+}
+
+impl fmt::Debug for dyn TokenStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("TokenStore")
+    }
+}
+
+/// Function opening the store of an account
+type StoreFactory = dyn Fn(&str) -> Result<Box<dyn TokenStore>> + Send + Sync;
+
+/// Access tokens of several accounts, such as the users managed by a bot,
+/// keyed by account id.
+///
+/// With a store, each account has its own [`TokenStore`](trait.TokenStore.html):
+/// tokens are loaded from it on first use and saved to it when inserted.
+/// Clones of a registry share its tokens.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::store::TokenRegistry;
+/// use oauth_client::{AccessToken, ConsumerToken, Session};
+///
+/// let registry = TokenRegistry::new();
+/// registry.insert("alice", AccessToken::new("alice-token", "secret")).unwrap();
+/// registry.insert("bob", AccessToken::new("bob-token", "secret")).unwrap();
+///
+/// let session = Session::new(ConsumerToken::new("key", "secret"), None);
+/// let bob = registry.session(&session, "bob").unwrap();
+/// assert_eq!(bob.token().unwrap().key, "bob-token");
+/// ```
+#[derive(Clone, Default)]
+pub struct TokenRegistry {
+    tokens: Arc<Mutex<HashMap<String, AccessToken<'static>>>>,
+    store: Option<Arc<StoreFactory>>,
+}
+
+impl TokenRegistry {
+    /// Create new registry keeping the tokens in memory only
+    pub fn new() -> TokenRegistry {
+        TokenRegistry::default()
+    }
+
+    /// Create new registry persisting the token of each account in the
+    /// store returned by `store` for its id
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[cfg(feature = "file-store")]
+    /// # {
+    /// use oauth_client::store::{JsonFile, TokenRegistry, TokenStore};
+    ///
+    /// let dir = std::env::temp_dir();
+    /// let registry = TokenRegistry::with_store(move |account: &str| {
+    ///     let path = dir.join(format!("oauth-client-doc-{}.json", account));
+    ///     Ok(Box::new(JsonFile::new(path)) as Box<dyn TokenStore>)
+    /// });
+    /// # }
+    /// ```
+    pub fn with_store<F>(store: F) -> TokenRegistry
+    where
+        F: Fn(&str) -> Result<Box<dyn TokenStore>> + Send + Sync + 'static,
+    {
+        TokenRegistry {
+            tokens: Arc::default(),
+            store: Some(Arc::new(store)),
+        }
+    }
+
+    fn tokens(&self) -> std::sync::MutexGuard<'_, HashMap<String, AccessToken<'static>>> {
+        self.tokens.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Add or replace the token of `account`, saving it to its store
+    pub fn insert(&self, account: &str, token: AccessToken<'static>) -> Result<()> {
+        if let Some(store) = &self.store {
+            store(account)?.save(&token)?;
+        }
+        let _ = self.tokens().insert(account.to_string(), token);
+        Ok(())
+    }
+
+    /// The token of `account`, loading it from its store the first time
+    pub fn get(&self, account: &str) -> Result<Option<AccessToken<'static>>> {
+        if let Some(token) = self.tokens().get(account) {
+            return Ok(Some(token.clone()));
+        }
+        let token = match &self.store {
+            Some(store) => store(account)?.load()?.map(AccessToken::from),
+            None => None,
+        };
+        if let Some(token) = &token {
+            let _ = self.tokens().insert(account.to_string(), token.clone());
+        }
+        Ok(token)
+    }
+
+    /// Forget the token of `account`, deleting it from its store
+    pub fn remove(&self, account: &str) -> Result<Option<AccessToken<'static>>> {
+        if let Some(store) = &self.store {
+            store(account)?.delete()?;
+        }
+        Ok(self.tokens().remove(account))
+    }
+
+    /// The ids of the accounts inserted or loaded so far
+    pub fn accounts(&self) -> Vec<String> {
+        self.tokens().keys().cloned().collect()
+    }
+
+    /// A copy of `session` signing with the token of `account`, failing with
+    /// `Error::UnknownAccount` if it has none
+    pub fn session(&self, session: &Session, account: &str) -> Result<Session> {
+        match self.get(account)? {
+            Some(token) => Ok(session.with_token(Some(token))),
+            None => Err(Error::UnknownAccount(account.to_string())),
+        }
+    }
+}
+
+impl fmt::Debug for TokenRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenRegistry")
+            .field("accounts", &self.accounts())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(any(feature = "file-store", feature = "keyring"))]
+fn to_json(token: &Token<'_>) -> Result<String> {
+    serde_json::to_string(token).map_err(|err| Error::Store(err.into()))
+}
+
+#[cfg(any(feature = "file-store", feature = "keyring"))]
+fn from_json(json: &[u8]) -> Result<Token<'static>> {
+    serde_json::from_slice(json).map_err(|err| Error::Store(err.into()))
+}
+
+/// Store keeping the token in a JSON file.
+///
+/// The file is replaced atomically and, on Unix, is only readable by its
+/// owner. The secret is written in plain text.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::store::{JsonFile, TokenStore};
+///
+/// let store = JsonFile::new(std::env::temp_dir().join("oauth-client-doc.json"));
+/// store.save(&oauth_client::Token::new("token", "secret")).unwrap();
+/// assert_eq!(store.load().unwrap().unwrap().key, "token");
+/// store.delete().unwrap();
+/// ```
+#[cfg(feature = "file-store")]
+#[derive(Clone, Debug)]
+pub struct JsonFile {
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "file-store")]
+impl JsonFile {
+    /// Create new store in the file at `path`
+    pub fn new<P: Into<std::path::PathBuf>>(path: P) -> JsonFile {
+        JsonFile { path: path.into() }
+    }
+}
+
+#[cfg(feature = "file-store")]
+impl TokenStore for JsonFile {
+    fn load(&self) -> Result<Option<Token<'static>>> {
+        match read_file(&self.path)? {
+            Some(json) => from_json(&json).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn save(&self, token: &Token<'_>) -> Result<()> {
+        write_file(&self.path, to_json(token)?.as_bytes())
+    }
+
+    fn delete(&self) -> Result<()> {
+        delete_file(&self.path)
+    }
+}
+
+/// The content of the file at `path`, or `None` if there is no such file
+#[cfg(feature = "file-store")]
+fn read_file(path: &std::path::Path) -> Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Replace the file at `path` with `content` atomically, creating it
+/// readable only by its owner on Unix
+#[cfg(feature = "file-store")]
+fn write_file(path: &std::path::Path, content: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Remove the file at `path`, if any
+#[cfg(feature = "file-store")]
+fn delete_file(path: &std::path::Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Store keeping the token in a file encrypted with AES-256-GCM, under a key
+/// derived from a passphrase or given by the application.
+///
+/// Passphrases are stretched with PBKDF2-HMAC-SHA256 and a random salt. The
+/// file is written as by [`JsonFile`](struct.JsonFile.html), and loading it
+/// with another passphrase or key fails with `Error::Store`.
+///
+/// Requires the `encrypted-store` feature.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::store::{EncryptedFile, TokenStore};
+///
+/// let path = std::env::temp_dir().join("oauth-client-doc.enc");
+/// let store = EncryptedFile::with_key(path, [7; 32]);
+/// store.save(&oauth_client::Token::new("token", "secret")).unwrap();
+/// assert_eq!(store.load().unwrap().unwrap().key, "token");
+/// store.delete().unwrap();
+/// ```
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+pub struct EncryptedFile {
+    path: std::path::PathBuf,
+    key: StoreKey,
+}
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+enum StoreKey {
+    Passphrase(String),
+    Key([u8; 32]),
+}
+
+/// Format of the encrypted files: the magic number, then the salt, the
+/// nonce and the ciphertext with its tag
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+const MAGIC: &[u8] = b"OCT1";
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+const SALT_LEN: usize = 16;
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+const PBKDF2_ITERATIONS: u32 = 600_000;
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+impl EncryptedFile {
+    /// Create new store in the file at `path`, encrypted under a key
+    /// derived from `passphrase`
+    pub fn with_passphrase<P, S>(path: P, passphrase: S) -> EncryptedFile
+    where
+        P: Into<std::path::PathBuf>,
+        S: Into<String>,
+    {
+        EncryptedFile {
+            path: path.into(),
+            key: StoreKey::Passphrase(passphrase.into()),
+        }
+    }
+
+    /// Create new store in the file at `path`, encrypted under `key`, such
+    /// as a key kept by the operating system or derived by the application
+    pub fn with_key<P: Into<std::path::PathBuf>>(path: P, key: [u8; 32]) -> EncryptedFile {
+        EncryptedFile {
+            path: path.into(),
+            key: StoreKey::Key(key),
+        }
+    }
+
+    /// The AES-256-GCM key for the file with `salt`
+    fn aead_key(&self, salt: &[u8]) -> ring::aead::LessSafeKey {
+        use ring::{aead, pbkdf2};
+
+        let mut key = [0; 32];
+        match &self.key {
+            StoreKey::Passphrase(passphrase) => {
+                // `PBKDF2_ITERATIONS` is not zero.
+                let iterations = std::num::NonZeroU32::new(PBKDF2_ITERATIONS).unwrap();
+                pbkdf2::derive(
+                    pbkdf2::PBKDF2_HMAC_SHA256,
+                    iterations,
+                    salt,
+                    passphrase.as_bytes(),
+                    &mut key,
+                );
+            }
+            StoreKey::Key(k) => key = *k,
+        }
+        // The key has the length of AES-256.
+        let aead_key =
+            aead::LessSafeKey::new(aead::UnboundKey::new(&aead::AES_256_GCM, &key).unwrap());
+        wipe(&mut key);
+        aead_key
+    }
+}
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+fn store_error(reason: &'static str) -> crate::Error {
+    Error::Store(reason.into())
+}
+
+/// Wipe a copy of a key or of a token from memory
+#[cfg(all(
+    feature = "encrypted-store",
+    feature = "zeroize",
+    not(target_arch = "wasm32")
+))]
+fn wipe(key: &mut [u8]) {
+    zeroize::Zeroize::zeroize(key);
+}
+
+#[cfg(all(
+    feature = "encrypted-store",
+    not(feature = "zeroize"),
+    not(target_arch = "wasm32")
+))]
+fn wipe(_key: &mut [u8]) {}
+
+#[cfg(all(
+    feature = "encrypted-store",
+    feature = "zeroize",
+    not(target_arch = "wasm32")
+))]
+impl Drop for EncryptedFile {
+    fn drop(&mut self) {
+        use zeroize::Zeroize;
+
+        match &mut self.key {
+            StoreKey::Passphrase(passphrase) => passphrase.zeroize(),
+            StoreKey::Key(key) => key.zeroize(),
+        }
+    }
+}
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+impl fmt::Debug for EncryptedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedFile")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+impl TokenStore for EncryptedFile {
+    fn load(&self) -> Result<Option<Token<'static>>> {
+        use ring::aead::{Aad, Nonce, NONCE_LEN};
+
+        let mut content = match read_file(&self.path)? {
+            Some(content) => content,
+            None => return Ok(None),
+        };
+        if content.len() < MAGIC.len() + SALT_LEN + NONCE_LEN || !content.starts_with(MAGIC) {
+            return Err(store_error("not an encrypted token file"));
+        }
+        let (header, ciphertext) = content.split_at_mut(MAGIC.len() + SALT_LEN + NONCE_LEN);
+        let salt = &header[MAGIC.len()..MAGIC.len() + SALT_LEN];
+        let nonce = Nonce::try_assume_unique_for_key(&header[MAGIC.len() + SALT_LEN..])
+            .map_err(|_| store_error("not an encrypted token file"))?;
+        let json = self
+            .aead_key(salt)
+            .open_in_place(nonce, Aad::from(MAGIC), ciphertext)
+            .map_err(|_| store_error("cannot decrypt the token file, wrong key?"))?;
+        let token = from_json(json);
+        wipe(json);
+        token.map(Some)
+    }
+
+    fn save(&self, token: &Token<'_>) -> Result<()> {
+        use ring::aead::{Aad, Nonce, NONCE_LEN};
+        use ring::rand::{SecureRandom, SystemRandom};
+
+        let random = SystemRandom::new();
+        let mut salt = [0; SALT_LEN];
+        let mut nonce = [0; NONCE_LEN];
+        random
+            .fill(&mut salt)
+            .and_then(|()| random.fill(&mut nonce))
+            .map_err(|_| store_error("no random number generator"))?;
+
+        let mut content = MAGIC.to_vec();
+        content.extend_from_slice(&salt);
+        content.extend_from_slice(&nonce);
+        let mut ciphertext = to_json(token)?.into_bytes();
+        self.aead_key(&salt)
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(MAGIC),
+                &mut ciphertext,
+            )
+            .map_err(|_| store_error("cannot encrypt the token"))?;
+        content.extend_from_slice(&ciphertext);
+        write_file(&self.path, &content)
+    }
+
+    fn delete(&self) -> Result<()> {
+        delete_file(&self.path)
+    }
+}
+
+/// Store keeping the token in the credential store of the operating system:
+/// the Keychain on macOS, the Credential Manager on Windows and the Secret
+/// Service on Linux.
+///
+/// # Examples
+///
+/// ```no_run
+/// use oauth_client::store::{Keyring, TokenStore};
+///
+/// let store = Keyring::new("my-app", "alice").unwrap();
+/// let token = store.load().unwrap();
+/// ```
+#[cfg(feature = "keyring")]
+pub struct Keyring {
+    entry: keyring::Entry,
+}
+
+#[cfg(feature = "keyring")]
+impl Keyring {
+    /// Create new store in the entry of `user` for `service`
+    pub fn new(service: &str, user: &str) -> Result<Keyring> {
+        let entry = keyring::Entry::new(service, user).map_err(|err| Error::Store(err.into()))?;
+        Ok(Keyring { entry })
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keyring").finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "keyring")]
+impl TokenStore for Keyring {
+    fn load(&self) -> Result<Option<Token<'static>>> {
+        match self.entry.get_password() {
+            Ok(json) => from_json(json.as_bytes()).map(Some),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(err) => Err(Error::Store(err.into())),
+        }
+    }
+
+    fn save(&self, token: &Token<'_>) -> Result<()> {
+        self.entry
+            .set_password(&to_json(token)?)
+            .map_err(|err| Error::Store(err.into()))
+    }
+
+    fn delete(&self) -> Result<()> {
+        match self.entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(Error::Store(err.into())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn registry() {
+        use super::{TokenRegistry, TokenStore};
+        use crate::{AccessToken, ConsumerToken, Error, Result, Session, Token};
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Memory(Arc<Mutex<Option<Token<'static>>>>);
+
+        impl TokenStore for Memory {
+            fn load(&self) -> Result<Option<Token<'static>>> {
+                Ok(self.0.lock().unwrap().clone())
+            }
+
+            fn save(&self, token: &Token<'_>) -> Result<()> {
+                *self.0.lock().unwrap() =
+                    Some(Token::new(token.key.to_string(), token.secret.to_string()));
+                Ok(())
+            }
+
+            fn delete(&self) -> Result<()> {
+                *self.0.lock().unwrap() = None;
+                Ok(())
+            }
+        }
+
+        let alice = Memory::default();
+        alice.save(&Token::new("alice", "secret")).unwrap();
+        let bob = Memory::default();
+        let stores = [("alice", alice), ("bob", bob.clone())];
+        let registry = TokenRegistry::with_store(move |account: &str| {
+            let (_, store) = stores.iter().find(|(id, _)| *id == account).unwrap();
+            Ok(Box::new(store.clone()) as Box<dyn TokenStore>)
+        });
+        registry
+            .insert("bob", AccessToken::new("bob", "secret"))
+            .unwrap();
+        assert_eq!(bob.load().unwrap().unwrap().key, "bob");
+
+        let session = Session::new(ConsumerToken::new("key", "secret"), None);
+        let signed = registry.session(&session, "alice").unwrap();
+        assert_eq!(signed.token().unwrap().key, "alice");
+        assert!(session.token().is_none());
+
+        assert!(registry.remove("bob").unwrap().is_some());
+        assert!(bob.load().unwrap().is_none());
+        match registry.session(&session, "bob") {
+            Err(Error::UnknownAccount(account)) => assert_eq!(account, "bob"),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        assert_eq!(registry.accounts(), ["alice"]);
+    }
+
+    #[test]
+    #[cfg(feature = "file-store")]
+    fn json_file() {
+        use super::{JsonFile, TokenStore};
+        use crate::Token;
+
+        let path = std::env::temp_dir().join(format!("oauth-client-{}.json", std::process::id()));
+        let store = JsonFile::new(&path);
+        assert!(store.load().unwrap().is_none());
+        store.save(&Token::new("token", "secret")).unwrap();
+        store.save(&Token::new("token", "other")).unwrap();
+        assert_eq!(store.load().unwrap().unwrap().expose_secret(), "other");
+        store.delete().unwrap();
+        store.delete().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(all(feature = "encrypted-store", not(target_arch = "wasm32")))]
+    fn encrypted_file() {
+        use super::{EncryptedFile, TokenStore};
+        use crate::Token;
+
+        let path = std::env::temp_dir().join(format!("oauth-client-{}.enc", std::process::id()));
+        let store = EncryptedFile::with_passphrase(&path, "passphrase");
+        assert!(store.load().unwrap().is_none());
+        store.save(&Token::new("token", "secret")).unwrap();
+        let content = std::fs::read(&path).unwrap();
+        assert!(!content.windows(6).any(|w| w == b"secret"));
+        assert_eq!(store.load().unwrap().unwrap().expose_secret(), "secret");
+
+        let other = EncryptedFile::with_passphrase(&path, "other");
+        assert!(matches!(other.load(), Err(crate::Error::Store(_))));
+        let keyed = EncryptedFile::with_key(&path, [0; 32]);
+        keyed.save(&Token::new("token", "other")).unwrap();
+        assert_eq!(keyed.load().unwrap().unwrap().expose_secret(), "other");
+        assert!(store.load().is_err());
+        store.delete().unwrap();
+        assert!(!path.exists());
+    }
+
+    #[test]
+    #[cfg(feature = "keyring")]
+    fn keyring() {
+        use super::{Keyring, TokenStore};
+        use crate::Token;
+
+        keyring::set_default_credential_builder(keyring::mock::default_credential_builder());
+        let store = Keyring::new("oauth-client", "test").unwrap();
+        assert!(store.load().unwrap().is_none());
+        store.save(&Token::new("token", "secret")).unwrap();
+        assert_eq!(store.load().unwrap().unwrap().key, "token");
+        store.delete().unwrap();
+        assert!(store.load().unwrap().is_none());
+    }
+}
@@ -0,0 +1,354 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Long-lived connections to streaming endpoints, yielding the lines of the
+//! response and reconnecting when the connection drops.
+//!
+//! Requires the `streaming` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use futures_util::StreamExt;
+//! use oauth_client::streaming::StreamingConnection;
+//!
+//! async {
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//!     let access = oauth_client::AccessToken::new("token", "secret");
+//!     let session = oauth_client::Session::new(consumer, Some(access));
+//!     let lines = StreamingConnection::get(session, "https://stream.example.com/1/feed")
+//!         .into_stream();
+//!     futures_util::pin_mut!(lines);
+//!     while let Some(line) = lines.next().await {
+//!         println!("{}", line.unwrap());
+//!     }
+//! };
+//! ```
+
+use crate::audit::Audit;
+use crate::core::check_status_with;
+use crate::transport::check_size;
+use crate::{Error, ParamList, Result, Session};
+use futures_util::stream::{self, Stream};
+use http::{Method, StatusCode};
+use log::*;
+use std::time::Duration;
+
+/// Maximum size of a line, unless the session limits the size of responses
+const MAX_LINE_SIZE: usize = 1024 * 1024;
+
+/// A signed streaming request, reconnected with exponential backoff.
+///
+/// The request is signed again, with a new nonce and timestamp, on every
+/// connection. Lines are separated by `\n`, and blank lines, sent by most
+/// providers to keep the connection alive, are skipped. The delays between
+/// reconnections are waited with the timer of the session.
+///
+/// The connection is sent with a reqwest client of its own: the transport,
+/// the interceptors, the circuit breaker, the cancellation token, the
+/// deadline and the retry policy of the session do not apply to it.
+#[derive(Debug)]
+pub struct StreamingConnection {
+    client: reqwest::Client,
+    session: Session,
+    method: Method,
+    uri: String,
+    param: Option<ParamList<'static>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    backoff: Duration,
+    max_line_size: usize,
+    response: Option<reqwest::Response>,
+    buffer: Vec<u8>,
+}
+
+impl StreamingConnection {
+    /// Create new connection sending a GET request to `uri`
+    pub fn get<U: Into<String>>(session: Session, uri: U) -> StreamingConnection {
+        StreamingConnection::new(session, Method::GET, uri.into())
+    }
+
+    /// Create new connection sending a form-encoded POST request to `uri`
+    pub fn post<U: Into<String>>(session: Session, uri: U) -> StreamingConnection {
+        StreamingConnection::new(session, Method::POST, uri.into())
+    }
+
+    fn new(session: Session, method: Method, uri: String) -> StreamingConnection {
+        let initial_backoff = Duration::from_secs(1);
+        let max_line_size = session.config().max_response_size.unwrap_or(MAX_LINE_SIZE);
+        StreamingConnection {
+            client: reqwest::Client::new(),
+            session,
+            method,
+            uri,
+            param: None,
+            initial_backoff,
+            max_backoff: Duration::from_secs(320),
+            backoff: initial_backoff,
+            max_line_size,
+            response: None,
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Set the parameters of the request
+    pub fn param(mut self, param: ParamList<'static>) -> StreamingConnection {
+        self.param = Some(param);
+        self
+    }
+
+    /// Set the delay before the first reconnection, 1 second by default.
+    /// It is doubled after every failed attempt.
+    pub fn initial_backoff(mut self, delay: Duration) -> StreamingConnection {
+        self.initial_backoff = delay;
+        self.backoff = delay;
+        self
+    }
+
+    /// Set the maximum delay between reconnections, 320 seconds by default
+    pub fn max_backoff(mut self, delay: Duration) -> StreamingConnection {
+        self.max_backoff = delay;
+        self
+    }
+
+    /// Fail with `Error::BodyTooLarge` on lines longer than `bytes`, the
+    /// maximum response size of the session or 1 MiB by default
+    pub fn max_line_size(mut self, bytes: usize) -> StreamingConnection {
+        self.max_line_size = bytes;
+        self
+    }
+
+    /// Read the next line, connecting first if needed.
+    ///
+    /// Closed and dropped connections and server errors (5xx, 420 and 429)
+    /// are retried after a delay; other errors, such as a rejected signature
+    /// or a line too long, are returned.
+    pub async fn next_line(&mut self) -> Result<String> {
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(line);
+            }
+            if let Err(err) = check_size(Some(self.max_line_size), self.buffer.len() as u64) {
+                self.response = None;
+                self.buffer.clear();
+                return Err(err);
+            }
+            let response = match &mut self.response {
+                Some(response) => response,
+                None => {
+                    self.connect().await?;
+                    continue;
+                }
+            };
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    self.backoff = self.initial_backoff;
+                    self.buffer.extend_from_slice(&chunk);
+                }
+                Ok(None) => {
+                    debug!("Streaming connection closed");
+                    self.response = None;
+                    let closed = Error::Http("streaming connection closed".into());
+                    self.wait(closed).await?;
+                }
+                Err(err) => {
+                    warn!("Streaming connection dropped: {}", err);
+                    self.response = None;
+                    self.wait(err.into()).await?;
+                }
+            }
+        }
+    }
+
+    /// The lines of the response, as a stream ending after the first error
+    /// returned by [`next_line`](#method.next_line)
+    pub fn into_stream(self) -> impl Stream<Item = Result<String>> {
+        stream::unfold(Some(self), |connection| async move {
+            let mut connection = connection?;
+            match connection.next_line().await {
+                Ok(line) => Some((Ok(line), Some(connection))),
+                Err(err) => Some((Err(err), None)),
+            }
+        })
+    }
+
+    /// Take the next non-blank line out of the buffer
+    fn take_line(&mut self) -> Option<String> {
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if !line.trim().is_empty() {
+                return Some(line.to_string());
+            }
+        }
+        None
+    }
+
+    /// Send the freshly signed request, until it is accepted
+    async fn connect(&mut self) -> Result<()> {
+        self.buffer.clear();
+        loop {
+            let request = if self.method == Method::POST {
+                self.session.post_request(&self.uri, self.param.as_ref())?
+            } else {
+                self.session.get_request(&self.uri, self.param.as_ref())?
+            };
+            let audit = Audit::start(self.session.config(), &request);
+            let mut builder = self
+                .client
+                .request(request.method, &request.url)
+                .headers(request.headers);
+            if !request.body.is_empty() {
+                builder = builder.body(request.body);
+            }
+
+            let response = builder.send().await;
+            if let Some(audit) = audit {
+                audit.finish(response.as_ref().ok().map(reqwest::Response::status));
+            }
+            match response {
+                Ok(response) if response.status() == StatusCode::OK => {
+                    self.response = Some(response);
+                    return Ok(());
+                }
+                Ok(response) => {
+                    let url = response.url().to_string();
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    let body = response.bytes().await.unwrap_or_default();
+                    let retry = status.is_server_error()
+                        || status == StatusCode::TOO_MANY_REQUESTS
+                        || status.as_u16() == 420;
+                    if !retry {
+                        return check_status_with(
+                            &url,
+                            status,
+                            &headers,
+                            &body,
+                            self.session.config(),
+                        );
+                    }
+                    warn!("Streaming connection refused with status {}", status);
+                    let refused = format!("streaming connection refused with status {}", status);
+                    self.wait(Error::Http(refused.into())).await?;
+                }
+                Err(err) => {
+                    warn!("Streaming connection failed: {}", err);
+                    self.wait(err.into()).await?;
+                }
+            }
+        }
+    }
+
+    /// Wait before reconnecting, doubling the next delay, or fail with `err`
+    /// without a timer
+    async fn wait(&mut self, err: Error) -> Result<()> {
+        let timer = self.session.config().timer().ok_or(err)?;
+        timer.sleep(self.backoff).await;
+        self.backoff = (self.backoff * 2).min(self.max_backoff);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreamingConnection;
+    use crate::test_server::serve;
+    use crate::{ConsumerToken, Error, Session};
+    use futures_util::StreamExt;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn reconnect() {
+        let (uri, server) = serve(
+            "/feed",
+            &[
+                "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\na\r\n\r\nb\n",
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\nc\n",
+            ],
+        )
+        .await;
+        let session = Session::new(ConsumerToken::new("key", "secret"), None);
+        let mut connection =
+            StreamingConnection::get(session, uri).initial_backoff(Duration::from_millis(1));
+        assert_eq!(connection.next_line().await.unwrap(), "a");
+        assert_eq!(connection.next_line().await.unwrap(), "b");
+        assert_eq!(connection.next_line().await.unwrap(), "c");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn backoff_on_close() {
+        let (uri, server) = serve(
+            "/feed",
+            &[
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\n",
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\na\n",
+            ],
+        )
+        .await;
+        let delays = Arc::new(Mutex::new(Vec::new()));
+        let timer_delays = delays.clone();
+        let session = Session::new(ConsumerToken::new("key", "secret"), None).timer(
+            move |delay: Duration| {
+                timer_delays.lock().unwrap().push(delay);
+                std::future::ready(())
+            },
+        );
+        let mut connection =
+            StreamingConnection::get(session, uri).initial_backoff(Duration::from_millis(5));
+        assert_eq!(connection.next_line().await.unwrap(), "a");
+        server.await.unwrap();
+
+        let expected = [Duration::from_millis(5), Duration::from_millis(10)];
+        assert_eq!(*delays.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn stream_ends_on_error() {
+        let (uri, server) = serve(
+            "/feed",
+            &[
+                "HTTP/1.1 200 OK\r\nconnection: close\r\n\r\na\n",
+                "HTTP/1.1 401 Unauthorized\r\nconnection: close\r\ncontent-length: 0\r\n\r\n",
+            ],
+        )
+        .await;
+        let session = Session::new(ConsumerToken::new("key", "secret"), None);
+        let lines = StreamingConnection::get(session, uri)
+            .initial_backoff(Duration::from_millis(1))
+            .into_stream()
+            .collect::<Vec<_>>()
+            .await;
+        server.await.unwrap();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].as_ref().unwrap(), "a");
+        assert!(lines[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn line_too_long() {
+        let (uri, server) = serve(
+            "/feed",
+            &["HTTP/1.1 200 OK\r\nconnection: close\r\n\r\nab\n0123456789abcdef"],
+        )
+        .await;
+        let session = Session::new(ConsumerToken::new("key", "secret"), None);
+        let mut connection = StreamingConnection::get(session, uri).max_line_size(8);
+        assert_eq!(connection.next_line().await.unwrap(), "ab");
+        match connection.next_line().await {
+            Err(Error::BodyTooLarge { limit: 8 }) => {}
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Local HTTP server for the tests of the modules sending with reqwest.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+/// Serve `responses` in turn at `path`, checking that the requests are
+/// signed. Returns the URL served and the server task.
+pub(crate) async fn serve(
+    path: &str,
+    responses: &'static [&'static str],
+) -> (String, JoinHandle<()>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let uri = format!("http://{}{}", listener.local_addr().unwrap(), path);
+    let server = tokio::spawn(async move {
+        for response in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let n = socket.read(&mut request).await.unwrap();
+            let request = String::from_utf8_lossy(&request[..n]).to_lowercase();
+            assert!(request.contains("authorization: oauth "));
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    (uri, server)
+}
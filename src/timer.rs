@@ -0,0 +1,288 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Delays between the attempts of a retried request.
+
+use async_trait::async_trait;
+#[cfg(not(target_arch = "wasm32"))]
+use lazy_static::lazy_static;
+#[cfg(not(target_arch = "wasm32"))]
+use std::collections::BTreeMap;
+use std::fmt;
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Condvar, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::task::{Context, Poll, Waker};
+#[cfg(not(target_arch = "wasm32"))]
+use std::thread;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+
+/// Source of the delays before retrying a request, so that retries work on
+/// any executor.
+///
+/// Implemented for closures returning a future, such as
+/// `tokio::time::sleep`.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None)
+///     .timer(tokio::time::sleep)
+///     .retry_after(3);
+/// ```
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait Timer: Send + Sync {
+    /// Complete after `duration`
+    async fn sleep(&self, duration: Duration);
+}
+
+impl fmt::Debug for dyn Timer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Timer")
+    }
+}
+
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<F, T> Timer for F
+where
+    F: Fn(Duration) -> T + Send + Sync,
+    T: Future<Output = ()> + Send,
+{
+    async fn sleep(&self, duration: Duration) {
+        self(duration).await
+    }
+}
+
+/// Timer waking its sleeps from a single thread, used by default.
+///
+/// The thread is shared by all the sessions, and a sleep dropped before
+/// completing, such as the timeout of a request answered in time, is
+/// forgotten at once. Not available on `wasm32`, where a timer must be given
+/// to the session for requests to be retried.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemTimer;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Timer for SystemTimer {
+    async fn sleep(&self, duration: Duration) {
+        Sleep {
+            at: Instant::now() + duration,
+            id: None,
+        }
+        .await
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+lazy_static! {
+    static ref TIMER_THREAD: Arc<TimerThread> = TimerThread::start();
+}
+
+/// The pending sleeps of `SystemTimer`, woken by a thread of their own
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct TimerThread {
+    sleeps: Mutex<Sleeps>,
+    /// Notified when a sleep ends before the earliest one
+    changed: Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+struct Sleeps {
+    wakers: BTreeMap<(Instant, u64), Waker>,
+    next_id: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TimerThread {
+    fn start() -> Arc<TimerThread> {
+        let timer = Arc::new(TimerThread::default());
+        let thread = timer.clone();
+        let _ = thread::Builder::new()
+            .name("oauth-client-timer".to_string())
+            .spawn(move || thread.run())
+            .expect("failed to spawn the timer thread");
+        timer
+    }
+
+    /// Wake the sleeps as they end
+    fn run(&self) {
+        let mut sleeps = self.sleeps.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let mut ended = Vec::new();
+            while let Some(sleep) = sleeps.wakers.first_entry() {
+                if sleep.key().0 > now {
+                    break;
+                }
+                ended.push(sleep.remove());
+            }
+            if !ended.is_empty() {
+                // Wakers may poll their task at once, which locks the sleeps.
+                drop(sleeps);
+                ended.into_iter().for_each(Waker::wake);
+                sleeps = self.sleeps.lock().unwrap();
+                continue;
+            }
+            sleeps = match sleeps.wakers.keys().next() {
+                Some(&(at, _)) => self.changed.wait_timeout(sleeps, at - now).unwrap().0,
+                None => self.changed.wait(sleeps).unwrap(),
+            };
+        }
+    }
+}
+
+/// A sleep until `at`, registered with the timer thread once polled
+#[cfg(not(target_arch = "wasm32"))]
+struct Sleep {
+    at: Instant,
+    id: Option<u64>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.at {
+            self.cancel();
+            return Poll::Ready(());
+        }
+        let timer = &*TIMER_THREAD;
+        let mut sleeps = timer.sleeps.lock().unwrap();
+        let id = match self.id {
+            Some(id) => id,
+            None => {
+                let id = sleeps.next_id;
+                sleeps.next_id += 1;
+                self.id = Some(id);
+                id
+            }
+        };
+        let earliest = sleeps
+            .wakers
+            .keys()
+            .next()
+            .is_none_or(|&(at, _)| self.at < at);
+        let _ = sleeps.wakers.insert((self.at, id), cx.waker().clone());
+        drop(sleeps);
+        if earliest {
+            timer.changed.notify_one();
+        }
+        Poll::Pending
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Sleep {
+    /// Forget the sleep
+    fn cancel(&mut self) {
+        if let Some(id) = self.id.take() {
+            let mut sleeps = TIMER_THREAD.sleeps.lock().unwrap();
+            let _ = sleeps.wakers.remove(&(self.at, id));
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Sleep {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Run `future` for up to `duration`, `None` if it did not complete in time
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn timeout<F: Future>(
+    timer: &dyn Timer,
+    duration: Duration,
+    future: F,
+) -> Option<F::Output> {
+    let mut future = std::pin::pin!(future);
+    let mut sleep = timer.sleep(duration);
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        sleep.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{timeout, Sleep, SystemTimer, Timer, TIMER_THREAD};
+    use std::future::Future;
+    use std::sync::Arc;
+    use std::task::{Context, Wake, Waker};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn system_timer() {
+        let start = Instant::now();
+        tokio::join!(
+            SystemTimer.sleep(Duration::from_millis(20)),
+            SystemTimer.sleep(Duration::from_millis(40)),
+        );
+        assert!(start.elapsed() >= Duration::from_millis(40));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test]
+    async fn timeout_forgets_its_sleep() {
+        let start = Instant::now();
+        let done = timeout(&SystemTimer, Duration::from_secs(30), async { 1 }).await;
+        assert_eq!(done, Some(1));
+        let late = timeout(
+            &SystemTimer,
+            Duration::from_millis(10),
+            std::future::pending::<()>(),
+        );
+        assert_eq!(late.await, None);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    struct Noop;
+
+    impl Wake for Noop {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    #[test]
+    fn dropped_sleep() {
+        let at = Instant::now() + Duration::from_secs(60);
+        let mut sleep = Box::pin(Sleep { at, id: None });
+        let waker: Waker = Arc::new(Noop).into();
+        let mut cx = Context::from_waker(&waker);
+        assert!(sleep.as_mut().poll(&mut cx).is_pending());
+        let id = sleep.id.unwrap();
+        assert!(TIMER_THREAD
+            .sleeps
+            .lock()
+            .unwrap()
+            .wakers
+            .contains_key(&(at, id)));
+        drop(sleep);
+        assert!(!TIMER_THREAD
+            .sleeps
+            .lock()
+            .unwrap()
+            .wakers
+            .contains_key(&(at, id)));
+    }
+}
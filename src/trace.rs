@@ -0,0 +1,129 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! `tracing` spans around the requests sent.
+//!
+//! A span records the method, the host, the status and the duration of a
+//! request, never its URL, headers or body, which carry the tokens and the
+//! signature.
+
+use crate::transport::{HttpTransport, Response, SignedRequest};
+use crate::Result;
+use std::time::Instant;
+use tracing::field::{display, Empty};
+use tracing::Instrument;
+
+/// Send `request` with `transport`, within an `oauth_request` span
+pub(crate) async fn execute(
+    transport: &dyn HttpTransport,
+    request: SignedRequest,
+) -> Result<Response> {
+    let host = url::Url::parse(&request.url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default();
+    let span = tracing::info_span!(
+        "oauth_request",
+        method = %request.method,
+        host = %host,
+        status = Empty,
+        duration_ms = Empty,
+        error = Empty,
+    );
+    let start = Instant::now();
+    let rsp = transport.execute(request).instrument(span.clone()).await;
+    let _ = span.record("duration_ms", start.elapsed().as_millis() as u64);
+    match &rsp {
+        Ok(response) => span.record("status", response.status.as_u16()),
+        Err(err) => span.record("error", display(err)),
+    };
+    rsp
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::transport::{HttpTransport, Response, SignedRequest};
+    use crate::{AccessToken, ConsumerToken, Result, Session};
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    /// Subscriber writing the fields of the spans as `name=value`
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            let field = format!("{}={:?}", field.name(), value);
+            self.0.lock().unwrap().push(field);
+        }
+    }
+
+    impl Subscriber for Fields {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            event.record(&mut self.clone());
+        }
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    struct Empty;
+
+    #[async_trait::async_trait]
+    impl HttpTransport for Empty {
+        async fn execute(&self, request: SignedRequest) -> Result<Response> {
+            Ok(Response {
+                url: request.url,
+                status: http::StatusCode::OK,
+                headers: http::HeaderMap::new(),
+                body: Vec::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn span() {
+        let fields = Fields::default();
+        let _guard = tracing::subscriber::set_default(fields.clone());
+        let session = Session::new(
+            ConsumerToken::new("key", "consumer-secret"),
+            Some(AccessToken::new("the-token", "token-secret")),
+        )
+        .transport(Empty);
+        session.get("http://example.com/a?b=1", None).await.unwrap();
+
+        let fields = fields.0.lock().unwrap();
+        assert!(fields.contains(&"method=GET".to_string()));
+        assert!(fields.contains(&"host=example.com".to_string()));
+        assert!(fields.contains(&"status=200".to_string()));
+        assert!(fields.iter().any(|f| f.starts_with("duration_ms=")));
+        for field in fields.iter() {
+            assert!(!field.contains("secret"), "{}", field);
+            assert!(!field.contains("the-token"), "{}", field);
+            assert!(!field.contains("oauth_signature"), "{}", field);
+        }
+    }
+}
@@ -0,0 +1,389 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Signed transfers of large bodies, streamed instead of held in memory.
+//!
+//! Requires the `transfer` feature. Transfers are always sent with reqwest,
+//! whatever the transport of the session, and are not retried. The
+//! [`Session`](../struct.Session.html) variants report their
+//! [`Progress`](struct.Progress.html) to the hook set with
+//! [`Session::progress`](../struct.Session.html#method.progress).
+//!
+//! # Examples
+//!
+//! ```
+//! async {
+//!     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+//!     let access = oauth_client::AccessToken::new("token", "secret");
+//!     let file = tokio::fs::File::create("export.zip").await.unwrap();
+//!     let size = oauth_client::transfer::download(
+//!         "https://api.example.com/1/export",
+//!         &consumer,
+//!         Some(&access),
+//!         file,
+//!     )
+//!     .await
+//!     .unwrap();
+//! };
+//! ```
+
+use crate::audit::Audit;
+use crate::core::{check_status_with, get_request_with, post_request_with};
+use crate::transport::{check_size, read_reqwest_body, SignedRequest};
+use crate::{
+    AccessToken, Config, ConsumerToken, Result, StatusClass, Token, CLIENT, ERROR_BODY_LIMIT,
+};
+use futures_util::stream::{self, Stream};
+use http::header::CONTENT_TYPE;
+use reqwest::multipart::{Form, Part};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Size of the chunks read from uploaded files
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Bytes of a body sent or received so far.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes transferred
+    pub transferred: u64,
+    /// Size of the whole body, if known
+    pub total: Option<u64>,
+}
+
+/// Observer of the progress of transfers, called after every chunk.
+///
+/// Implemented for closures taking a [`Progress`](struct.Progress.html).
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::transfer::Progress;
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).progress(|progress: Progress| {
+///     if let Some(total) = progress.total {
+///         println!("{}/{} bytes", progress.transferred, total);
+///     }
+/// });
+/// ```
+pub trait ProgressHook: Send + Sync {
+    /// `progress` was made on the body
+    fn progress(&self, progress: Progress);
+}
+
+impl fmt::Debug for dyn ProgressHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressHook")
+    }
+}
+
+impl<F> ProgressHook for F
+where
+    F: Fn(Progress) + Send + Sync,
+{
+    fn progress(&self, progress: Progress) {
+        self(progress)
+    }
+}
+
+/// Send authorized GET request to the specified URL, and write its body
+/// into `writer` as it is received. Returns the number of bytes written.
+///
+/// On an error status, nothing is written into `writer`. The request is
+/// sent with reqwest and the default settings, bypassing any session
+/// transport.
+pub async fn download<W: AsyncWrite + Unpin>(
+    uri: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+    writer: W,
+) -> Result<u64> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    download_with(uri, consumer, token, &Config::default(), writer).await
+}
+
+pub(crate) async fn download_with<W: AsyncWrite + Unpin>(
+    uri: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    config: &Config,
+    mut writer: W,
+) -> Result<u64> {
+    let request = get_request_with(uri, consumer, token, None, config)?;
+    let mut response = send(request, None, config).await?;
+    if config.classify(response.status()) != StatusClass::Success {
+        let url = response.url().to_string();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() >= ERROR_BODY_LIMIT {
+                break;
+            }
+        }
+        check_status_with(&url, status, &headers, &body, config)?;
+    }
+
+    let total = response.content_length();
+    let mut size = 0;
+    while let Some(chunk) = response.chunk().await? {
+        size += chunk.len() as u64;
+        check_size(config.max_response_size, size)?;
+        writer.write_all(&chunk).await?;
+        if let Some(hook) = &config.progress {
+            hook.progress(Progress {
+                transferred: size,
+                total,
+            });
+        }
+    }
+    writer.flush().await?;
+    Ok(size)
+}
+
+/// Send authorized multipart POST request to the specified URL, with the
+/// file at `path` streamed as the part `field_name`. Returns the body of the
+/// response.
+///
+/// Only the `oauth_*` parameters are signed, as multipart bodies are not.
+/// The request is sent with reqwest and the default settings, bypassing any
+/// session transport.
+///
+/// # Examples
+///
+/// ```
+/// async {
+///     let consumer = oauth_client::ConsumerToken::new("key", "secret");
+///     let access = oauth_client::AccessToken::new("token", "secret");
+///     let bytes = oauth_client::transfer::upload_file(
+///         "https://upload.twitter.com/1.1/media/upload.json",
+///         "photo.jpg",
+///         "media",
+///         &consumer,
+///         Some(&access),
+///     )
+///     .await
+///     .unwrap();
+/// };
+/// ```
+pub async fn upload_file(
+    uri: impl AsRef<str>,
+    path: impl AsRef<Path>,
+    field_name: &str,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+) -> Result<Vec<u8>> {
+    let uri = uri.as_ref();
+    let token = token.map(|t| &**t);
+    let path = path.as_ref();
+    upload_file_with(uri, path, field_name, consumer, token, &Config::default()).await
+}
+
+pub(crate) async fn upload_file_with(
+    uri: &str,
+    path: &Path,
+    field_name: &str,
+    consumer: &Token<'_>,
+    token: Option<&Token<'_>>,
+    config: &Config,
+) -> Result<Vec<u8>> {
+    let mut request = post_request_with(uri, consumer, token, None, config)?;
+    // The multipart form sets its own content type.
+    let _ = request.headers.remove(CONTENT_TYPE);
+
+    let file = File::open(path).await?;
+    let len = file.metadata().await?.len();
+    let chunks = read_chunks(file, len, config.progress.clone());
+    let body = reqwest::Body::wrap_stream(chunks);
+    let mut part = Part::stream_with_length(body, len);
+    if let Some(name) = path.file_name() {
+        part = part.file_name(name.to_string_lossy().into_owned());
+    }
+    let form = Form::new().part(field_name.to_string(), part);
+
+    let response = send(request, Some(form), config).await?;
+    let url = response.url().to_string();
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = read_reqwest_body(response, config.max_response_size).await?;
+    check_status_with(&url, status, &headers, &body, config)?;
+    Ok(body)
+}
+
+/// The content of `file`, of `len` bytes, in chunks of `CHUNK_SIZE` bytes
+/// reported to `hook` as they are read
+fn read_chunks(
+    file: File,
+    len: u64,
+    hook: Option<Arc<dyn ProgressHook>>,
+) -> impl Stream<Item = io::Result<Vec<u8>>> {
+    stream::unfold((Some(file), 0), move |(file, read)| {
+        let hook = hook.clone();
+        async move {
+            let mut file = file?;
+            let mut chunk = vec![0; CHUNK_SIZE];
+            match file.read(&mut chunk).await {
+                Ok(0) => None,
+                Ok(n) => {
+                    chunk.truncate(n);
+                    let read = read + n as u64;
+                    if let Some(hook) = hook {
+                        hook.progress(Progress {
+                            transferred: read,
+                            total: Some(len),
+                        });
+                    }
+                    Some((Ok(chunk), (Some(file), read)))
+                }
+                Err(err) => Some((Err(err), (None, read))),
+            }
+        }
+    })
+}
+
+/// Send `request` with the shared reqwest client, with `form` as its body
+/// if any
+async fn send(
+    request: SignedRequest,
+    form: Option<Form>,
+    config: &Config,
+) -> Result<reqwest::Response> {
+    let audit = Audit::start(config, &request);
+    let mut builder = builder(request);
+    if let Some(form) = form {
+        builder = builder.multipart(form);
+    }
+    let response = builder.send().await;
+    if let Some(audit) = audit {
+        audit.finish(response.as_ref().ok().map(reqwest::Response::status));
+    }
+    Ok(response?)
+}
+
+/// Build `request` with the shared reqwest client
+fn builder(request: SignedRequest) -> reqwest::RequestBuilder {
+    let mut builder = CLIENT
+        .request(request.method, &request.url)
+        .headers(request.headers);
+    if !request.body.is_empty() {
+        builder = builder.body(request.body);
+    }
+    if let Some(timeout) = request.timeout {
+        builder = builder.timeout(timeout);
+    }
+    builder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Progress;
+    use crate::test_server::serve;
+    use crate::{ConsumerToken, Error, Session};
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn download() {
+        let (uri, server) = serve(
+            "/export",
+            &[
+                "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 5\r\n\r\nhello",
+                "HTTP/1.1 404 Not Found\r\nconnection: close\r\ncontent-length: 7\r\n\r\nmissing",
+            ],
+        )
+        .await;
+        let consumer = ConsumerToken::new("key", "secret");
+        let mut file = Vec::new();
+        let size = super::download(&uri, &consumer, None, &mut file)
+            .await
+            .unwrap();
+        assert_eq!(size, 5);
+        assert_eq!(file, b"hello");
+
+        let mut file = Vec::new();
+        match super::download(&uri, &consumer, None, &mut file)
+            .await
+            .map_err(Error::into_inner)
+        {
+            Err(Error::Status(err)) => assert_eq!(err.status, 404),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        assert!(file.is_empty());
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn download_progress() {
+        let (uri, server) = serve(
+            "/export",
+            &["HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 5\r\n\r\nhello"],
+        )
+        .await;
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = progress.clone();
+        let session = Session::new(ConsumerToken::new("key", "secret"), None)
+            .progress(move |p: Progress| reported.lock().unwrap().push(p));
+        let mut file = Vec::new();
+        assert_eq!(session.download(&uri, &mut file).await.unwrap(), 5);
+        let expected = Progress {
+            transferred: 5,
+            total: Some(5),
+        };
+        assert_eq!(progress.lock().unwrap().last(), Some(&expected));
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn upload_file() {
+        let path = std::env::temp_dir().join("oauth-client-upload.txt");
+        std::fs::write(&path, "hello upload").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let uri = format!("http://{}/upload", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            while !request.ends_with(b"--\r\n") {
+                let mut chunk = [0; 1024];
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let request = String::from_utf8_lossy(&request).to_lowercase();
+            assert!(request.contains("authorization: oauth "));
+            assert!(request.contains("content-type: multipart/form-data; boundary="));
+            assert!(request.contains("name=\"media\"; filename=\"oauth-client-upload.txt\""));
+            assert!(request.contains("\r\n\r\nhello upload\r\n"));
+            let response = "HTTP/1.1 200 OK\r\nconnection: close\r\ncontent-length: 2\r\n\r\nok";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let consumer = ConsumerToken::new("key", "secret");
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = progress.clone();
+        let session = Session::new(consumer.clone(), None)
+            .progress(move |p: Progress| reported.lock().unwrap().push(p));
+        let body = session.upload_file(&uri, &path, "media").await.unwrap();
+        assert_eq!(body, b"ok");
+        let expected = Progress {
+            transferred: 12,
+            total: Some(12),
+        };
+        assert_eq!(*progress.lock().unwrap(), [expected]);
+        server.await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let rsp = super::upload_file(&uri, &path, "media", &consumer, None).await;
+        assert!(matches!(rsp, Err(Error::Io(_))));
+    }
+}
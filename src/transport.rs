@@ -0,0 +1,915 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HTTP transports executing signed requests.
+//!
+//! The reqwest transport is enabled by the default `reqwest` feature, the hyper
+//! transport by the `hyper` feature and the surf transport, for async-std
+//! applications, by the `surf` feature and the isahc (libcurl) transport by
+//! the `isahc` feature. Blocking transports are enabled by the
+//! `blocking` (reqwest) and `ureq` features.
+
+#[cfg(feature = "client")]
+use crate::Error;
+use crate::RateLimit;
+#[cfg(feature = "client")]
+use crate::Result;
+#[cfg(feature = "client")]
+use async_trait::async_trait;
+use http::{HeaderMap, Method, StatusCode};
+#[cfg(feature = "client")]
+use std::fmt;
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// A signed request, ready to be sent.
+#[derive(Clone, Debug)]
+pub struct SignedRequest {
+    /// HTTP method
+    pub method: Method,
+    /// Full URL, including any query string
+    pub url: String,
+    /// Headers, including `Authorization`
+    pub headers: HeaderMap,
+    /// Body, empty for requests without one
+    pub body: Vec<u8>,
+    /// Time left to complete the exchange, from the deadline of the session
+    pub timeout: Option<Duration>,
+    /// Maximum size of the response body in bytes. Transports stop reading
+    /// past it and fail with `Error::BodyTooLarge`.
+    pub max_response_size: Option<usize>,
+}
+
+impl SignedRequest {
+    pub(crate) fn new(method: Method, url: String) -> SignedRequest {
+        SignedRequest {
+            method,
+            url,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            timeout: None,
+            max_response_size: None,
+        }
+    }
+
+    /// A curl command sending this request, to reproduce it outside the
+    /// application. With `redact`, the values of `oauth_token`,
+    /// `oauth_token_secret`, `oauth_verifier` and `oauth_signature` are
+    /// replaced by `REDACTED`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let token = oauth_client::AccessToken::new("token", "secret");
+    /// let request =
+    ///     oauth_client::core::get_request("http://example.com", &consumer, Some(&token), None).unwrap();
+    /// let command = request.to_curl(true);
+    /// assert!(command.starts_with("curl -X GET 'http://example.com' -H 'authorization: OAuth "));
+    /// assert!(command.contains("oauth_token=\"REDACTED\""));
+    /// ```
+    pub fn to_curl(&self, redact: bool) -> String {
+        let redact = |s: &str| {
+            if redact {
+                redact_params(s)
+            } else {
+                s.to_string()
+            }
+        };
+
+        let mut command = format!(
+            "curl -X {} {}",
+            self.method,
+            shell_quote(&redact(&self.url))
+        );
+        for (name, value) in &self.headers {
+            let value = redact(&String::from_utf8_lossy(value.as_bytes()));
+            command.push_str(" -H ");
+            command.push_str(&shell_quote(&format!("{}: {}", name, value)));
+        }
+        if !self.body.is_empty() {
+            command.push_str(" --data-raw ");
+            command.push_str(&shell_quote(&redact(&String::from_utf8_lossy(&self.body))));
+        }
+        command
+    }
+}
+
+/// Quote `s` for POSIX shells
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Replace the values of `oauth_token`, `oauth_token_secret`,
+/// `oauth_verifier` and `oauth_signature`, in a header, a query string or a
+/// form body
+pub(crate) fn redact_params(s: &str) -> String {
+    let mut redacted = s.to_string();
+    for name in &[
+        "oauth_token=",
+        "oauth_token_secret=",
+        "oauth_verifier=",
+        "oauth_signature=",
+    ] {
+        let mut from = 0;
+        while let Some(i) = redacted[from..].find(name) {
+            let start = from + i;
+            let prefixed = redacted[..start]
+                .chars()
+                .last()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_');
+            let mut value = start + name.len();
+            if redacted[value..].starts_with('"') {
+                value += 1;
+            }
+            let end = redacted[value..]
+                .find(['&', '"', ','])
+                .map_or(redacted.len(), |i| value + i);
+            if !prefixed {
+                redacted.replace_range(value..end, "REDACTED");
+            }
+            from = value;
+        }
+    }
+    redacted
+}
+
+/// The decoded parameter `name` of an `OAuth` `Authorization` header
+#[cfg(feature = "client")]
+pub(crate) fn authorization_param(headers: &HeaderMap, name: &str) -> Option<String> {
+    let header = headers.get(http::header::AUTHORIZATION)?.to_str().ok()?;
+    let header = header.strip_prefix("OAuth ")?;
+    header.split(", ").find_map(|pair| {
+        let value = pair
+            .strip_prefix(name)?
+            .strip_prefix("=\"")?
+            .strip_suffix('"')?;
+        let value = percent_encoding::percent_decode_str(value).decode_utf8_lossy();
+        Some(value.into_owned())
+    })
+}
+
+/// Fail with `Error::BodyTooLarge` when `size` bytes are over `limit`
+#[cfg(feature = "client")]
+pub(crate) fn check_size(limit: Option<usize>, size: u64) -> Result<()> {
+    match limit {
+        Some(limit) if size > limit as u64 => Err(Error::BodyTooLarge { limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Read the whole of `reader`, up to `limit` bytes
+#[cfg(any(feature = "blocking", feature = "ureq"))]
+fn read_limited<R: std::io::Read>(mut reader: R, limit: Option<usize>) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut body = Vec::new();
+    match limit {
+        Some(limit) => {
+            let _ = reader.take(limit as u64 + 1).read_to_end(&mut body)?;
+            check_size(Some(limit), body.len() as u64)?;
+        }
+        None => {
+            let _ = reader.read_to_end(&mut body)?;
+        }
+    }
+    Ok(body)
+}
+
+/// A response read by a transport.
+#[derive(Clone, Debug)]
+pub struct Response {
+    /// The URL of the response, after any redirect
+    pub url: String,
+    /// HTTP status
+    pub status: StatusCode,
+    /// Headers
+    pub headers: HeaderMap,
+    /// The whole body
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The rate limit headers of the response
+    pub fn rate_limit(&self) -> RateLimit {
+        RateLimit::from_headers(&self.headers)
+    }
+}
+
+/// An HTTP client able to execute signed requests.
+///
+/// On `wasm32`, the returned future does not need to be `Send`.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::transport::{HttpTransport, Response, SignedRequest};
+///
+/// struct Unavailable;
+///
+/// #[async_trait::async_trait]
+/// impl HttpTransport for Unavailable {
+///     async fn execute(&self, request: SignedRequest) -> oauth_client::Result<Response> {
+///         Ok(Response {
+///             url: request.url,
+///             status: http::StatusCode::SERVICE_UNAVAILABLE,
+///             headers: http::HeaderMap::new(),
+///             body: Vec::new(),
+///         })
+///     }
+/// }
+///
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).transport(Unavailable);
+/// ```
+#[cfg(feature = "client")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+pub trait HttpTransport: Send + Sync {
+    /// Send `request` and read the whole response
+    async fn execute(&self, request: SignedRequest) -> Result<Response>;
+}
+
+#[cfg(feature = "client")]
+impl fmt::Debug for dyn HttpTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("HttpTransport")
+    }
+}
+
+#[cfg(feature = "client")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<T: HttpTransport + ?Sized> HttpTransport for std::sync::Arc<T> {
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        (**self).execute(request).await
+    }
+}
+
+/// A blocking HTTP client able to execute signed requests, used by the
+/// [`blocking`](../blocking/index.html) module.
+#[cfg(feature = "client")]
+pub trait BlockingTransport: Send + Sync {
+    /// Send `request` and read the whole response
+    fn execute(&self, request: SignedRequest) -> Result<Response>;
+}
+
+#[cfg(feature = "client")]
+impl fmt::Debug for dyn BlockingTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BlockingTransport")
+    }
+}
+
+/// HTTP version used by the reqwest transports.
+#[cfg(feature = "reqwest")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum HttpVersion {
+    /// HTTP/1.1, or HTTP/2 when negotiated by TLS
+    #[default]
+    Auto,
+    /// HTTP/1.1 only, for gateways misbehaving on HTTP/2
+    Http1Only,
+    /// HTTP/2 only, without negotiation
+    Http2PriorKnowledge,
+}
+
+/// Settings of the clients created by the reqwest transports.
+///
+/// # Examples
+///
+/// ```
+/// use oauth_client::transport::{ClientOptions, ReqwestTransport};
+///
+/// let options = ClientOptions::new()
+///     .resolve("api.example.com", ([10, 0, 0, 2], 443).into())
+///     .pool_max_idle_per_host(32)
+///     .pool_idle_timeout(Some(std::time::Duration::from_secs(30)));
+/// let transport = ReqwestTransport::with_options(&options).unwrap();
+/// ```
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+#[derive(Clone, Debug, Default)]
+pub struct ClientOptions {
+    version: HttpVersion,
+    resolve: Vec<(String, SocketAddr)>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Option<Duration>>,
+    no_redirect: bool,
+}
+
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+impl ClientOptions {
+    /// Create new options, with the defaults of reqwest
+    pub fn new() -> ClientOptions {
+        ClientOptions::default()
+    }
+
+    /// Set the HTTP version
+    pub fn http_version(mut self, version: HttpVersion) -> ClientOptions {
+        self.version = version;
+        self
+    }
+
+    /// Connect to `addr` for the host `domain`, instead of resolving it. The
+    /// port of `addr` is ignored, the port of the URL is used.
+    pub fn resolve<D: Into<String>>(mut self, domain: D, addr: SocketAddr) -> ClientOptions {
+        self.resolve.push((domain.into(), addr));
+        self
+    }
+
+    /// Set the maximum number of idle connections kept per host, unlimited by
+    /// default. reqwest has no limit on the total number of connections.
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> ClientOptions {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long idle connections are kept, 90 seconds by default. `None`
+    /// keeps them forever.
+    pub fn pool_idle_timeout(mut self, timeout: Option<Duration>) -> ClientOptions {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Follow redirects, up to 10 by default. When disabled, redirects are
+    /// returned as `Error::Redirected`.
+    pub fn follow_redirects(mut self, follow: bool) -> ClientOptions {
+        self.no_redirect = !follow;
+        self
+    }
+
+    fn client(&self) -> Result<reqwest::Client> {
+        let mut builder = reqwest::Client::builder();
+        builder = match self.version {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1Only => builder.http1_only(),
+            HttpVersion::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+        };
+        for (domain, addr) in &self.resolve {
+            builder = builder.resolve(domain, *addr);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if self.no_redirect {
+            builder = builder.redirect(reqwest::redirect::Policy::none());
+        }
+        Ok(builder.build()?)
+    }
+
+    #[cfg(feature = "blocking")]
+    fn blocking_client(&self) -> Result<reqwest::blocking::Client> {
+        let mut builder = reqwest::blocking::Client::builder();
+        builder = match self.version {
+            HttpVersion::Auto => builder,
+            HttpVersion::Http1Only => builder.http1_only(),
+            HttpVersion::Http2PriorKnowledge => builder.http2_prior_knowledge(),
+        };
+        for (domain, addr) in &self.resolve {
+            builder = builder.resolve(domain, *addr);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if self.no_redirect {
+            builder = builder.redirect(reqwest::redirect::Policy::none());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Transport based on a `reqwest::Client`.
+#[cfg(feature = "reqwest")]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestTransport {
+    /// Create new transport sending requests with `client`
+    pub fn new(client: reqwest::Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+
+    /// Create new transport with a client using `version`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use oauth_client::transport::{HttpVersion, ReqwestTransport};
+    ///
+    /// let transport = ReqwestTransport::with_version(HttpVersion::Http1Only).unwrap();
+    /// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+    /// let session = oauth_client::Session::new(consumer, None).transport(transport);
+    /// ```
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_version(version: HttpVersion) -> Result<ReqwestTransport> {
+        ReqwestTransport::with_options(&ClientOptions::new().http_version(version))
+    }
+
+    /// Create new transport with a client using `options`
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_options(options: &ClientOptions) -> Result<ReqwestTransport> {
+        Ok(ReqwestTransport::new(options.client()?))
+    }
+}
+
+#[cfg(feature = "reqwest")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl HttpTransport for ReqwestTransport {
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        let mut builder = self
+            .client
+            .request(request.method, &request.url)
+            .headers(request.headers);
+        if !request.body.is_empty() {
+            builder = builder.body(request.body);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let limit = request.max_response_size;
+        let response = builder.send().await?;
+        check_size(limit, response.content_length().unwrap_or(0))?;
+        Ok(Response {
+            url: response.url().to_string(),
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: read_reqwest_body(response, limit).await?,
+        })
+    }
+}
+
+/// Read the body of `response`, up to `limit` bytes
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+pub(crate) async fn read_reqwest_body(
+    mut response: reqwest::Response,
+    limit: Option<usize>,
+) -> Result<Vec<u8>> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        body.extend_from_slice(&chunk);
+        check_size(limit, body.len() as u64)?;
+    }
+    Ok(body)
+}
+
+/// The browser `fetch` reads the body at once.
+#[cfg(all(feature = "reqwest", target_arch = "wasm32"))]
+pub(crate) async fn read_reqwest_body(
+    response: reqwest::Response,
+    limit: Option<usize>,
+) -> Result<Vec<u8>> {
+    let body = response.bytes().await?.to_vec();
+    check_size(limit, body.len() as u64)?;
+    Ok(body)
+}
+
+/// Transport based on a `hyper::Client`, for applications already holding a
+/// client or a connector.
+///
+/// # Examples
+///
+/// ```
+/// let transport = oauth_client::transport::HyperTransport::new(hyper::Client::new());
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).transport(transport);
+/// ```
+#[cfg(feature = "hyper")]
+#[derive(Clone, Debug)]
+pub struct HyperTransport<C = hyper::client::HttpConnector> {
+    client: hyper::Client<C>,
+}
+
+#[cfg(feature = "hyper")]
+impl<C> HyperTransport<C> {
+    /// Create new transport sending requests with `client`
+    pub fn new(client: hyper::Client<C>) -> HyperTransport<C> {
+        HyperTransport { client }
+    }
+}
+
+#[cfg(feature = "hyper")]
+impl Default for HyperTransport {
+    fn default() -> HyperTransport {
+        HyperTransport::new(hyper::Client::new())
+    }
+}
+
+#[cfg(feature = "hyper")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl<C> HttpTransport for HyperTransport<C>
+where
+    C: hyper::client::connect::Connect + Clone + Send + Sync + 'static,
+{
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        let url = request.url.clone();
+        let mut builder = http::Request::builder()
+            .method(request.method)
+            .uri(request.url);
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(request.headers);
+        }
+        let limit = request.max_response_size;
+        let request = builder
+            .body(hyper::Body::from(request.body))
+            .map_err(Error::http)?;
+        let response = self.client.request(request).await?;
+        let (parts, mut stream) = response.into_parts();
+        let mut body = Vec::new();
+        while let Some(chunk) = hyper::body::HttpBody::data(&mut stream).await {
+            body.extend_from_slice(&chunk?);
+            check_size(limit, body.len() as u64)?;
+        }
+        Ok(Response {
+            url,
+            status: parts.status,
+            headers: parts.headers,
+            body,
+        })
+    }
+}
+
+/// Blocking transport based on a `reqwest::blocking::Client`.
+#[cfg(feature = "blocking")]
+#[derive(Clone, Debug, Default)]
+pub struct ReqwestBlockingTransport {
+    client: reqwest::blocking::Client,
+}
+
+#[cfg(feature = "blocking")]
+impl ReqwestBlockingTransport {
+    /// Create new transport sending requests with `client`
+    pub fn new(client: reqwest::blocking::Client) -> ReqwestBlockingTransport {
+        ReqwestBlockingTransport { client }
+    }
+
+    /// Create new transport with a client using `version`
+    pub fn with_version(version: HttpVersion) -> Result<ReqwestBlockingTransport> {
+        ReqwestBlockingTransport::with_options(&ClientOptions::new().http_version(version))
+    }
+
+    /// Create new transport with a client using `options`
+    pub fn with_options(options: &ClientOptions) -> Result<ReqwestBlockingTransport> {
+        Ok(ReqwestBlockingTransport::new(options.blocking_client()?))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl BlockingTransport for ReqwestBlockingTransport {
+    fn execute(&self, request: SignedRequest) -> Result<Response> {
+        let mut builder = self
+            .client
+            .request(request.method, &request.url)
+            .headers(request.headers);
+        if !request.body.is_empty() {
+            builder = builder.body(request.body);
+        }
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        let limit = request.max_response_size;
+        let response = builder.send()?;
+        check_size(limit, response.content_length().unwrap_or(0))?;
+        Ok(Response {
+            url: response.url().to_string(),
+            status: response.status(),
+            headers: response.headers().clone(),
+            body: read_limited(response, limit)?,
+        })
+    }
+}
+
+/// Blocking transport based on a `ureq::Agent`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use oauth_client::transport::UreqTransport;
+///
+/// let transport = UreqTransport::new(ureq::agent());
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let bytes = oauth_client::blocking::get_with_transport(
+///     &transport,
+///     "http://oauthbin.com/v1/request-token",
+///     &consumer,
+///     None,
+///     None,
+/// )
+/// .unwrap();
+/// ```
+#[cfg(feature = "ureq")]
+#[derive(Clone, Debug)]
+pub struct UreqTransport {
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "ureq")]
+impl UreqTransport {
+    /// Create new transport sending requests with `agent`
+    pub fn new(agent: ureq::Agent) -> UreqTransport {
+        UreqTransport { agent }
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl Default for UreqTransport {
+    fn default() -> UreqTransport {
+        UreqTransport::new(ureq::agent())
+    }
+}
+
+#[cfg(feature = "ureq")]
+impl BlockingTransport for UreqTransport {
+    fn execute(&self, request: SignedRequest) -> Result<Response> {
+        let mut builder = self.agent.request(request.method.as_str(), &request.url);
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        for (name, value) in &request.headers {
+            builder = builder.set(name.as_str(), value.to_str().map_err(Error::http)?);
+        }
+        let result = if request.body.is_empty() {
+            builder.call()
+        } else {
+            builder.send_bytes(&request.body)
+        };
+        // Error statuses are reported by `check_status`, with the body.
+        let response = match result {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+            Err(err) => return Err(err.into()),
+        };
+
+        let url = response.get_url().to_string();
+        let status = StatusCode::from_u16(response.status()).map_err(Error::http)?;
+        let mut headers = HeaderMap::new();
+        for name in response.headers_names() {
+            if let Some(value) = response.header(&name) {
+                let _ = headers.append(
+                    http::header::HeaderName::from_bytes(name.as_bytes()).map_err(Error::http)?,
+                    http::HeaderValue::from_str(value).map_err(Error::http)?,
+                );
+            }
+        }
+        let body = read_limited(response.into_reader(), request.max_response_size)?;
+        Ok(Response {
+            url,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Transport based on a `surf::Client`, for applications running on
+/// async-std.
+///
+/// # Examples
+///
+/// ```
+/// let transport = oauth_client::transport::SurfTransport::new(surf::client());
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).transport(transport);
+/// ```
+#[cfg(feature = "surf")]
+#[derive(Clone, Debug)]
+pub struct SurfTransport {
+    client: surf::Client,
+}
+
+#[cfg(feature = "surf")]
+impl SurfTransport {
+    /// Create new transport sending requests with `client`
+    pub fn new(client: surf::Client) -> SurfTransport {
+        SurfTransport { client }
+    }
+}
+
+#[cfg(feature = "surf")]
+impl Default for SurfTransport {
+    fn default() -> SurfTransport {
+        SurfTransport::new(surf::client())
+    }
+}
+
+#[cfg(feature = "surf")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl HttpTransport for SurfTransport {
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        // `surf::Error` does not implement `std::error::Error`.
+        // Its message may include the URL, which may carry the signature.
+        let surf_err = |err: surf::Error| Error::Http(crate::error::redacted(err));
+
+        let url = request.url.clone();
+        let limit = request.max_response_size;
+        let method = request.method.as_str().parse().map_err(surf_err)?;
+        let mut surf_request =
+            surf::Request::new(method, surf::Url::parse(&request.url).map_err(Error::http)?);
+        for (name, value) in &request.headers {
+            surf_request.append_header(name.as_str(), value.to_str().map_err(Error::http)?);
+        }
+        if !request.body.is_empty() {
+            surf_request.set_body(request.body);
+        }
+
+        let mut response = self.client.send(surf_request).await.map_err(surf_err)?;
+        // surf reads the body at once: only a declared length stops it early.
+        check_size(limit, response.len().unwrap_or(0) as u64)?;
+        let status = StatusCode::from_u16(response.status().into()).map_err(Error::http)?;
+        let mut headers = HeaderMap::new();
+        for (name, values) in response.iter() {
+            for value in values {
+                let _ = headers.append(
+                    http::header::HeaderName::from_bytes(name.as_str().as_bytes())
+                        .map_err(Error::http)?,
+                    http::HeaderValue::from_str(value.as_str()).map_err(Error::http)?,
+                );
+            }
+        }
+        let body = response.body_bytes().await.map_err(surf_err)?;
+        check_size(limit, body.len() as u64)?;
+        Ok(Response {
+            url,
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+/// Transport based on an `isahc::HttpClient`, sending the requests with
+/// libcurl.
+///
+/// # Examples
+///
+/// ```
+/// let client = isahc::HttpClient::new().unwrap();
+/// let transport = oauth_client::transport::IsahcTransport::new(client);
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let session = oauth_client::Session::new(consumer, None).transport(transport);
+/// ```
+#[cfg(feature = "isahc")]
+#[derive(Clone, Debug)]
+pub struct IsahcTransport {
+    client: isahc::HttpClient,
+}
+
+#[cfg(feature = "isahc")]
+impl IsahcTransport {
+    /// Create new transport sending requests with `client`
+    pub fn new(client: isahc::HttpClient) -> IsahcTransport {
+        IsahcTransport { client }
+    }
+}
+
+#[cfg(feature = "isahc")]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+impl HttpTransport for IsahcTransport {
+    async fn execute(&self, request: SignedRequest) -> Result<Response> {
+        use isahc::config::Configurable;
+        use isahc::{AsyncReadResponseExt, ResponseExt};
+
+        let url = request.url.clone();
+        let mut builder = http::Request::builder()
+            .method(request.method)
+            .uri(request.url);
+        if let Some(timeout) = request.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(headers) = builder.headers_mut() {
+            headers.extend(request.headers);
+        }
+        let limit = request.max_response_size;
+        let request = builder.body(request.body).map_err(Error::http)?;
+        let mut response = self.client.send_async(request).await?;
+        // isahc reads the body at once: only a declared length stops it early.
+        check_size(limit, response.body().len().unwrap_or(0))?;
+        let body = response.bytes().await?;
+        check_size(limit, body.len() as u64)?;
+        Ok(Response {
+            url: response.effective_uri().map_or(url, |uri| uri.to_string()),
+            status: response.status(),
+            headers: response.headers().clone(),
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SignedRequest;
+    use http::header::{HeaderValue, AUTHORIZATION};
+    use http::Method;
+
+    #[test]
+    fn redact_params() {
+        let redacted = super::redact_params(
+            "oauth_token=t&oauth_verifier=v&oauth_token_secret=s&xoauth_verifier=x",
+        );
+        assert_eq!(
+            redacted,
+            "oauth_token=REDACTED&oauth_verifier=REDACTED&oauth_token_secret=REDACTED\
+             &xoauth_verifier=x"
+        );
+        let redacted = super::redact_params(r#"OAuth oauth_verifier="v", oauth_signature="s""#);
+        assert_eq!(
+            redacted,
+            r#"OAuth oauth_verifier="REDACTED", oauth_signature="REDACTED""#
+        );
+    }
+
+    #[test]
+    fn to_curl() {
+        let mut request = SignedRequest::new(Method::POST, "http://example.com/a?b=it's".into());
+        let _ = request.headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_static(r#"OAuth oauth_token="t", oauth_signature="s%3D""#),
+        );
+        request.body = b"status=hi&oauth_token=t&xoauth_token=x".to_vec();
+
+        assert_eq!(
+            request.to_curl(false),
+            "curl -X POST 'http://example.com/a?b=it'\\''s' \
+             -H 'authorization: OAuth oauth_token=\"t\", oauth_signature=\"s%3D\"' \
+             --data-raw 'status=hi&oauth_token=t&xoauth_token=x'"
+        );
+        assert_eq!(
+            request.to_curl(true),
+            "curl -X POST 'http://example.com/a?b=it'\\''s' \
+             -H 'authorization: OAuth oauth_token=\"REDACTED\", oauth_signature=\"REDACTED\"' \
+             --data-raw 'status=hi&oauth_token=REDACTED&xoauth_token=x'"
+        );
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "reqwest")]
+    async fn resolve() {
+        use super::{ClientOptions, HttpTransport, ReqwestTransport};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 1024]).await.unwrap();
+            let response = "HTTP/1.1 200 OK\r\ncontent-length: 2\r\n\r\nok";
+            socket.write_all(response.as_bytes()).await.unwrap();
+        });
+
+        let options = ClientOptions::new().resolve("api.example.test", addr);
+        let transport = ReqwestTransport::with_options(&options).unwrap();
+        let url = format!("http://api.example.test:{}/", addr.port());
+        let response = transport
+            .execute(SignedRequest::new(Method::GET, url))
+            .await
+            .unwrap();
+        assert_eq!(response.body, b"ok");
+        server.await.unwrap();
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "reqwest")]
+    async fn body_too_large() {
+        use super::{HttpTransport, ReqwestTransport};
+        use crate::Error;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let _ = socket.read(&mut [0; 1024]).await.unwrap();
+            let head = "HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n";
+            socket.write_all(head.as_bytes()).await.unwrap();
+            for _ in 0..4 {
+                let _ = socket.write_all(b"8\r\n01234567\r\n").await;
+            }
+            let _ = socket.write_all(b"0\r\n\r\n").await;
+        });
+
+        let mut request = SignedRequest::new(Method::GET, format!("http://{}/", addr));
+        request.max_response_size = Some(20);
+        match ReqwestTransport::default().execute(request).await {
+            Err(Error::BodyTooLarge { limit }) => assert_eq!(limit, 20),
+            rsp => panic!("unexpected {:?}", rsp),
+        }
+        server.await.unwrap();
+    }
+}
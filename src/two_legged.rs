@@ -0,0 +1,184 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Two-legged OAuth, signing with the consumer credentials only.
+
+#[cfg(feature = "client")]
+use crate::HttpTransport;
+use crate::{AuthorizationHeader, ConsumerToken, ParamList, QuirkProfile, Result, Session};
+
+/// Client for two-legged OAuth.
+///
+/// Requests are signed with the consumer credentials only: no `oauth_token`
+/// is sent and the signing key is `consumer_secret&`. Use it for APIs that
+/// don't involve a user; APIs acting on behalf of a user require an access
+/// token and must use [`get`](fn.get.html) / [`post`](fn.post.html) with
+/// `Some(token)` instead.
+///
+/// There is no runtime check behind this: the guarantee comes from the
+/// client building its own session from the consumer alone, with no way to
+/// add a token to it.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "client")]
+/// async {
+///     let client = oauth_client::TwoLegged::new(oauth_client::ConsumerToken::new("key", "secret"));
+///     let bytes = client.get("http://oauthbin.com/v1/echo", None).await.unwrap();
+/// };
+/// ```
+#[derive(Clone, Debug)]
+pub struct TwoLegged {
+    session: Session,
+}
+
+impl TwoLegged {
+    /// Create new client for `consumer`, copying its key and secret
+    pub fn new(consumer: ConsumerToken<'_>) -> TwoLegged {
+        TwoLegged {
+            session: Session::new(consumer, None),
+        }
+    }
+
+    /// Set the `realm` sent first in the `Authorization` header.
+    /// See [`Session::realm`](struct.Session.html#method.realm).
+    pub fn realm<R: Into<String>>(self, realm: R) -> TwoLegged {
+        TwoLegged {
+            session: self.session.realm(realm),
+        }
+    }
+
+    /// Follow the deviations from the specification in `quirks`.
+    /// See [`Session::quirks`](struct.Session.html#method.quirks).
+    pub fn quirks(self, quirks: QuirkProfile) -> TwoLegged {
+        TwoLegged {
+            session: self.session.quirks(quirks),
+        }
+    }
+
+    /// Send the requests with `transport` instead of the default reqwest client.
+    /// See [`Session::transport`](struct.Session.html#method.transport).
+    #[cfg(feature = "client")]
+    pub fn transport<T: HttpTransport + 'static>(self, transport: T) -> TwoLegged {
+        TwoLegged {
+            session: self.session.transport(transport),
+        }
+    }
+
+    /// The consumer token requests are signed with
+    pub fn consumer(&self) -> &ConsumerToken<'static> {
+        self.session.consumer()
+    }
+
+    /// Create an authorization header.
+    /// See [`authorization_header`](fn.authorization_header.html).
+    pub fn authorization_header(
+        &self,
+        method: &str,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList>,
+    ) -> Result<AuthorizationHeader> {
+        let uri = uri.as_ref();
+        self.session.authorization_header(method, uri, other_param)
+    }
+
+    /// Send authorized GET request to the specified URL
+    #[cfg(feature = "client")]
+    pub async fn get(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        self.session.get(uri, other_param).await
+    }
+
+    /// Send authorized POST request to the specified URL
+    #[cfg(feature = "client")]
+    pub async fn post(
+        &self,
+        uri: impl AsRef<str>,
+        other_param: Option<&ParamList<'_>>,
+    ) -> Result<Vec<u8>> {
+        let uri = uri.as_ref();
+        self.session.post(uri, other_param).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TwoLegged;
+    use crate::ConsumerToken;
+
+    #[test]
+    fn no_token() {
+        let client = TwoLegged::new(ConsumerToken::new("key", "secret"));
+        let header = client
+            .authorization_header("GET", "http://example.com", None)
+            .unwrap()
+            .into_header();
+        assert!(header.contains("oauth_consumer_key=\"key\""));
+        assert!(!header.contains("oauth_token"));
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "client")]
+    async fn signing_key() {
+        use crate::transport::{authorization_param, HttpTransport, Response, SignedRequest};
+        use crate::{ParamList, Result};
+        use std::sync::{Arc, Mutex};
+
+        /// Record of the requests sent
+        #[derive(Clone, Default)]
+        struct Record(Arc<Mutex<Vec<SignedRequest>>>);
+
+        #[async_trait::async_trait]
+        impl HttpTransport for Record {
+            async fn execute(&self, request: SignedRequest) -> Result<Response> {
+                let url = request.url.clone();
+                self.0.lock().unwrap().push(request);
+                Ok(Response {
+                    url,
+                    status: http::StatusCode::OK,
+                    headers: http::HeaderMap::new(),
+                    body: Vec::new(),
+                })
+            }
+        }
+
+        let record = Record::default();
+        let client = TwoLegged::new(ConsumerToken::new("key", "s&cret")).transport(record.clone());
+        client.get("http://example.com/a?b=1", None).await.unwrap();
+
+        let requests = record.0.lock().unwrap();
+        let request = &requests[0];
+        assert!(!request.url.contains("oauth_token"));
+        assert_eq!(authorization_param(&request.headers, "oauth_token"), None);
+        let mut param = ParamList::new();
+        let _ = param.insert("b".into(), "1".into());
+        for name in &[
+            "oauth_consumer_key",
+            "oauth_nonce",
+            "oauth_signature_method",
+            "oauth_timestamp",
+            "oauth_version",
+        ] {
+            if let Some(value) = authorization_param(&request.headers, name) {
+                let _ = param.insert((*name).into(), value.into());
+            }
+        }
+        let base = crate::signature_base_string("GET", "http://example.com/a", &param);
+        let key = "s%26cret&";
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key.as_bytes());
+        let signature = base64::encode(ring::hmac::sign(&key, base.as_bytes()));
+        assert_eq!(
+            authorization_param(&request.headers, "oauth_signature"),
+            Some(signature)
+        );
+    }
+}
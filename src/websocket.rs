@@ -0,0 +1,72 @@
+// Copyright 2016 oauth-client-rs Developers
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Signed WebSocket handshakes.
+
+use crate::core::{parse_url, request_header, sign_headers};
+use crate::{AccessToken, ConsumerToken, Error, Result};
+use http::{HeaderMap, Method};
+
+/// Create the headers authorizing the WebSocket handshake to `url`, a
+/// `ws://` or `wss://` URL, to add to the upgrade request of any WebSocket
+/// client.
+///
+/// The handshake is signed as a GET request to the corresponding `http://`
+/// or `https://` URL, with its query parameters.
+///
+/// # Examples
+///
+/// ```
+/// let consumer = oauth_client::ConsumerToken::new("key", "secret");
+/// let access = oauth_client::AccessToken::new("token", "secret");
+/// let headers =
+///     oauth_client::websocket_headers("wss://stream.example.com/1/feed?track=rust", &consumer, Some(&access))
+///         .unwrap();
+/// assert!(headers.contains_key(http::header::AUTHORIZATION));
+/// ```
+pub fn websocket_headers(
+    url: impl AsRef<str>,
+    consumer: &ConsumerToken<'_>,
+    token: Option<&AccessToken<'_>>,
+) -> Result<HeaderMap> {
+    let url = url.as_ref();
+    let token = token.map(|t| &**t);
+    let mut url = url::Url::parse(url).map_err(|err| Error::invalid_url(url, err))?;
+    let scheme = match url.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        scheme => scheme,
+    }
+    .to_string();
+    // Both schemes are special, so the change cannot fail.
+    let _ = url.set_scheme(&scheme);
+    let url = parse_url(url.as_str())?;
+
+    let header = request_header(&Method::GET, &url, &HeaderMap::new(), None, consumer, token);
+    let mut headers = HeaderMap::new();
+    sign_headers(&mut headers, header)?;
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::websocket_headers;
+    use crate::{AccessToken, ConsumerToken};
+    use http::header::AUTHORIZATION;
+
+    #[test]
+    fn websocket() {
+        let consumer = ConsumerToken::new("key", "secret");
+        let access = AccessToken::new("token", "secret");
+        let headers =
+            websocket_headers("wss://example.com/feed", &consumer, Some(&access)).unwrap();
+        let header = headers[AUTHORIZATION].to_str().unwrap();
+        assert!(header.starts_with("OAuth "));
+        assert!(header.contains("oauth_token=\"token\""));
+        assert!(websocket_headers("/feed", &consumer, None).is_err());
+    }
+}